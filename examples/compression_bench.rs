@@ -0,0 +1,55 @@
+//! 比较 zlib 后端的导出和还原耗时
+//!
+//! ```bash
+//! cargo run --release --example compression_bench -- ./world 5
+//! cargo run --release --example compression_bench --features libdeflate -- ./world 5
+//! ```
+
+use anyhow::{Context, Result};
+use mcj::{export_world_to_memory, restore_world_from_memory, Config};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let world = PathBuf::from(
+        args.next()
+            .context("用法: compression_bench <世界目录> [次数]")?,
+    );
+    let iterations: u32 = match args.next() {
+        Some(n) => n.parse().context("次数必须是正整数")?,
+        None => 5,
+    };
+    anyhow::ensure!(iterations > 0, "次数必须是正整数");
+
+    let backend = if cfg!(feature = "libdeflate") {
+        "libdeflate"
+    } else {
+        "flate2 (miniz_oxide)"
+    };
+    println!("zlib 后端: {}", backend);
+
+    let config = Config::default();
+    let output = std::env::temp_dir().join(format!("mcj_bench_{}", std::process::id()));
+    let mut export_time = Duration::ZERO;
+    let mut restore_time = Duration::ZERO;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let files = export_world_to_memory(&world, true, false, &config)?;
+        export_time += start.elapsed();
+
+        let _ = std::fs::remove_dir_all(&output);
+        let start = Instant::now();
+        restore_world_from_memory(&files, &output, true, &config)?;
+        restore_time += start.elapsed();
+    }
+    let _ = std::fs::remove_dir_all(&output);
+
+    println!(
+        "导出: 平均 {:.3}s，还原: 平均 {:.3}s（{} 次）",
+        export_time.as_secs_f64() / iterations as f64,
+        restore_time.as_secs_f64() / iterations as f64,
+        iterations
+    );
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! 世界分析 - 不解压区块，按区域文件名和 level.dat 快速了解世界的范围
+
+use crate::config::ChunkBounds;
+use crate::export::{read_gzip_nbt, region_files};
+use crate::mca::{parse_region_filename, read_region_header};
+use anyhow::Result;
+use fastnbt::Value;
+use log::warn;
+use std::path::Path;
+
+/// 世界范围（区块坐标），只统计主世界的 `region/`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorldBounds {
+    /// 最小区块 X（最西侧 region 的起点）
+    pub min_cx: i32,
+    /// 最大区块 X（最东侧 region 的终点）
+    pub max_cx: i32,
+    /// 最小区块 Z
+    pub min_cz: i32,
+    /// 最大区块 Z
+    pub max_cz: i32,
+    /// 出生点方块 X，level.dat 不存在或无法读取时为 `None`
+    pub spawn_x: Option<i32>,
+    /// 出生点方块 Z
+    pub spawn_z: Option<i32>,
+    /// region 文件数量（不包括文件头无法读取的文件），为 0 时区块范围没有意义
+    pub region_count: usize,
+    /// 按各 region 文件头统计的区块数量（包括未完整生成和数据损坏的区块）
+    pub approximate_chunk_count: usize,
+}
+
+impl WorldBounds {
+    /// 作为区块范围（可用于 `bounds` 过滤），没有 region 文件时返回 `None`
+    pub fn chunk_bounds(&self) -> Option<ChunkBounds> {
+        (self.region_count > 0)
+            .then(|| ChunkBounds::new(self.min_cx, self.min_cz, self.max_cx, self.max_cz))
+    }
+
+    /// 出生点所在的区块坐标
+    pub fn spawn_chunk(&self) -> Option<(i32, i32)> {
+        Some((self.spawn_x?.div_euclid(16), self.spawn_z?.div_euclid(16)))
+    }
+}
+
+/// 检测世界的范围：按主世界 `region/` 中的区域文件名计算区块范围（按整个 region 对齐），
+/// 只读取各文件头统计区块数量，并从 level.dat 读取出生点；文件头无法读取的区域文件输出警告后跳过
+pub fn detect_world_bounds(world_path: &Path) -> Result<WorldBounds> {
+    let mut bounds = WorldBounds::default();
+
+    let region_path = world_path.join("region");
+    if region_path.is_dir() {
+        let mut range: Option<(i32, i32, i32, i32)> = None;
+        for path in region_files(&region_path)? {
+            let Some((rx, rz, _)) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(parse_region_filename)
+            else {
+                continue;
+            };
+            let header = match read_region_header(&path) {
+                Ok(header) => header,
+                Err(e) => {
+                    warn!("跳过无法读取文件头的区域文件 {}: {:#}", path.display(), e);
+                    continue;
+                }
+            };
+            range = Some(match range {
+                Some((min_rx, max_rx, min_rz, max_rz)) => (
+                    min_rx.min(rx),
+                    max_rx.max(rx),
+                    min_rz.min(rz),
+                    max_rz.max(rz),
+                ),
+                None => (rx, rx, rz, rz),
+            });
+            bounds.region_count += 1;
+            bounds.approximate_chunk_count += header.chunk_count();
+        }
+        if let Some((min_rx, max_rx, min_rz, max_rz)) = range {
+            bounds.min_cx = min_rx * 32;
+            bounds.max_cx = (max_rx + 1) * 32 - 1;
+            bounds.min_cz = min_rz * 32;
+            bounds.max_cz = (max_rz + 1) * 32 - 1;
+        }
+    }
+
+    let level_dat = world_path.join("level.dat");
+    if level_dat.is_file() {
+        if let Some((x, z)) = read_gzip_nbt(&level_dat)
+            .ok()
+            .as_ref()
+            .and_then(spawn_point)
+        {
+            bounds.spawn_x = Some(x);
+            bounds.spawn_z = Some(z);
+        }
+    }
+
+    Ok(bounds)
+}
+
+/// level.dat 中的出生点方块坐标：`Data.SpawnX`/`SpawnZ`，或 1.21.9 之后的 `Data.spawn.pos`
+fn spawn_point(level: &Value) -> Option<(i32, i32)> {
+    let Value::Compound(root) = level else {
+        return None;
+    };
+    let Some(Value::Compound(data)) = root.get("Data") else {
+        return None;
+    };
+    if let (Some(Value::Int(x)), Some(Value::Int(z))) = (data.get("SpawnX"), data.get("SpawnZ")) {
+        return Some((*x, *z));
+    }
+    match data.get("spawn") {
+        Some(Value::Compound(spawn)) => match spawn.get("pos") {
+            Some(Value::IntArray(pos)) if pos.len() == 3 => Some((pos[0], pos[2])),
+            _ => None,
+        },
+        _ => None,
+    }
+}
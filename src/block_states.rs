@@ -0,0 +1,664 @@
+//! 可读的压缩数组 - 将 block_states、biomes 和 Heightmaps 中压缩的 LongArray 展开为整数数组
+//!
+//! 每个索引占 `ceil(log2(调色板长度))` 位（block_states 至少 4 位），
+//! 按低位优先依次放入 long 中，索引不跨越 long 边界；
+//! section 中有 4096 个方块和 64 个生物群系单元，高度图每列一个高度（共 256 列）
+
+use anyhow::{Context, Result};
+use fastnbt::{LongArray, Value};
+use std::collections::HashMap;
+
+/// 调色板容器的压缩布局
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    /// section 中的字段名
+    key: &'static str,
+    /// 索引数量
+    entries: usize,
+    /// 最小索引位数
+    min_bits: usize,
+}
+
+/// block_states：16 × 16 × 16 个方块，至少 4 位
+const BLOCK_STATES: Layout = Layout {
+    key: "block_states",
+    entries: 4096,
+    min_bits: 4,
+};
+
+/// biomes：4 × 4 × 4 个单元，调色板只有一项时为 0 位（没有 data）
+const BIOMES: Layout = Layout {
+    key: "biomes",
+    entries: 64,
+    min_bits: 0,
+};
+
+/// 调色板长度对应的索引位数
+fn bits_per_entry(palette_len: usize, layout: Layout) -> usize {
+    let needed = usize::BITS - palette_len.saturating_sub(1).leading_zeros();
+    (needed as usize).max(layout.min_bits)
+}
+
+/// 解包 `data`，长度与调色板不匹配或不需要 data 时返回 `None`
+fn unpack(data: &[i64], palette_len: usize, layout: Layout) -> Option<Vec<u32>> {
+    let bits = bits_per_entry(palette_len, layout);
+    if bits == 0 {
+        return None;
+    }
+    unpack_bits(data, bits, layout.entries)
+}
+
+/// 按调色板长度重新打包索引，不需要 data 时（0 位）返回 `None`
+fn pack(indices: &[u32], palette_len: usize, layout: Layout) -> Result<Option<Vec<i64>>> {
+    if indices.len() != layout.entries {
+        anyhow::bail!(
+            "{} 应有 {} 个索引，实际为 {} 个",
+            layout.key,
+            layout.entries,
+            indices.len()
+        );
+    }
+    if let Some(&index) = indices.iter().find(|&&i| i as usize >= palette_len) {
+        anyhow::bail!(
+            "{} 索引 {} 超出调色板长度 {}",
+            layout.key,
+            index,
+            palette_len
+        );
+    }
+
+    let bits = bits_per_entry(palette_len, layout);
+    if bits == 0 {
+        return Ok(None);
+    }
+    Ok(Some(pack_bits(indices, bits)))
+}
+
+/// 按固定位数解包 `entries` 个值，`data` 长度不匹配时返回 `None`
+fn unpack_bits(data: &[i64], bits: usize, entries: usize) -> Option<Vec<u32>> {
+    let per_long = 64 / bits;
+    if data.len() != entries.div_ceil(per_long) {
+        return None;
+    }
+
+    let mask = (1u64 << bits) - 1;
+    let values = (0..entries)
+        .map(|i| ((data[i / per_long] as u64 >> ((i % per_long) * bits)) & mask) as u32)
+        .collect();
+    Some(values)
+}
+
+/// 按固定位数打包（调用方保证每个值都能用 `bits` 位表示）
+fn pack_bits(values: &[u32], bits: usize) -> Vec<i64> {
+    let per_long = 64 / bits;
+    values
+        .chunks(per_long)
+        .map(|entries| {
+            entries.iter().enumerate().fold(0u64, |long, (j, &value)| {
+                long | (value as u64) << (j * bits)
+            }) as i64
+        })
+        .collect()
+}
+
+/// 解包 block_states 的 `data`，长度与调色板不匹配时返回 `None`
+pub fn unpack_indices(data: &[i64], palette_len: usize) -> Option<Vec<u32>> {
+    unpack(data, palette_len, BLOCK_STATES)
+}
+
+/// 按调色板长度重新打包 block_states 索引
+pub fn pack_indices(indices: &[u32], palette_len: usize) -> Result<Vec<i64>> {
+    Ok(pack(indices, palette_len, BLOCK_STATES)?.unwrap_or_default())
+}
+
+/// 解包 biomes 的 `data`，长度与调色板不匹配或调色板只有一项时返回 `None`
+pub fn unpack_biome_indices(data: &[i64], palette_len: usize) -> Option<Vec<u32>> {
+    unpack(data, palette_len, BIOMES)
+}
+
+/// 按调色板长度重新打包 biomes 索引，调色板只有一项时返回 `None`（不写 data）
+pub fn pack_biome_indices(indices: &[u32], palette_len: usize) -> Result<Option<Vec<i64>>> {
+    pack(indices, palette_len, BIOMES)
+}
+
+/// 将区块中所有 section 的 block_states `data` 展开为索引列表
+///
+/// 只展开能无损还原的数据（重新打包后与原数据逐位相同），其余保持 LongArray
+pub fn unpack_block_states(chunk: &mut Value) {
+    unpack_containers(chunk, BLOCK_STATES);
+}
+
+/// 将区块中所有 section 的 biomes `data` 展开为索引列表（规则同 [`unpack_block_states`]）
+pub fn unpack_biomes(chunk: &mut Value) {
+    unpack_containers(chunk, BIOMES);
+}
+
+/// 将展开的 block_states 索引列表重新打包为 LongArray（还原时使用）
+///
+/// 位数按当前调色板长度计算
+pub fn pack_block_states(chunk: &mut Value) -> Result<()> {
+    pack_containers(chunk, BLOCK_STATES)
+}
+
+/// 将展开的 biomes 索引列表重新打包为 LongArray（规则同 [`pack_block_states`]）
+///
+/// 调色板只有一项时移除 data，与游戏一致
+pub fn pack_biomes(chunk: &mut Value) -> Result<()> {
+    pack_containers(chunk, BIOMES)
+}
+
+fn unpack_containers(chunk: &mut Value, layout: Layout) {
+    for container in containers_mut(chunk, layout) {
+        let palette_len = palette_len(container);
+        let Some(Value::LongArray(data)) = container.get("data") else {
+            continue;
+        };
+        let Some(indices) = unpack(data, palette_len, layout) else {
+            continue;
+        };
+        let lossless = pack(&indices, palette_len, layout)
+            .is_ok_and(|packed| packed.is_some_and(|packed| *packed == **data));
+        if lossless {
+            let list = indices.into_iter().map(|i| Value::Int(i as i32)).collect();
+            container.insert("data".to_string(), Value::List(list));
+        }
+    }
+}
+
+fn pack_containers(chunk: &mut Value, layout: Layout) -> Result<()> {
+    for container in containers_mut(chunk, layout) {
+        let palette_len = palette_len(container);
+        let Some(Value::List(list)) = container.get("data") else {
+            continue;
+        };
+        let indices = list
+            .iter()
+            .map(|v| match v {
+                Value::Byte(i) => u32::try_from(*i).ok(),
+                Value::Short(i) => u32::try_from(*i).ok(),
+                Value::Int(i) => u32::try_from(*i).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<u32>>>()
+            .ok_or_else(|| anyhow::anyhow!("{} 索引必须是非负整数", layout.key))?;
+        match pack(&indices, palette_len, layout)? {
+            Some(data) => {
+                container.insert("data".to_string(), Value::LongArray(LongArray::new(data)));
+            }
+            None => {
+                container.remove("data");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 高度图的列数（16 × 16）
+const HEIGHTMAP_ENTRIES: usize = 256;
+
+/// 导出时记录在 Heightmaps 中的高度位数，还原时移除
+const HEIGHTMAP_BITS_KEY: &str = "_bits";
+
+/// 按世界高度计算高度图位数 `ceil(log2(高度 + 1))`
+///
+/// 世界高度为含 block_states 的 section 数 × 16（原版会保存范围内的所有 section），
+/// 标准世界（384 格）为 9 位；没有 section 时返回 `None`
+fn heightmap_bits(chunk: &HashMap<String, Value>) -> Option<usize> {
+    let Some(Value::List(sections)) = chunk.get("sections") else {
+        return None;
+    };
+    let count = sections
+        .iter()
+        .filter(|s| matches!(s, Value::Compound(sec) if sec.contains_key("block_states")))
+        .count();
+    let height = count * 16;
+    (height > 0).then(|| (usize::BITS - height.leading_zeros()) as usize)
+}
+
+/// 将 Heightmaps 中的每个高度图展开为 16 × 16 的高度数组（按 z 行、x 列）
+///
+/// 位数由世界高度推算并记录在 `Heightmaps._bits` 中；
+/// 长度不匹配或无法无损还原的高度图保持 LongArray
+pub fn unpack_heightmaps(chunk: &mut Value) {
+    let Value::Compound(map) = chunk else {
+        return;
+    };
+    let Some(bits) = heightmap_bits(map) else {
+        return;
+    };
+    let Some(Value::Compound(heightmaps)) = map.get_mut("Heightmaps") else {
+        return;
+    };
+
+    let mut unpacked = false;
+    for value in heightmaps.values_mut() {
+        let Value::LongArray(data) = value else {
+            continue;
+        };
+        let Some(heights) = unpack_bits(data, bits, HEIGHTMAP_ENTRIES) else {
+            continue;
+        };
+        if pack_bits(&heights, bits) != **data {
+            continue;
+        }
+        let rows = heights
+            .chunks(16)
+            .map(|row| Value::List(row.iter().map(|&h| Value::Int(h as i32)).collect()))
+            .collect();
+        *value = Value::List(rows);
+        unpacked = true;
+    }
+    if unpacked {
+        heightmaps.insert(HEIGHTMAP_BITS_KEY.to_string(), Value::Int(bits as i32));
+    }
+}
+
+/// 将展开的高度图重新打包为 LongArray（还原时使用）
+///
+/// 使用 `Heightmaps._bits` 记录的位数，没有记录时按世界高度推算
+pub fn pack_heightmaps(chunk: &mut Value) -> Result<()> {
+    let Value::Compound(map) = chunk else {
+        return Ok(());
+    };
+    let derived = heightmap_bits(map);
+    let Some(Value::Compound(heightmaps)) = map.get_mut("Heightmaps") else {
+        return Ok(());
+    };
+    let bits = match heightmaps.remove(HEIGHTMAP_BITS_KEY) {
+        Some(Value::Byte(b)) => Some(b as usize),
+        Some(Value::Short(b)) => Some(b as usize),
+        Some(Value::Int(b)) => Some(b as usize),
+        _ => derived,
+    };
+
+    for (name, value) in heightmaps.iter_mut() {
+        let Value::List(rows) = value else {
+            continue;
+        };
+        let bits = bits
+            .filter(|b| (1..32).contains(b))
+            .with_context(|| format!("无法确定高度图 {} 的位数", name))?;
+        let heights = rows
+            .iter()
+            .flat_map(|row| match row {
+                Value::List(row) => row.iter().collect(),
+                _ => Vec::new(),
+            })
+            .map(|v| match v {
+                Value::Byte(h) => u32::try_from(*h).ok(),
+                Value::Short(h) => u32::try_from(*h).ok(),
+                Value::Int(h) => u32::try_from(*h).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<u32>>>()
+            .with_context(|| format!("高度图 {} 的高度必须是非负整数", name))?;
+        if heights.len() != HEIGHTMAP_ENTRIES {
+            anyhow::bail!(
+                "高度图 {} 应有 {} 个高度，实际为 {} 个",
+                name,
+                HEIGHTMAP_ENTRIES,
+                heights.len()
+            );
+        }
+        if let Some(&h) = heights.iter().find(|&&h| h >> bits != 0) {
+            anyhow::bail!("高度图 {} 的高度 {} 超出 {} 位", name, h, bits);
+        }
+        *value = Value::LongArray(LongArray::new(pack_bits(&heights, bits)));
+    }
+    Ok(())
+}
+
+/// 计算高度图时视为空气的方块
+const AIR_BLOCKS: &[&str] = &["air", "cave_air", "void_air"];
+
+/// 不阻挡移动的常见方块（计算 MOTION_BLOCKING 时跳过，含水时除外）
+const NON_BLOCKING_BLOCKS: &[&str] = &[
+    "short_grass",
+    "grass",
+    "tall_grass",
+    "fern",
+    "large_fern",
+    "dead_bush",
+    "dandelion",
+    "poppy",
+    "blue_orchid",
+    "allium",
+    "azure_bluet",
+    "red_tulip",
+    "orange_tulip",
+    "white_tulip",
+    "pink_tulip",
+    "oxeye_daisy",
+    "cornflower",
+    "lily_of_the_valley",
+    "sunflower",
+    "lilac",
+    "rose_bush",
+    "peony",
+    "brown_mushroom",
+    "red_mushroom",
+    "sugar_cane",
+    "vine",
+    "cobweb",
+    "torch",
+    "wall_torch",
+    "soul_torch",
+    "soul_wall_torch",
+    "redstone_torch",
+    "redstone_wall_torch",
+    "redstone_wire",
+    "lever",
+    "tripwire",
+    "tripwire_hook",
+    "rail",
+    "powered_rail",
+    "detector_rail",
+    "activator_rail",
+    "nether_portal",
+    "structure_void",
+];
+
+/// 不阻挡移动的方块名后缀
+const NON_BLOCKING_SUFFIXES: &[&str] =
+    &["_sapling", "_button", "_pressure_plate", "_sign", "_banner"];
+
+/// 计算的高度图类型
+const COMPUTED_HEIGHTMAPS: [&str; 2] = ["MOTION_BLOCKING", "WORLD_SURFACE"];
+
+/// 根据 block_states 计算区块缺少的 MOTION_BLOCKING 和 WORLD_SURFACE 高度图（还原时使用）
+///
+/// WORLD_SURFACE 为每列最高的非空气方块，MOTION_BLOCKING 再跳过不阻挡移动的方块
+/// （只识别常见的植物、火把、铁轨等，含水的方块和流体视为阻挡）。
+/// 已有的高度图保持不变；1.18 之前的区块和 block_states 无法解包的区块不处理。
+/// 位数按 `yPos` 到最高 section 的高度计算，且至少按 256 格（原版各维度均为 9 位），
+/// 因为导出时被过滤的顶部空 section 无法确定。返回是否写入了高度图
+pub fn compute_heightmaps(chunk: &mut Value) -> bool {
+    let Value::Compound(map) = chunk else {
+        return false;
+    };
+    let missing: Vec<&str> = COMPUTED_HEIGHTMAPS
+        .into_iter()
+        .filter(|name| match map.get("Heightmaps") {
+            Some(Value::Compound(heightmaps)) => !heightmaps.contains_key(*name),
+            _ => true,
+        })
+        .collect();
+    if missing.is_empty() {
+        return false;
+    }
+    let Some(Value::List(sections)) = map.get("sections") else {
+        return false;
+    };
+
+    // 每个 section 的 Y、方块索引和调色板中各方块的 (非空气, 阻挡移动)
+    let mut layers = Vec::new();
+    for section in sections {
+        let Value::Compound(sec) = section else {
+            continue;
+        };
+        let (Some(y), Some(Value::Compound(states))) = (sec.get("Y"), sec.get("block_states"))
+        else {
+            continue;
+        };
+        let Some(y) = int_value(y) else {
+            continue;
+        };
+        let Some(Value::List(palette)) = states.get("palette") else {
+            continue;
+        };
+        let indices = match states.get("data") {
+            Some(Value::LongArray(data)) => unpack_indices(data, palette.len()),
+            None if palette.len() == 1 => Some(vec![0; BLOCK_STATES.entries]),
+            _ => None,
+        };
+        let Some(indices) = indices else {
+            return false;
+        };
+        let flags: Vec<(bool, bool)> = palette.iter().map(block_flags).collect();
+        layers.push((y, indices, flags));
+    }
+    let (Some(bottom), Some(top)) = (
+        map.get("yPos")
+            .and_then(int_value)
+            .or_else(|| layers.iter().map(|(y, ..)| *y).min()),
+        layers.iter().map(|(y, ..)| *y + 1).max(),
+    ) else {
+        return false;
+    };
+    let height = (((top - bottom) * 16).max(256)) as usize;
+    let bits = (usize::BITS - height.leading_zeros()) as usize;
+
+    // 从上往下扫描，每列记录第一个满足条件的方块的高度（方块 Y + 1 - 世界底部）
+    layers.sort_by_key(|(y, ..)| std::cmp::Reverse(*y));
+    let mut surface = vec![0u32; HEIGHTMAP_ENTRIES];
+    let mut motion = vec![0u32; HEIGHTMAP_ENTRIES];
+    for (y, indices, flags) in &layers {
+        for local_y in (0..16).rev() {
+            let Ok(h) = u32::try_from(y * 16 + local_y as i32 + 1 - bottom * 16) else {
+                continue;
+            };
+            for column in 0..HEIGHTMAP_ENTRIES {
+                let index = indices[local_y * HEIGHTMAP_ENTRIES + column] as usize;
+                let (solid, blocking) = flags.get(index).copied().unwrap_or_default();
+                if solid && surface[column] == 0 {
+                    surface[column] = h;
+                }
+                if blocking && motion[column] == 0 {
+                    motion[column] = h;
+                }
+            }
+        }
+    }
+
+    let heightmaps = map
+        .entry("Heightmaps".to_string())
+        .or_insert_with(|| Value::Compound(HashMap::new()));
+    let Value::Compound(heightmaps) = heightmaps else {
+        return false;
+    };
+    for name in missing {
+        let heights = if name == "MOTION_BLOCKING" {
+            &motion
+        } else {
+            &surface
+        };
+        heightmaps.insert(
+            name.to_string(),
+            Value::LongArray(LongArray::new(pack_bits(heights, bits))),
+        );
+    }
+    true
+}
+
+/// 调色板中的方块是否为非空气方块、是否阻挡移动（含流体）
+fn block_flags(entry: &Value) -> (bool, bool) {
+    let Value::Compound(block) = entry else {
+        return (false, false);
+    };
+    let name = match block.get("Name") {
+        Some(Value::String(name)) => name.strip_prefix("minecraft:").unwrap_or(name),
+        _ => return (false, false),
+    };
+    if AIR_BLOCKS.contains(&name) {
+        return (false, false);
+    }
+    let waterlogged = matches!(
+        block.get("Properties"),
+        Some(Value::Compound(props))
+            if matches!(props.get("waterlogged"), Some(Value::String(v)) if v == "true")
+    );
+    let non_blocking = NON_BLOCKING_BLOCKS.contains(&name)
+        || NON_BLOCKING_SUFFIXES
+            .iter()
+            .any(|suffix| name.ends_with(suffix));
+    (true, waterlogged || !non_blocking)
+}
+
+/// 整数类型标签的值
+fn int_value(value: &Value) -> Option<i32> {
+    match value {
+        Value::Byte(v) => Some((*v).into()),
+        Value::Short(v) => Some((*v).into()),
+        Value::Int(v) => Some(*v),
+        _ => None,
+    }
+}
+
+/// 区块中所有 section 的指定调色板容器
+fn containers_mut(
+    chunk: &mut Value,
+    layout: Layout,
+) -> impl Iterator<Item = &mut HashMap<String, Value>> {
+    let sections = match chunk {
+        Value::Compound(map) => match map.get_mut("sections") {
+            Some(Value::List(sections)) => sections.as_mut_slice(),
+            _ => &mut [],
+        },
+        _ => &mut [],
+    };
+    sections
+        .iter_mut()
+        .filter_map(move |section| match section {
+            Value::Compound(sec) => match sec.get_mut(layout.key) {
+                Some(Value::Compound(container)) => Some(container),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+/// 调色板长度
+fn palette_len(container: &HashMap<String, Value>) -> usize {
+    match container.get("palette") {
+        Some(Value::List(palette)) => palette.len(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 覆盖调色板所有索引的伪随机索引序列
+    fn sample_indices(palette_len: usize) -> Vec<u32> {
+        (0..4096u32)
+            .map(|i| i.wrapping_mul(2_654_435_761) % palette_len as u32)
+            .collect()
+    }
+
+    #[test]
+    fn block_states_round_trip_bit_identical() {
+        for (palette_len, bits) in [(1, 4), (2, 4), (16, 4), (17, 5), (100, 7), (300, 9)] {
+            let indices = sample_indices(palette_len);
+            let data = pack_indices(&indices, palette_len).unwrap();
+            assert_eq!(bits_per_entry(palette_len, BLOCK_STATES), bits);
+            // 索引不跨越 long 边界
+            assert_eq!(data.len(), 4096usize.div_ceil(64 / bits), "{}", palette_len);
+
+            let unpacked = unpack_indices(&data, palette_len).unwrap();
+            assert_eq!(unpacked, indices);
+            assert_eq!(pack_indices(&unpacked, palette_len).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn palette_growth_changes_bits_per_entry() {
+        let indices = sample_indices(16);
+        let packed_16 = pack_indices(&indices, 16).unwrap();
+        // 调色板增加到 17 项后按 5 位重新打包
+        let packed_17 = pack_indices(&indices, 17).unwrap();
+        assert_eq!(packed_16.len(), 256);
+        assert_eq!(packed_17.len(), 342);
+        assert_eq!(unpack_indices(&packed_17, 17).unwrap(), indices);
+        // 位数不匹配的 data 不会被展开
+        assert_eq!(unpack_indices(&packed_16, 17), None);
+    }
+
+    #[test]
+    fn out_of_range_index_is_an_error() {
+        let mut indices = vec![0; 4096];
+        indices[10] = 2;
+        assert!(pack_indices(&indices, 2).is_err());
+        assert!(pack_indices(&indices[..100], 4).is_err());
+    }
+
+    #[test]
+    fn unpacked_chunk_repacks_to_original() {
+        let data = pack_indices(&sample_indices(17), 17).unwrap();
+        let palette = (0..17)
+            .map(|i| {
+                Value::Compound(HashMap::from([(
+                    "Name".to_string(),
+                    Value::String(format!("minecraft:block_{}", i)),
+                )]))
+            })
+            .collect();
+        let section = Value::Compound(HashMap::from([(
+            "block_states".to_string(),
+            Value::Compound(HashMap::from([
+                ("palette".to_string(), Value::List(palette)),
+                (
+                    "data".to_string(),
+                    Value::LongArray(LongArray::new(data.clone())),
+                ),
+            ])),
+        )]));
+        let original = Value::Compound(HashMap::from([(
+            "sections".to_string(),
+            Value::List(vec![section]),
+        )]));
+
+        let mut chunk = original.clone();
+        unpack_block_states(&mut chunk);
+        assert_ne!(chunk, original);
+        pack_block_states(&mut chunk).unwrap();
+        assert_eq!(chunk, original);
+    }
+
+    #[test]
+    fn biomes_are_packed_separately() {
+        let indices: Vec<Value> = (0..64).map(|i| Value::Int(i % 3)).collect();
+        let biomes = |palette_len: usize, data: Vec<Value>| {
+            let palette = (0..palette_len)
+                .map(|i| Value::String(format!("minecraft:biome_{}", i)))
+                .collect();
+            Value::Compound(HashMap::from([(
+                "sections".to_string(),
+                Value::List(vec![Value::Compound(HashMap::from([(
+                    "biomes".to_string(),
+                    Value::Compound(HashMap::from([
+                        ("palette".to_string(), Value::List(palette)),
+                        ("data".to_string(), Value::List(data)),
+                    ])),
+                )]))]),
+            )]))
+        };
+
+        // pack_block_states 不处理 biomes
+        let mut chunk = biomes(3, indices.clone());
+        pack_block_states(&mut chunk).unwrap();
+        assert_eq!(chunk, biomes(3, indices.clone()));
+
+        pack_biomes(&mut chunk).unwrap();
+        let mut expected = chunk.clone();
+        unpack_biomes(&mut expected);
+        assert_eq!(expected, biomes(3, indices));
+
+        // 调色板只有一项时不写 data
+        let mut chunk = biomes(1, vec![Value::Int(0); 64]);
+        pack_biomes(&mut chunk).unwrap();
+        let Value::Compound(root) = &chunk else {
+            unreachable!()
+        };
+        let Some(Value::List(sections)) = root.get("sections") else {
+            unreachable!()
+        };
+        let Value::Compound(section) = &sections[0] else {
+            unreachable!()
+        };
+        let Some(Value::Compound(biomes)) = section.get("biomes") else {
+            unreachable!()
+        };
+        assert!(!biomes.contains_key("data"));
+    }
+}
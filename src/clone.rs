@@ -0,0 +1,140 @@
+//! 不经过 JSON 的快速克隆
+//!
+//! 直接复制各区块压缩后的数据（见 [`read_mca_raw`](crate::mca::read_mca_raw)），
+//! 不解压也不解析 NBT，因此比导出再还原快得多，也能复制 fastnbt 无法解析的区块；不支持去噪和区域过滤
+
+use crate::config::Config;
+use crate::export::{build_thread_pool, dimensions, region_files, RegionKind};
+use crate::mca::{write_mca_raw, McaReader};
+use crate::progress;
+use crate::restore::RegionFailure;
+use anyhow::{Context, Result};
+use log::{debug, error, info};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// 快速克隆结果汇总
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CloneReport {
+    /// 处理的 region 文件数量
+    pub regions_total: usize,
+    /// 复制的区块数量
+    pub chunks_copied: usize,
+    /// 因数据截断而跳过的区块数量
+    pub chunks_failed: usize,
+    /// 复制失败的 region
+    pub failures: Vec<RegionFailure>,
+    /// 总耗时（秒）
+    pub elapsed_secs: f64,
+}
+
+/// 将世界原样复制到 `dest`，`region/`、`entities/`、`poi/` 中的区块按原压缩数据重新紧密排列
+///
+/// 同时复制 level.dat、各维度 `data/` 中的 `.dat` 文件、外部区块文件（`c.{x}.{z}.mcc`），
+/// 以及 `include_playerdata` 开启时的 `playerdata/`；
+/// 在 `[performance] threads` 指定大小的线程池中并行处理 region
+pub fn clone_world_raw(source: &Path, dest: &Path, config: &Config) -> Result<CloneReport> {
+    let start = Instant::now();
+    fs::create_dir_all(dest)?;
+
+    let level_dat = source.join("level.dat");
+    if level_dat.exists() {
+        info!("复制 level.dat");
+        fs::copy(&level_dat, dest.join("level.dat"))
+            .with_context(|| format!("复制 {} 失败", level_dat.display()))?;
+    }
+    for (dim_folder, _) in &dimensions(source) {
+        copy_files(
+            &source.join(dim_folder).join("data"),
+            &dest.join(dim_folder).join("data"),
+            "dat",
+        )?;
+    }
+    if config.export.include_playerdata {
+        copy_files(&source.join("playerdata"), &dest.join("playerdata"), "dat")?;
+    }
+
+    let report = Mutex::new(CloneReport::default());
+    let pool = build_thread_pool(config.performance.threads)?;
+    for (dim_folder, dim_name) in &dimensions(source) {
+        for folder in RegionKind::ALL.map(RegionKind::folder) {
+            let region_path = source.join(dim_folder).join(folder);
+            if !region_path.is_dir() {
+                continue;
+            }
+            let region_output = dest.join(dim_folder).join(folder);
+            copy_files(&region_path, &region_output, "mcc")?;
+
+            let files = region_files(&region_path)?;
+            if files.is_empty() {
+                continue;
+            }
+            info!("复制 {} {}/ ({} 个文件)", dim_name, folder, files.len());
+            progress::start(dim_name, files.len());
+            let result = pool.install(|| {
+                files.par_iter().try_for_each(|path| {
+                    let output = region_output.join(path.file_name().unwrap());
+                    let result = clone_region(path, &output);
+                    progress::inc();
+                    let mut report = report.lock().unwrap();
+                    report.regions_total += 1;
+                    match result {
+                        Ok((copied, failed)) => {
+                            debug!("  完成 {:?}", path.file_name().unwrap());
+                            report.chunks_copied += copied;
+                            report.chunks_failed += failed;
+                        }
+                        Err(e) => {
+                            error!("  失败 {:?}: {}", path.file_name().unwrap(), e);
+                            if config.export.fail_fast {
+                                return Err(e.context(format!("复制 {} 失败", path.display())));
+                            }
+                            report.failures.push(RegionFailure {
+                                path: output,
+                                error: e.to_string(),
+                            });
+                        }
+                    }
+                    Ok(())
+                })
+            });
+            progress::finish();
+            result?;
+        }
+    }
+
+    let mut report = report.into_inner().unwrap();
+    report.failures.sort_by(|a, b| a.path.cmp(&b.path));
+    report.elapsed_secs = start.elapsed().as_secs_f64();
+    Ok(report)
+}
+
+/// 复制单个 region 文件中的区块，返回复制和因截断跳过的区块数量
+fn clone_region(path: &Path, output: &Path) -> Result<(usize, usize)> {
+    let Some(mut reader) = McaReader::open(path)? else {
+        return Ok((0, 0));
+    };
+    let chunks = reader.read_all_raw()?;
+    write_mca_raw(output, &chunks)?;
+    Ok((chunks.len(), reader.failed_chunks()))
+}
+
+/// 复制目录中指定扩展名的文件，目录不存在时不做任何事
+fn copy_files(dir: &Path, output_dir: &Path, extension: &str) -> Result<()> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != extension) {
+            continue;
+        }
+        fs::create_dir_all(output_dir)?;
+        fs::copy(&path, output_dir.join(path.file_name().unwrap()))
+            .with_context(|| format!("复制 {} 失败", path.display()))?;
+    }
+    Ok(())
+}
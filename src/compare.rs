@@ -0,0 +1,193 @@
+//! NBT 语义比较 - 逐路径比较两棵 NBT 树，用于校验导出 → 还原往返是否无损
+
+use crate::mca::{read_mca, ChunkData};
+use crate::nbt_json::nbt_to_snbt;
+use anyhow::{Context, Result};
+use fastnbt::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+/// 两棵 NBT 树在某个路径上的差异
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// 路径，复合标签的键以 `.` 连接，列表元素为 `[i]`（如 `sections[3].block_states.data`），
+    /// 根节点本身不同时为空
+    pub path: String,
+    /// 左侧的值，不存在时为 `None`
+    pub left: Option<Value>,
+    /// 右侧的值，不存在时为 `None`
+    pub right: Option<Value>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<Value>| match value {
+            Some(value) => nbt_to_snbt(value),
+            None => "(不存在)".to_string(),
+        };
+        let path = if self.path.is_empty() {
+            "(根)"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {} -> {}", path, show(&self.left), show(&self.right))
+    }
+}
+
+/// 单个区块的差异（region 内坐标）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkDifference {
+    pub x: i32,
+    pub z: i32,
+    /// 区块内的差异；区块只在一边存在时为一条路径为空的差异
+    pub differences: Vec<Difference>,
+}
+
+/// 比较两棵 NBT 树，返回所有不同的路径（按路径排序）
+///
+/// 复合标签按键比较，与顺序无关；Byte 的 0/1 与其它整数类型的 0/1 视为相同（布尔值）；
+/// `ignore` 中的路径及其子路径不比较，列表下标可以省略（`sections.SkyLight` 忽略所有 section 的 `SkyLight`）
+pub fn compare_nbt(a: &Value, b: &Value, ignore: &[&str]) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    compare_at(
+        a,
+        b,
+        &mut String::new(),
+        &mut String::new(),
+        ignore,
+        &mut differences,
+    );
+    differences
+}
+
+/// 按坐标配对比较两组区块，返回有差异的区块（按 z、x 排序）
+pub fn compare_chunks(a: &[ChunkData], b: &[ChunkData], ignore: &[&str]) -> Vec<ChunkDifference> {
+    let (a, b) = (chunks_by_pos(a), chunks_by_pos(b));
+    let positions: BTreeSet<(i32, i32)> = a.keys().chain(b.keys()).copied().collect();
+
+    positions
+        .into_iter()
+        .filter_map(|pos| {
+            let differences = match (a.get(&pos), b.get(&pos)) {
+                (Some(left), Some(right)) => compare_nbt(left, right, ignore),
+                (left, right) => vec![Difference {
+                    path: String::new(),
+                    left: left.map(|v| (*v).clone()),
+                    right: right.map(|v| (*v).clone()),
+                }],
+            };
+            (!differences.is_empty()).then_some(ChunkDifference {
+                x: pos.1,
+                z: pos.0,
+                differences,
+            })
+        })
+        .collect()
+}
+
+/// 以 (z, x) 为键的区块数据
+fn chunks_by_pos(chunks: &[ChunkData]) -> BTreeMap<(i32, i32), &Value> {
+    chunks.iter().map(|c| ((c.z, c.x), &c.data)).collect()
+}
+
+/// 读取两个 region 文件并按坐标比较其中的区块
+pub fn compare_regions(a: &Path, b: &Path, ignore: &[&str]) -> Result<Vec<ChunkDifference>> {
+    let left = read_mca(a).with_context(|| format!("读取 {} 失败", a.display()))?;
+    let right = read_mca(b).with_context(|| format!("读取 {} 失败", b.display()))?;
+    Ok(compare_chunks(&left, &right, ignore))
+}
+
+/// 递归比较，`path` 为完整路径，`pattern` 为省略列表下标的路径（用于匹配 `ignore`）
+fn compare_at(
+    a: &Value,
+    b: &Value,
+    path: &mut String,
+    pattern: &mut String,
+    ignore: &[&str],
+    differences: &mut Vec<Difference>,
+) {
+    if !path.is_empty() && ignore.iter().any(|i| *i == path || *i == pattern) {
+        return;
+    }
+    match (a, b) {
+        (Value::Compound(a), Value::Compound(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let (path_len, pattern_len) = (path.len(), pattern.len());
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                if !pattern.is_empty() {
+                    pattern.push('.');
+                }
+                path.push_str(key);
+                pattern.push_str(key);
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => compare_at(a, b, path, pattern, ignore, differences),
+                    (left, right) => {
+                        if !ignore.iter().any(|i| *i == path || *i == pattern) {
+                            differences.push(Difference {
+                                path: path.clone(),
+                                left: left.cloned(),
+                                right: right.cloned(),
+                            });
+                        }
+                    }
+                }
+                path.truncate(path_len);
+                pattern.truncate(pattern_len);
+            }
+        }
+        (Value::List(a), Value::List(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                let len = path.len();
+                path.push_str(&format!("[{}]", i));
+                match (a.get(i), b.get(i)) {
+                    (Some(a), Some(b)) => compare_at(a, b, path, pattern, ignore, differences),
+                    (left, right) => differences.push(Difference {
+                        path: path.clone(),
+                        left: left.cloned(),
+                        right: right.cloned(),
+                    }),
+                }
+                path.truncate(len);
+            }
+        }
+        _ if values_equal(a, b) => {}
+        _ => differences.push(Difference {
+            path: path.clone(),
+            left: Some(a.clone()),
+            right: Some(b.clone()),
+        }),
+    }
+}
+
+/// 比较两个非容器值：浮点数按位比较（NaN 与 NaN 相同），Byte 的 0/1 与其它整数的 0/1 相同
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+        (Value::Double(a), Value::Double(b)) => a.to_bits() == b.to_bits(),
+        (Value::Byte(_), _) | (_, Value::Byte(_)) => match (boolean(a), boolean(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => a == b,
+        },
+        _ => a == b,
+    }
+}
+
+/// 值为 0 或 1 的整数视为布尔值
+fn boolean(value: &Value) -> Option<bool> {
+    let n = match value {
+        Value::Byte(v) => i64::from(*v),
+        Value::Short(v) => i64::from(*v),
+        Value::Int(v) => i64::from(*v),
+        Value::Long(v) => *v,
+        _ => return None,
+    };
+    match n {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
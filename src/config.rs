@@ -1,12 +1,21 @@
 //! 配置文件加载与管理
 
-use anyhow::Result;
+use crate::denoise::{
+    check_field_patterns, default_restore_values, ENTITY_AGGRESSIVE_FIELDS, ENTITY_NOISE_FIELDS,
+    SECTION_AGGRESSIVE_FIELDS, SECTION_LIGHT_FIELDS,
+};
+use crate::nbt_json::{check_aliases, json_to_nbt, nbt_to_json, BUILTIN_FIELD_ALIASES};
+use anyhow::{Context, Result};
+use log::{info, warn};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 /// 主配置结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct Config {
     /// 导出配置
@@ -17,10 +26,40 @@ pub struct Config {
     pub denoise: DenoiseConfig,
     /// 字段名映射配置
     pub field_mapping: FieldMappingConfig,
+    /// 性能配置
+    pub performance: PerformanceConfig,
+    /// 命名配置方案，每个方案只需列出与上面不同的字段（结构与整个配置相同），
+    /// 通过 `--profile` 选择后覆盖到基础配置上
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, serde_json::Value>,
+}
+
+/// 性能配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PerformanceConfig {
+    /// 所有并行处理（导出、还原、校验、比较、统计）使用的线程数，0 表示使用所有 CPU
+    pub threads: usize,
 }
 
+/// 默认单个切片的最大大小（字节）
+pub const DEFAULT_MAX_SLICE_SIZE: usize = 8 * 1024 * 1024; // 8MB
+
+/// 切片大小上限的允许范围（MB）
+pub const SLICE_SIZE_MB_RANGE: std::ops::RangeInclusive<f64> = 0.1..=64.0;
+
+/// 1MB 的字节数
+const MB: f64 = 1024.0 * 1024.0;
+
+/// 默认视为空的方块（游戏会将缺失的 section 重新生成为空气）
+pub const DEFAULT_EMPTY_BLOCKS: &[&str] =
+    &["minecraft:air", "minecraft:cave_air", "minecraft:void_air"];
+
+/// 默认导出的区块生成状态（只导出完整生成的区块）
+pub const DEFAULT_INCLUDED_STATUSES: &[&str] = &["minecraft:full", "full"];
+
 /// 导出配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct ExportConfig {
     /// 默认启用去噪
@@ -29,66 +68,692 @@ pub struct ExportConfig {
     pub aggressive: bool,
     /// 跳过没有实际数据的区块
     pub skip_empty_chunks: bool,
+    /// 判断区块是否没有实际数据的规则（`skip_empty_chunks` 为 true 时生效）
+    pub empty_chunk_policy: EmptyChunkPolicy,
+    /// 短于该字节数的 ByteArray 使用十六进制编码（0 表示始终使用 base64）
+    pub hex_byte_array_threshold: usize,
+    /// ByteArray/IntArray/LongArray 的编码方式
+    pub array_encoding: ByteArrayEncoding,
+    /// 输出的区块数据格式
+    pub format: SliceFormat,
+    /// 单个切片文件的最大大小（MB），允许 0.1 – 64，至少要能容纳一个区块
+    pub max_slice_size_mb: f64,
+    /// 输出文件布局（切片文件或每个区块一个文件）
+    pub layout: ExportLayout,
+    /// 区块分配到切片文件的方式
+    pub slice_mode: SliceMode,
+    /// 稳定切片模式下每个切片包含的区块槽位数（region 内按 `z * 32 + x` 编号）
+    pub chunks_per_slice: usize,
+    /// 任一 region 导出失败时立即中止（否则继续处理其余 region）
+    pub fail_fast: bool,
+    /// 只导出该范围内的区块（区块坐标），不设置时导出全部区块
+    pub bounds: Option<ChunkBounds>,
+    /// 将方块实体拆分到 `be/` 子目录中的切片 `r.{rx}.{rz}.{id}.json`，不写入区块切片
+    pub split_block_entities: bool,
+    /// 分层导出：方块（`r.{rx}.{rz}.{N}.blocks.json`）、生物群系（`.biomes.json`）
+    /// 和方块实体（`.be.json`）各自写入独立的切片集合，主切片只保留其余字段
+    pub layered: bool,
+    /// 将 block_states 的 `data` 展开为调色板索引数组（每个方块一个整数），单个方块的修改只改变对应的索引
+    pub readable_block_states: bool,
+    /// 将 biomes 的 `data` 展开为调色板索引数组（每个 4×4×4 单元一个整数）
+    pub readable_biomes: bool,
+    /// 将 Heightmaps 中的每个高度图展开为 16 × 16 的高度数组，无法无损展开时保持 LongArray
+    pub readable_heightmaps: bool,
+    /// 试运行：执行完整的导出流程但不写入任何文件（只由命令行设置）
+    #[serde(skip)]
+    pub dry_run: bool,
+    /// 视为空的方块 ID，只由其中一种方块填满的 section 不导出（省略命名空间时为 `minecraft:`）
+    pub empty_blocks: Vec<String>,
+    /// 导出的区块生成状态（`Status`），其它状态的区块视为未完整生成而跳过；
+    /// 包含 `"*"` 时导出所有区块
+    pub included_statuses: Vec<String>,
+    /// 导出 `playerdata/` 中各玩家的数据（背包、位置等），归档时可关闭以去除个人数据
+    pub include_playerdata: bool,
+    /// 通过内存映射读取 MCA 文件（映射失败时回退到普通读取），世界正被游戏使用时不要启用
+    pub use_mmap: bool,
+    /// 在导出目录根部写入 `mcj-manifest.json`，列出导出的 region 及其切片和区块数量
+    pub manifest: bool,
+    /// 额外的区块过滤器，区块需通过所有过滤器才会导出（与 `included_statuses`、
+    /// `skip_empty_chunks` 和 `bounds` 的检查一样在去噪之后的区块上执行）
+    pub filters: Vec<ChunkFilterName>,
+    /// 只导出方块调色板中含有其中任一方块的区块（省略命名空间时为 `minecraft:`），为空时不过滤
+    pub include_blocks: Vec<String>,
+    /// 要求所有区块的 `DataVersion` 等于该值，否则中止整个导出（不受 `fail_fast` 影响，
+    /// 避免导出版本混杂的世界），不设置时不检查
+    pub require_data_version: Option<i32>,
 }
 
 /// 还原配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct RestoreConfig {
     /// 默认恢复默认值
     pub restore_defaults: bool,
+    /// 读取的区块数据格式
+    pub format: SliceFormat,
+    /// 任一 region 还原失败时立即中止（否则继续处理其余 region）
+    pub fail_fast: bool,
+    /// 写入 MCA 时的区块压缩方式和级别
+    pub compression: CompressionConfig,
+    /// 输出目录已存在时的处理方式（只由命令行设置）
+    #[serde(skip)]
+    pub existing_output: ExistingOutputPolicy,
+    /// 只还原该范围内的区块（区块坐标），范围外的区块不写入，不设置时还原全部区块
+    pub bounds: Option<ChunkBounds>,
+    /// 只还原这些 region（`[rx, rz]`），为空时还原全部 region
+    pub regions: Vec<[i32; 2]>,
+    /// 将坐标不属于所在 region 的区块（如被移到错误切片文件中的区块）移动到正确的 region，
+    /// 否则这些区块视为错误
+    pub relocate_chunks: bool,
+    /// 同一 region 的切片中出现坐标重复的区块时的处理方式
+    pub dedupe: DuplicateChunkPolicy,
+    /// 尽力还原：跳过无法读取的文件和无法还原的区块（输出错误信息），还原其余所有区块，
+    /// 用于从不完整的导出目录中恢复世界
+    pub best_effort: bool,
+    /// 为缺少高度图的区块根据 block_states 计算 MOTION_BLOCKING 和 WORLD_SURFACE
+    pub compute_heightmaps: bool,
+    /// 恢复默认值时补全的区块字段及默认值（值使用 JSON 导出的类型表示法，如 `"0L"`）
+    pub defaults: BTreeMap<String, serde_json::Value>,
+}
+
+impl RestoreConfig {
+    /// 解析 `defaults` 中的默认值
+    pub fn default_values(&self) -> Result<Vec<(String, fastnbt::Value)>> {
+        self.defaults
+            .iter()
+            .map(|(field, value)| {
+                let value = json_to_nbt(value)
+                    .with_context(|| format!("restore.defaults.{} 配置错误", field))?;
+                Ok((field.clone(), value))
+            })
+            .collect()
+    }
+}
+
+/// 还原时输出目录已存在的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExistingOutputPolicy {
+    /// 报错，不修改输出目录
+    #[default]
+    Fail,
+    /// 先删除各维度的 `region/` 和 `level.dat`（保留其它文件），再还原
+    Clean,
+    /// 合并到已有世界：覆盖导出的区块，保留其余区块和已有的 level.dat
+    Merge,
+}
+
+/// 同一 region 中坐标重复的区块（如 Git 合并出错后两个切片都含有同一区块）的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateChunkPolicy {
+    /// 视为错误，列出两个来源文件
+    #[default]
+    Error,
+    /// 保留最后出现的区块（按文件名排序，同一文件内按顺序）
+    Last,
+}
+
+impl std::str::FromStr for DuplicateChunkPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(DuplicateChunkPolicy::Error),
+            "last" => Ok(DuplicateChunkPolicy::Last),
+            _ => Err(format!(
+                "未知的重复区块处理方式: {}（可选: error, last）",
+                s
+            )),
+        }
+    }
+}
+
+/// 空区块判断规则：区块缺少哪些数据时视为空区块并跳过
+///
+/// 同时接受旧版配置中的 PascalCase 名称（如 `RequireSectionsOrBlockEntities`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyChunkPolicy {
+    /// 没有非空 section 时视为空
+    #[serde(alias = "RequireSections")]
+    RequireSections,
+    /// 没有非空 section 和方块实体时视为空
+    #[default]
+    #[serde(alias = "RequireSectionsOrBlockEntities")]
+    RequireSectionsOrBlockEntities,
+    /// 除坐标、状态、版本等基本字段外没有任何数据时视为空（保留只有 structures 等数据的区块）
+    #[serde(alias = "RequireAnyField")]
+    RequireAnyField,
+    /// 从不视为空，导出所有区块
+    #[serde(alias = "Never")]
+    Never,
+}
+
+/// 导出时使用的内置区块过滤器（见 [`crate::filter`]）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkFilterName {
+    /// 只导出完整生成（`Status` 为 `minecraft:full`）的区块
+    FullStatus,
+    /// 只导出按 `empty_chunk_policy` 判断有实际数据的区块
+    HasData,
+    /// 只导出 `bounds` 范围内的区块
+    Bounds,
+    /// 导出所有区块
+    All,
+}
+
+/// NBT 数组（ByteArray/IntArray/LongArray）在 JSON 中的编码方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ByteArrayEncoding {
+    /// base64 字符串 `"B;..."` / `"I;..."` / `"L;..."`
+    #[default]
+    Base64,
+    /// ByteArray 写为十六进制字符串 `"B;hex:..."`，IntArray/LongArray 仍使用 base64
+    Hex,
+    /// 元素不多的数组写为 JSON 整数数组 `{"L;": [...]}`，较大的数组仍使用 base64
+    Array,
+}
+
+/// 区块输出文件布局
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportLayout {
+    /// 每个 region 切分为若干切片文件 `r.{rx}.{rz}.{id}.json`
+    #[default]
+    Sliced,
+    /// 每个区块一个文件 `r.{rx}.{rz}/c.{cx}.{cz}.json`（区块坐标为世界区块坐标）
+    PerChunk,
+}
+
+impl std::str::FromStr for ExportLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "sliced" => Ok(ExportLayout::Sliced),
+            "per_chunk" => Ok(ExportLayout::PerChunk),
+            _ => Err(format!("未知布局: {}（可选: sliced, per-chunk）", s)),
+        }
+    }
+}
+
+/// 切片分配方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SliceMode {
+    /// 按区块编号固定分桶：修改一个区块只会改写它所在的切片，
+    /// 但切片大小不受 `max_slice_size_mb` 严格限制（超出时输出警告）
+    #[default]
+    Stable,
+    /// 按大小贪心切片：切片大小严格受限，
+    /// 但区块变大或增加会使后续区块整体移动到其它切片
+    Size,
+}
+
+/// 区块切片文件格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SliceFormat {
+    /// 紧凑 JSON（类型通过后缀编码）
+    #[default]
+    Json,
+    /// SNBT（字符串化 NBT），每行一个区块
+    Snbt,
+}
+
+impl SliceFormat {
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SliceFormat::Json => "json",
+            SliceFormat::Snbt => "snbt",
+        }
+    }
+}
+
+impl std::str::FromStr for SliceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(SliceFormat::Json),
+            "snbt" => Ok(SliceFormat::Snbt),
+            _ => Err(format!("未知格式: {}（可选: json, snbt）", s)),
+        }
+    }
+}
+
+/// MCA 中区块的压缩方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionCodec {
+    /// Gzip（压缩类型 1）
+    Gzip,
+    /// Zlib（压缩类型 2，原版默认）
+    #[default]
+    Zlib,
+    /// 不压缩（压缩类型 3）
+    None,
+    /// LZ4（压缩类型 4，1.20.5+ 的 `region-file-compression=lz4`，更旧的版本无法读取）
+    Lz4,
+}
+
+impl CompressionCodec {
+    /// 写入区块头部的压缩类型字节
+    pub fn id(&self) -> u8 {
+        match self {
+            CompressionCodec::Gzip => 1,
+            CompressionCodec::Zlib => 2,
+            CompressionCodec::None => 3,
+            CompressionCodec::Lz4 => 4,
+        }
+    }
+}
+
+/// 写入 MCA 时的区块压缩配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct CompressionConfig {
+    /// 压缩方式
+    pub codec: CompressionCodec,
+    /// 压缩级别（0–9），1 最快，9 文件最小；`codec = "none"` 或 `"lz4"` 时忽略
+    pub level: u32,
+}
+
+impl CompressionConfig {
+    /// 检查压缩级别是否在 0–9 之间
+    pub fn check(&self) -> Result<()> {
+        if self.level > 9 {
+            anyhow::bail!("compression.level 必须在 0–9 之间，当前为 {}", self.level);
+        }
+        Ok(())
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            codec: CompressionCodec::Zlib,
+            level: 6,
+        }
+    }
+}
+
+/// 区块范围（区块坐标，包含边界）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ChunkBounds {
+    pub min_x: i32,
+    pub min_z: i32,
+    pub max_x: i32,
+    pub max_z: i32,
+}
+
+impl ChunkBounds {
+    /// 创建区块范围，自动调整最小/最大值的顺序
+    pub fn new(x1: i32, z1: i32, x2: i32, z2: i32) -> Self {
+        Self {
+            min_x: x1.min(x2),
+            min_z: z1.min(z2),
+            max_x: x1.max(x2),
+            max_z: z1.max(z2),
+        }
+    }
+
+    /// 检查区块是否在范围内
+    pub fn contains(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        (self.min_x..=self.max_x).contains(&chunk_x) && (self.min_z..=self.max_z).contains(&chunk_z)
+    }
+
+    /// 检查 region 是否与范围有交集（每个 region 包含 32x32 个区块）
+    pub fn may_contain_region(&self, region_x: i32, region_z: i32) -> bool {
+        let region_min_x = region_x * 32;
+        let region_min_z = region_z * 32;
+        region_min_x + 31 >= self.min_x
+            && region_min_x <= self.max_x
+            && region_min_z + 31 >= self.min_z
+            && region_min_z <= self.max_z
+    }
+}
+
+impl std::str::FromStr for ChunkBounds {
+    type Err = String;
+
+    /// 解析 `minX,minZ,maxX,maxZ`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let values: Vec<i32> = s
+            .split(',')
+            .map(|v| v.trim().parse())
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| format!("无效的区块范围: {}（格式: minX,minZ,maxX,maxZ）", s))?;
+        match values[..] {
+            [x1, z1, x2, z2] => Ok(Self::new(x1, z1, x2, z2)),
+            _ => Err(format!(
+                "无效的区块范围: {}（格式: minX,minZ,maxX,maxZ）",
+                s
+            )),
+        }
+    }
+}
+
+/// 配置文件格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// 根据文件扩展名判断格式，无法识别时按 TOML 处理
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("json") => ConfigFormat::Json,
+            Some("yaml") | Some("yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Json => "json",
+            ConfigFormat::Yaml => "yaml",
+        }
+    }
+}
+
+impl std::str::FromStr for ConfigFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "toml" => Ok(ConfigFormat::Toml),
+            "json" => Ok(ConfigFormat::Json),
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            _ => Err(format!("未知格式: {}（可选: toml, json, yaml）", s)),
+        }
+    }
 }
 
 /// 去噪配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct DenoiseConfig {
     /// 区块级去噪配置
     pub chunk: ChunkDenoiseConfig,
     /// 存档级去噪配置
     pub level: LevelDenoiseConfig,
+    /// 实体级去噪配置（区块或实体区块中的 `Entities`）
+    pub entity: EntityDenoiseConfig,
+    /// 按维度覆盖的区块级去噪配置，键为维度路径（如 `DIM-1`、`dimensions/minecraft/the_nether`）
+    pub dimensions: HashMap<String, ChunkDenoiseOverlay>,
+    /// 无损去噪：被移除的值写入 `r.{rx}.{rz}.noise.{ext}`，还原时重新插入，得到与原区块相同的数据
+    pub lossless: bool,
+}
+
+impl DenoiseConfig {
+    /// 获取指定维度生效的区块级去噪配置
+    ///
+    /// 没有对应覆盖时直接返回基础配置；主世界传 `None`
+    pub fn chunk_config(&self, dimension: Option<&str>) -> Cow<'_, ChunkDenoiseConfig> {
+        match dimension.and_then(|dim| self.dimensions.get(dim)) {
+            Some(overlay) => Cow::Owned(self.chunk.merged_with(overlay)),
+            None => Cow::Borrowed(&self.chunk),
+        }
+    }
+
+    /// 检查所有字段列表中的正则表达式（`re:` 前缀）和通配符是否有效
+    pub fn check_field_patterns(&self) -> Result<()> {
+        for (name, fields) in self.field_lists() {
+            check_field_patterns(fields).with_context(|| format!("{} 配置错误", name))?;
+        }
+        Ok(())
+    }
+
+    /// 所有字段列表及其配置项名称（如 `denoise.chunk.fields`）
+    fn field_lists(&self) -> Vec<(String, &Vec<String>)> {
+        let chunk = &self.chunk;
+        let mut lists = vec![
+            ("denoise.chunk.fields".to_string(), &chunk.fields),
+            ("denoise.chunk.keep_fields".to_string(), &chunk.keep_fields),
+            (
+                "denoise.chunk.aggressive_fields".to_string(),
+                &chunk.aggressive_fields,
+            ),
+            (
+                "denoise.chunk.section_fields".to_string(),
+                &chunk.section_fields,
+            ),
+            (
+                "denoise.chunk.section_aggressive_fields".to_string(),
+                &chunk.section_aggressive_fields,
+            ),
+        ];
+        for (dim, overlay) in &self.dimensions {
+            for (name, fields) in overlay.field_lists() {
+                lists.push((format!("denoise.dimensions.\"{}\".{}", dim, name), fields));
+            }
+        }
+        lists.push(("denoise.entity.fields".to_string(), &self.entity.fields));
+        lists.push((
+            "denoise.entity.aggressive_fields".to_string(),
+            &self.entity.aggressive_fields,
+        ));
+        lists.push(("denoise.level.fields".to_string(), &self.level.fields));
+        lists
+    }
+}
+
+/// 去噪模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DenoiseMode {
+    /// 黑名单：移除 `fields` 中列出的字段
+    #[default]
+    Blacklist,
+    /// 白名单：只保留 `keep_fields` 中列出的字段和结构性字段
+    Whitelist,
 }
 
 /// 区块级去噪配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct ChunkDenoiseConfig {
-    /// 普通去噪字段
+    /// 去噪模式
+    pub mode: DenoiseMode,
+    /// 普通去噪字段（白名单模式下不使用），支持 `*` 通配符和 `re:` 开头的正则表达式
     pub fields: Vec<String>,
-    /// 激进去噪字段
+    /// 白名单模式下除结构性字段外保留的字段，支持 `*` 通配符和 `re:` 开头的正则表达式
+    pub keep_fields: Vec<String>,
+    /// 激进去噪字段，支持 `*` 通配符和 `re:` 开头的正则表达式
     pub aggressive_fields: Vec<String>,
+    /// 激进模式移除 `Heightmaps` 时保留的高度图（如 `MOTION_BLOCKING`），为空时移除整个 `Heightmaps`
+    pub keep_heightmaps: Vec<String>,
+    /// 从每个 section 中移除的字段（默认为光照数据，让游戏重新计算），为空时不移除任何 section 字段
+    pub section_fields: Vec<String>,
+    /// 激进模式下额外从每个 section 中移除的字段
+    pub section_aggressive_fields: Vec<String>,
+    /// 去噪时不导出区块时间戳（`_ts`）
+    pub strip_timestamp: bool,
+}
+
+/// 按维度覆盖的区块级去噪配置，未写出的项沿用 `[denoise.chunk]`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ChunkDenoiseOverlay {
+    /// 去噪模式
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<DenoiseMode>,
+    /// 普通去噪字段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<String>>,
+    /// 白名单模式下保留的字段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_fields: Option<Vec<String>>,
+    /// 激进去噪字段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggressive_fields: Option<Vec<String>>,
+    /// 激进模式移除 `Heightmaps` 时保留的高度图
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_heightmaps: Option<Vec<String>>,
+    /// 从每个 section 中移除的字段，写出时替换 `[denoise.chunk]` 的列表（`[]` 保留光照数据）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section_fields: Option<Vec<String>>,
+    /// 激进模式下额外从每个 section 中移除的字段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section_aggressive_fields: Option<Vec<String>>,
+    /// 去噪时不导出区块时间戳（`_ts`）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strip_timestamp: Option<bool>,
+}
+
+impl ChunkDenoiseOverlay {
+    /// 写出的字段列表及其名称（如 `fields`）
+    fn field_lists(&self) -> Vec<(&'static str, &Vec<String>)> {
+        [
+            ("fields", &self.fields),
+            ("keep_fields", &self.keep_fields),
+            ("aggressive_fields", &self.aggressive_fields),
+            ("section_fields", &self.section_fields),
+            ("section_aggressive_fields", &self.section_aggressive_fields),
+        ]
+        .into_iter()
+        .filter_map(|(name, fields)| fields.as_ref().map(|fields| (name, fields)))
+        .collect()
+    }
+}
+
+impl ChunkDenoiseConfig {
+    /// 将维度配置合并到基础配置之上
+    ///
+    /// `mode` 和 `strip_timestamp` 写出时取覆盖值；`section_fields` 写出时替换基础配置的列表。
+    /// 模式相同时其余字段列表取并集；模式不同时 `fields` 和 `keep_fields` 使用覆盖配置的列表
+    /// （未写出时为默认值），激进字段和保留的高度图总是取并集
+    pub fn merged_with(&self, overlay: &ChunkDenoiseOverlay) -> ChunkDenoiseConfig {
+        let merge = |base: &[String], extra: &Option<Vec<String>>| {
+            let mut fields = base.to_vec();
+            for field in extra.iter().flatten() {
+                if !fields.contains(field) {
+                    fields.push(field.clone());
+                }
+            }
+            fields
+        };
+        let mode = overlay.mode.unwrap_or(self.mode);
+        let (fields, keep_fields) = if mode == self.mode {
+            (
+                merge(&self.fields, &overlay.fields),
+                merge(&self.keep_fields, &overlay.keep_fields),
+            )
+        } else {
+            (
+                overlay
+                    .fields
+                    .clone()
+                    .unwrap_or_else(|| ChunkDenoiseConfig::default().fields),
+                overlay.keep_fields.clone().unwrap_or_default(),
+            )
+        };
+        ChunkDenoiseConfig {
+            mode,
+            fields,
+            keep_fields,
+            aggressive_fields: merge(&self.aggressive_fields, &overlay.aggressive_fields),
+            keep_heightmaps: merge(&self.keep_heightmaps, &overlay.keep_heightmaps),
+            section_fields: overlay
+                .section_fields
+                .clone()
+                .unwrap_or_else(|| self.section_fields.clone()),
+            section_aggressive_fields: merge(
+                &self.section_aggressive_fields,
+                &overlay.section_aggressive_fields,
+            ),
+            strip_timestamp: overlay.strip_timestamp.unwrap_or(self.strip_timestamp),
+        }
+    }
 }
 
 /// 存档级去噪配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct LevelDenoiseConfig {
-    /// 去噪字段
+    /// 去噪字段，支持 `*` 通配符和 `re:` 开头的正则表达式
     pub fields: Vec<String>,
     /// 重置天气状态
     pub reset_weather: bool,
 }
 
+/// 实体级去噪配置，作用于 `Entities` 中的每个实体及其乘客（`Passengers`）
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct EntityDenoiseConfig {
+    /// 从每个实体中移除的字段，支持 `*` 通配符和 `re:` 开头的正则表达式
+    pub fields: Vec<String>,
+    /// 激进模式下额外移除的字段（默认为 UUID，游戏加载时会重新生成）
+    pub aggressive_fields: Vec<String>,
+}
+
 /// 字段名映射配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct FieldMappingConfig {
     /// 是否启用字段名映射
     pub enabled: bool,
-    /// 字段名映射表：长名 -> 短名
+    /// 使用内置的字段名别名（如 `block_states` -> `bs`）
+    pub use_builtins: bool,
+    /// 额外的字段名映射表：长名 -> 短名（覆盖同名字段的内置别名）
     pub mappings: Vec<(String, String)>,
 }
 
+impl FieldMappingConfig {
+    /// 检查字段名映射是否有冲突：同一字段在 `mappings` 中出现多次、多个字段映射为同一短名，
+    /// 或短名与另一个被映射的字段名相同（还原时无法区分）
+    pub fn check_aliases(&self) -> Result<()> {
+        let mut shorten: BTreeMap<&str, &str> = BTreeMap::new();
+        if self.use_builtins {
+            shorten.extend(BUILTIN_FIELD_ALIASES.iter().copied());
+        }
+        let mut configured = HashSet::new();
+        for (long, short) in &self.mappings {
+            if !configured.insert(long.as_str()) {
+                anyhow::bail!("field_mapping.mappings 中字段 {} 出现多次", long);
+            }
+            shorten.insert(long, short);
+        }
+
+        check_aliases(shorten)
+    }
+}
+
 // ============== 默认值 ==============
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            export: ExportConfig::default(),
-            restore: RestoreConfig::default(),
-            denoise: DenoiseConfig::default(),
-            field_mapping: FieldMappingConfig::default(),
+impl ExportConfig {
+    /// 单个切片文件的最大大小（字节）
+    pub fn max_slice_size_bytes(&self) -> usize {
+        (self.max_slice_size_mb * MB) as usize
+    }
+
+    /// 检查切片大小上限是否在 0.1MB – 64MB 之间
+    pub fn check_slice_size(&self) -> Result<()> {
+        if !SLICE_SIZE_MB_RANGE.contains(&self.max_slice_size_mb) {
+            anyhow::bail!(
+                "max_slice_size_mb 必须在 {} – {} 之间，当前为 {}",
+                SLICE_SIZE_MB_RANGE.start(),
+                SLICE_SIZE_MB_RANGE.end(),
+                self.max_slice_size_mb
+            );
         }
+        Ok(())
     }
 }
 
@@ -98,6 +763,33 @@ impl Default for ExportConfig {
             denoise: true,
             aggressive: false,
             skip_empty_chunks: true,
+            empty_chunk_policy: EmptyChunkPolicy::default(),
+            hex_byte_array_threshold: 0,
+            array_encoding: ByteArrayEncoding::Base64,
+            format: SliceFormat::Json,
+            max_slice_size_mb: DEFAULT_MAX_SLICE_SIZE as f64 / MB,
+            layout: ExportLayout::Sliced,
+            slice_mode: SliceMode::Stable,
+            chunks_per_slice: 128,
+            fail_fast: false,
+            bounds: None,
+            split_block_entities: false,
+            layered: false,
+            readable_block_states: false,
+            readable_biomes: false,
+            readable_heightmaps: false,
+            dry_run: false,
+            empty_blocks: DEFAULT_EMPTY_BLOCKS.iter().map(|s| s.to_string()).collect(),
+            included_statuses: DEFAULT_INCLUDED_STATUSES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            include_playerdata: true,
+            use_mmap: false,
+            manifest: true,
+            filters: Vec::new(),
+            include_blocks: Vec::new(),
+            require_data_version: None,
         }
     }
 }
@@ -106,15 +798,20 @@ impl Default for RestoreConfig {
     fn default() -> Self {
         Self {
             restore_defaults: true,
-        }
-    }
-}
-
-impl Default for DenoiseConfig {
-    fn default() -> Self {
-        Self {
-            chunk: ChunkDenoiseConfig::default(),
-            level: LevelDenoiseConfig::default(),
+            format: SliceFormat::Json,
+            fail_fast: false,
+            compression: CompressionConfig::default(),
+            existing_output: ExistingOutputPolicy::Fail,
+            bounds: None,
+            regions: Vec::new(),
+            relocate_chunks: false,
+            dedupe: DuplicateChunkPolicy::default(),
+            best_effort: false,
+            compute_heightmaps: false,
+            defaults: default_restore_values()
+                .iter()
+                .map(|(field, value)| (field.clone(), nbt_to_json(value)))
+                .collect(),
         }
     }
 }
@@ -122,6 +819,7 @@ impl Default for DenoiseConfig {
 impl Default for ChunkDenoiseConfig {
     fn default() -> Self {
         Self {
+            mode: DenoiseMode::Blacklist,
             fields: vec![
                 "LastUpdate".to_string(),
                 "InhabitedTime".to_string(),
@@ -133,11 +831,19 @@ impl Default for ChunkDenoiseConfig {
                 "starlight.blocklight_state".to_string(),
                 "starlight.skylight_state".to_string(),
             ],
+            keep_fields: Vec::new(),
             aggressive_fields: vec![
                 "Heightmaps".to_string(),
                 "fluid_ticks".to_string(),
                 "block_ticks".to_string(),
             ],
+            keep_heightmaps: Vec::new(),
+            section_fields: SECTION_LIGHT_FIELDS.iter().map(|s| s.to_string()).collect(),
+            section_aggressive_fields: SECTION_AGGRESSIVE_FIELDS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            strip_timestamp: false,
         }
     }
 }
@@ -164,56 +870,204 @@ impl Default for LevelDenoiseConfig {
     }
 }
 
+impl Default for EntityDenoiseConfig {
+    fn default() -> Self {
+        Self {
+            fields: ENTITY_NOISE_FIELDS.iter().map(|s| s.to_string()).collect(),
+            aggressive_fields: ENTITY_AGGRESSIVE_FIELDS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    }
+}
+
 impl Default for FieldMappingConfig {
     fn default() -> Self {
         Self {
             enabled: true,
-            mappings: vec![
-                // 版本
-                ("DataVersion".to_string(), "dv".to_string()),
-                // 区块核心字段
-                ("sections".to_string(), "sec".to_string()),
-                ("block_entities".to_string(), "be".to_string()),
-                ("block_states".to_string(), "bs".to_string()),
-                ("block_ticks".to_string(), "bt".to_string()),
-                ("fluid_ticks".to_string(), "ft".to_string()),
-                ("PostProcessing".to_string(), "pp".to_string()),
-                ("InhabitedTime".to_string(), "it".to_string()),
-                ("LastUpdate".to_string(), "lu".to_string()),
-                ("Heightmaps".to_string(), "hm".to_string()),
-                ("CarvingMasks".to_string(), "cm".to_string()),
-                ("blending_data".to_string(), "bd".to_string()),
-                ("structures".to_string(), "st".to_string()),
-                // section 字段
-                ("BlockLight".to_string(), "bl".to_string()),
-                ("SkyLight".to_string(), "skl".to_string()),
-                ("biomes".to_string(), "bio".to_string()),
-                ("palette".to_string(), "pal".to_string()),
-                // starlight mod 字段
-                (
-                    "starlight.blocklight_state".to_string(),
-                    "sl.bls".to_string(),
-                ),
-                ("starlight.skylight_state".to_string(), "sl.sls".to_string()),
-                ("starlight.light_version".to_string(), "sl.lv".to_string()),
-            ],
+            use_builtins: true,
+            mappings: Vec::new(),
         }
     }
 }
 
 // ============== 配置加载 ==============
 
+/// 配置文件检查结果（见 [`Config::check_file`]）
+#[derive(Debug, Clone, Default)]
+pub struct ConfigCheck {
+    /// 会导致加载失败、或导出和还原失败的错误
+    pub errors: Vec<String>,
+    /// 可能不符合预期的配置
+    pub warnings: Vec<String>,
+}
+
+impl ConfigCheck {
+    /// 是否没有错误（可以有警告）
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
 impl Config {
-    /// 从文件加载配置
+    /// 从文件加载配置（按扩展名识别 TOML/JSON/YAML）
     pub fn load_from_file(path: &Path) -> Result<Self> {
-        let content = fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&content)?;
+        let config = Self::parse_file(path)?;
+        config.check()?;
+        Ok(config)
+    }
+
+    /// 检查字段列表、默认值和字段映射，在第一个错误处停止
+    fn check(&self) -> Result<()> {
+        self.denoise.check_field_patterns()?;
+        self.restore.default_values()?;
+        self.field_mapping.check_aliases()?;
+        Ok(())
+    }
+
+    /// 将 `[profiles.<name>]` 覆盖到当前配置上，返回新配置
+    ///
+    /// 方案中的字段优先，未列出的字段沿用当前配置；表逐层合并，列表等其它值整体替换。
+    /// 返回的配置不再包含 `profiles`
+    pub fn with_profile(&self, name: &str) -> Result<Config> {
+        let config = self.merge_profile(name)?;
+        config
+            .check()
+            .with_context(|| format!("配置方案 {} 无效", name))?;
+        Ok(config)
+    }
+
+    /// 合并配置方案，不检查字段列表等内容
+    fn merge_profile(&self, name: &str) -> Result<Config> {
+        let Some(profile) = self.profiles.get(name) else {
+            if self.profiles.is_empty() {
+                anyhow::bail!("配置方案 {} 不存在（配置中没有 [profiles]）", name);
+            }
+            let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            anyhow::bail!("配置方案 {} 不存在，可用的方案: {}", name, names.join(", "));
+        };
+
+        let base = Config {
+            profiles: BTreeMap::new(),
+            ..self.clone()
+        };
+        base.overlaid(profile.clone())
+            .with_context(|| format!("配置方案 {} 无效", name))
+    }
+
+    /// 将 `other` 中与默认值不同的字段覆盖到当前配置上：表逐层合并，列表（如去噪的 `fields`）整体替换而不是拼接
+    ///
+    /// 只能判断字段是否与默认值相同，因此 `other` 无法将字段改回默认值；
+    /// 按配置文件中实际写出的字段分层时使用 [`Config::load_layered`]
+    pub fn merge(&mut self, other: Config) -> Result<()> {
+        let other = serde_json::to_value(&other)?;
+        let default = serde_json::to_value(Config::default())?;
+        if let Some(overlay) = json_diff(other, &default) {
+            *self = self.overlaid(overlay)?;
+        }
+        Ok(())
+    }
+
+    /// 将 JSON 形式的部分配置覆盖到当前配置上，返回新配置
+    fn overlaid(&self, overlay: serde_json::Value) -> Result<Config> {
+        let mut value = serde_json::to_value(self)?;
+        merge_json(&mut value, overlay);
+        let mut config: Config = serde_json::from_value(value)?;
+        // 只由命令行设置的字段不参与序列化，沿用当前配置
+        config.export.dry_run = self.export.dry_run;
+        config.restore.existing_output = self.restore.existing_output;
+        Ok(config)
+    }
+
+    /// 依次合并多个配置文件，后面的文件优先
+    ///
+    /// 按各文件中实际写出的字段合并（表逐层合并，列表等其它值整体替换），
+    /// 未写出的字段沿用前面的文件或默认值
+    pub fn load_layered(paths: &[PathBuf]) -> Result<Self> {
+        let mut value = serde_json::Value::Object(Default::default());
+        for path in paths {
+            let layer = Self::read_file_value(path)
+                .with_context(|| format!("无法读取配置 {}", path.display()))?;
+            merge_json(&mut value, layer);
+        }
+        Self::default().overlaid_checked(value)
+    }
+
+    /// 覆盖后检查字段列表、默认值和字段映射
+    fn overlaid_checked(&self, overlay: serde_json::Value) -> Result<Config> {
+        let config = self.overlaid(overlay)?;
+        config.check()?;
         Ok(config)
     }
 
-    /// 保存配置到文件
+    /// 读取配置文件内容（不转换为配置，未写出的字段不会出现）
+    fn read_file_value(path: &Path) -> Result<serde_json::Value> {
+        let content = fs::read_to_string(path)?;
+        Ok(match ConfigFormat::from_path(path) {
+            ConfigFormat::Toml => toml::from_str(&content)?,
+            ConfigFormat::Json => serde_json::from_str(&content)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(&content)?,
+        })
+    }
+
+    /// 检查配置文件，列出其中的所有错误和警告（不回退到默认配置）
+    pub fn check_file(path: &Path) -> ConfigCheck {
+        match Self::parse_file(path) {
+            Ok(config) => {
+                let mut check = ConfigCheck {
+                    errors: config.errors(),
+                    warnings: config.validate(),
+                };
+                for name in config.profiles.keys() {
+                    let prefix = format!("profiles.{}", name);
+                    match config.merge_profile(name) {
+                        Ok(profile) => {
+                            let errors = profile.errors().into_iter();
+                            let warnings = profile.validate().into_iter();
+                            check
+                                .errors
+                                .extend(errors.map(|e| format!("{}: {}", prefix, e)));
+                            check
+                                .warnings
+                                .extend(warnings.map(|w| format!("{}: {}", prefix, w)));
+                        }
+                        Err(e) => check.errors.push(format!("{:#}", e)),
+                    }
+                }
+                check
+            }
+            Err(e) => ConfigCheck {
+                errors: vec![format!("{:#}", e)],
+                warnings: Vec::new(),
+            },
+        }
+    }
+
+    /// 读取并解析配置文件，不检查字段列表等内容
+    fn parse_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        // 先按配置结构解析，错误信息中带有行号
+        let _: Config = match ConfigFormat::from_path(path) {
+            ConfigFormat::Toml => toml::from_str(&content)?,
+            ConfigFormat::Json => serde_json::from_str(&content)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(&content)?,
+        };
+        Self::default().overlaid(Self::read_file_value(path)?)
+    }
+
+    /// 保存配置到文件（按扩展名选择格式）
     pub fn save_to_file(&self, path: &Path) -> Result<()> {
-        let content = toml::to_string_pretty(self)?;
+        self.save_to_file_with_format(path, ConfigFormat::from_path(path))
+    }
+
+    /// 以指定格式保存配置到文件
+    pub fn save_to_file_with_format(&self, path: &Path, format: ConfigFormat) -> Result<()> {
+        let content = match format {
+            ConfigFormat::Toml => toml::to_string_pretty(self)?,
+            ConfigFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+            ConfigFormat::Yaml => serde_yaml::to_string(self)?,
+        };
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
@@ -226,32 +1080,203 @@ impl Config {
         dirs::config_dir().map(|p| p.join("mcj").join("config.toml"))
     }
 
-    /// 按优先级加载配置：
-    /// 1. 当前目录的 mcj.toml
+    /// 分层加载配置：
+    /// 1. 默认配置
     /// 2. 用户配置目录的 config.toml
-    /// 3. 默认配置
+    /// 3. 当前目录的 mcj.toml、mcj.json、mcj.yaml（只使用第一个存在的文件）
+    ///
+    /// 后面的文件覆盖前面文件中的同名字段（见 [`Config::load_layered`]），
+    /// 加载后应用环境变量覆盖（见 [`Config::apply_env_overrides`]）
     pub fn load() -> Self {
-        // 当前目录
-        let local_config = Path::new("mcj.toml");
-        if local_config.exists() {
-            if let Ok(config) = Self::load_from_file(local_config) {
-                eprintln!("已加载配置: mcj.toml");
-                return config;
+        let mut config = Self::load_from_default_paths();
+        config.apply_env_overrides();
+        config
+    }
+
+    /// 与 [`Config::load`] 相同，但不应用环境变量覆盖
+    ///
+    /// 无法读取或合并后无效的文件输出警告后跳过（当前目录的配置无效时沿用用户配置）
+    pub fn load_from_default_paths() -> Self {
+        let user_config = Self::default_config_path().filter(|path| path.exists());
+        let local_config = ["mcj.toml", "mcj.json", "mcj.yaml"]
+            .into_iter()
+            .map(PathBuf::from)
+            .find(|path| path.exists());
+        let paths: Vec<PathBuf> = user_config.into_iter().chain(local_config).collect();
+        Self::load_layered_lenient(&paths)
+    }
+
+    /// 与 [`Config::load_layered`] 相同，但跳过无法读取或合并后无效的文件，而不是返回错误
+    fn load_layered_lenient(paths: &[PathBuf]) -> Self {
+        let mut value = serde_json::Value::Object(Default::default());
+        let mut config = Self::default();
+        let mut loaded = Vec::new();
+        for path in paths {
+            let layer = match Self::read_file_value(path) {
+                Ok(layer) => layer,
+                Err(e) => {
+                    warn!("无法加载配置 {}: {:#}", path.display(), e);
+                    continue;
+                }
+            };
+            let mut merged = value.clone();
+            merge_json(&mut merged, layer);
+            match Self::default().overlaid_checked(merged.clone()) {
+                Ok(next) => {
+                    value = merged;
+                    config = next;
+                    loaded.push(path.display().to_string());
+                }
+                Err(e) => warn!("无法加载配置 {}: {:#}", path.display(), e),
             }
         }
+        if !loaded.is_empty() {
+            info!("已加载配置: {}", loaded.join(" + "));
+        }
+        config
+    }
+
+    /// 用环境变量覆盖配置中的值：
+    ///
+    /// - `MCJ_EXPORT_DENOISE`：`export.denoise`
+    /// - `MCJ_EXPORT_AGGRESSIVE`：`export.aggressive`
+    /// - `MCJ_RESTORE_DEFAULTS`：`restore.restore_defaults`
+    /// - `MCJ_THREADS`：`performance.threads`
+    ///
+    /// 布尔值接受 `true`/`false`、`1`/`0`、`yes`/`no`、`on`/`off`；
+    /// 无法解析的值输出警告并保留原值
+    pub fn apply_env_overrides(&mut self) {
+        if let Some(value) = env_override(ENV_EXPORT_DENOISE, parse_env_bool) {
+            self.export.denoise = value;
+        }
+        if let Some(value) = env_override(ENV_EXPORT_AGGRESSIVE, parse_env_bool) {
+            self.export.aggressive = value;
+        }
+        if let Some(value) = env_override(ENV_RESTORE_DEFAULTS, parse_env_bool) {
+            self.restore.restore_defaults = value;
+        }
+        if let Some(threads) = env_override(ENV_THREADS, |v| v.parse::<usize>().ok()) {
+            self.performance.threads = threads;
+        }
+    }
+
+    /// 检查配置中会导致导出或还原失败的错误
+    ///
+    /// 与 [`Config::load_from_file`] 不同，会列出所有字段列表中的错误，而不是在第一个错误处停止
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (name, fields) in self.denoise.field_lists() {
+            if let Err(e) = check_field_patterns(fields) {
+                errors.push(format!("{}: {:#}", name, e));
+            }
+        }
+        // 白名单模式只按 keep_fields 保留字段，改动 fields 不会生效
+        let chunk = &self.denoise.chunk;
+        if chunk.mode == DenoiseMode::Whitelist
+            && chunk.fields != ChunkDenoiseConfig::default().fields
+        {
+            errors.push(
+                "denoise.chunk.fields 在白名单模式下不生效，请使用 keep_fields 列出要保留的字段"
+                    .to_string(),
+            );
+        }
+        for (dim, overlay) in &self.denoise.dimensions {
+            let mode = overlay.mode.unwrap_or(chunk.mode);
+            if mode == DenoiseMode::Whitelist && overlay.fields.is_some() {
+                errors.push(format!(
+                    "denoise.dimensions.\"{}\".fields 在白名单模式下不生效，请使用 keep_fields 列出要保留的字段",
+                    dim
+                ));
+            }
+        }
+        if let Err(e) = self.restore.default_values() {
+            errors.push(format!("{:#}", e));
+        }
+        if let Err(e) = self.field_mapping.check_aliases() {
+            errors.push(format!("field_mapping: {:#}", e));
+        }
+        if self.export.layout == ExportLayout::Sliced {
+            if let Err(e) = self.export.check_slice_size() {
+                errors.push(format!("export.{:#}", e));
+            }
+        }
+        if let Err(e) = self.restore.compression.check() {
+            errors.push(format!("restore.{:#}", e));
+        }
+        errors
+    }
 
-        // 用户配置目录
-        if let Some(user_config) = Self::default_config_path() {
-            if user_config.exists() {
-                if let Ok(config) = Self::load_from_file(&user_config) {
-                    eprintln!("已加载配置: {}", user_config.display());
-                    return config;
+    /// 检查配置中可能的问题，返回警告信息
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (name, fields) in self.denoise.field_lists() {
+            let mut seen = HashSet::new();
+            for field in fields {
+                if !seen.insert(field) {
+                    warnings.push(format!("{} 中字段 {} 出现多次", name, field));
                 }
             }
         }
+        // 维度按合并后的配置检查
+        let chunks = std::iter::once((
+            "denoise.chunk".to_string(),
+            Cow::Borrowed(&self.denoise.chunk),
+        ))
+        .chain(self.denoise.dimensions.keys().map(|dim| {
+            (
+                format!("denoise.dimensions.\"{}\"", dim),
+                self.denoise.chunk_config(Some(dim)),
+            )
+        }));
+        for (section, chunk) in chunks {
+            match chunk.mode {
+                DenoiseMode::Blacklist if !chunk.keep_fields.is_empty() => {
+                    warnings.push(format!(
+                        "{} 为黑名单模式，keep_fields 只在白名单模式下生效",
+                        section
+                    ));
+                }
+                DenoiseMode::Whitelist => {
+                    for field in chunk
+                        .keep_fields
+                        .iter()
+                        .filter(|f| chunk.aggressive_fields.contains(f))
+                    {
+                        warnings.push(format!(
+                            "字段 {} 同时出现在 {}.keep_fields 和 aggressive_fields 中，激进模式下仍会被移除",
+                            field, section
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        let chunk = &self.denoise.chunk;
+        if chunk.mode == DenoiseMode::Whitelist && chunk.keep_fields.is_empty() {
+            warnings.push(
+                "denoise.chunk.mode = \"whitelist\" 但 keep_fields 为空，将只保留结构性字段"
+                    .to_string(),
+            );
+        }
+        for dim in self.denoise.dimensions.keys() {
+            let chunk = self.denoise.chunk_config(Some(dim));
+            if chunk.mode == DenoiseMode::Whitelist && chunk.keep_fields.is_empty() {
+                warnings.push(format!(
+                    "denoise.dimensions.\"{}\" 为白名单模式但 keep_fields 为空，将只保留结构性字段",
+                    dim
+                ));
+            }
+        }
+        if self.export.slice_mode == SliceMode::Stable && self.export.chunks_per_slice == 0 {
+            warnings.push("export.chunks_per_slice 为 0，将按 1 处理".to_string());
+        }
+        warnings
+    }
 
-        // 默认配置
-        Self::default()
+    /// 生成配置文件的 JSON Schema（字段说明来自文档注释）
+    pub fn json_schema() -> String {
+        let schema = schemars::schema_for!(Config);
+        serde_json::to_string_pretty(&schema).unwrap_or_default()
     }
 
     /// 生成默认配置文件内容
@@ -259,6 +1284,165 @@ impl Config {
         let config = Self::default();
         toml::to_string_pretty(&config).unwrap_or_default()
     }
+
+    /// 以默认配置为基础构建配置
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+/// 覆盖 `export.denoise` 的环境变量
+pub const ENV_EXPORT_DENOISE: &str = "MCJ_EXPORT_DENOISE";
+/// 覆盖 `export.aggressive` 的环境变量
+pub const ENV_EXPORT_AGGRESSIVE: &str = "MCJ_EXPORT_AGGRESSIVE";
+/// 覆盖 `restore.restore_defaults` 的环境变量
+pub const ENV_RESTORE_DEFAULTS: &str = "MCJ_RESTORE_DEFAULTS";
+/// 覆盖所有线程数配置的环境变量
+pub const ENV_THREADS: &str = "MCJ_THREADS";
+
+/// 读取并解析环境变量，未设置时返回 `None`，无法解析时输出警告并返回 `None`
+fn env_override<T>(name: &str, parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+    let value = std::env::var(name).ok()?;
+    let parsed = parse(value.trim());
+    if parsed.is_none() {
+        warn!("环境变量 {}={:?} 无效，使用配置文件中的值", name, value);
+    }
+    parsed
+}
+
+fn parse_env_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// `value` 中与 `default` 不同的部分，完全相同时返回 `None`
+fn json_diff(value: serde_json::Value, default: &serde_json::Value) -> Option<serde_json::Value> {
+    match (value, default) {
+        (serde_json::Value::Object(value), serde_json::Value::Object(default)) => {
+            let diff: serde_json::Map<_, _> = value
+                .into_iter()
+                .filter_map(|(key, value)| match default.get(&key) {
+                    Some(default) => json_diff(value, default).map(|diff| (key, diff)),
+                    None => Some((key, value)),
+                })
+                .collect();
+            (!diff.is_empty()).then_some(serde_json::Value::Object(diff))
+        }
+        (value, default) => (value != *default).then_some(value),
+    }
+}
+
+/// 将 `overlay` 合并到 `target`：对象逐个键合并，其它值整体替换
+fn merge_json(target: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (target, overlay) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, overlay) => *target = overlay,
+    }
+}
+
+/// 配置构建器（用于在代码中构造配置）
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// 默认启用去噪
+    pub fn denoise(mut self, denoise: bool) -> Self {
+        self.config.export.denoise = denoise;
+        self
+    }
+
+    /// 默认启用激进模式
+    pub fn aggressive(mut self, aggressive: bool) -> Self {
+        self.config.export.aggressive = aggressive;
+        self
+    }
+
+    /// 导出和还原使用的区块数据格式
+    pub fn format(mut self, format: SliceFormat) -> Self {
+        self.config.export.format = format;
+        self.config.restore.format = format;
+        self
+    }
+
+    /// 还原时恢复默认值
+    pub fn restore_defaults(mut self, restore_defaults: bool) -> Self {
+        self.config.restore.restore_defaults = restore_defaults;
+        self
+    }
+
+    /// 并行处理使用的线程数（0 表示使用所有 CPU）
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.config.performance.threads = threads;
+        self
+    }
+
+    /// 追加区块去噪字段（保留默认字段）
+    pub fn add_chunk_noise_field(mut self, field: impl Into<String>) -> Self {
+        let field = field.into();
+        if !self.config.denoise.chunk.fields.contains(&field) {
+            self.config.denoise.chunk.fields.push(field);
+        }
+        self
+    }
+
+    /// 替换全部区块去噪字段
+    pub fn replace_chunk_noise_fields<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.denoise.chunk.fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 追加激进模式额外移除的区块字段
+    pub fn add_aggressive_field(mut self, field: impl Into<String>) -> Self {
+        let field = field.into();
+        if !self.config.denoise.chunk.aggressive_fields.contains(&field) {
+            self.config.denoise.chunk.aggressive_fields.push(field);
+        }
+        self
+    }
+
+    /// 去噪 level.dat 时重置天气状态
+    pub fn reset_weather(mut self, reset_weather: bool) -> Self {
+        self.config.denoise.level.reset_weather = reset_weather;
+        self
+    }
+
+    /// 生成配置，同一字段不能同时出现在 `fields` 和 `aggressive_fields` 中，
+    /// 字段列表中的正则表达式必须有效
+    pub fn build(self) -> Result<Config> {
+        self.config.denoise.check_field_patterns()?;
+        self.config.restore.default_values()?;
+        self.config.field_mapping.check_aliases()?;
+        let chunk = &self.config.denoise.chunk;
+        if let Some(field) = chunk
+            .fields
+            .iter()
+            .find(|f| chunk.aggressive_fields.contains(f))
+        {
+            anyhow::bail!(
+                "字段 {} 同时出现在 denoise.chunk.fields 和 aggressive_fields 中",
+                field
+            );
+        }
+        Ok(self.config)
+    }
 }
 
 // ============== 工作区配置（vigrid/workspace.yml） ==============
@@ -320,9 +1504,298 @@ impl WorkspaceConfig {
         let config: WorkspaceConfig = serde_yaml::from_str(&content).ok()?;
 
         if config.area.is_some() {
-            eprintln!("已加载工作区配置: {}", workspace_file.display());
+            info!("已加载工作区配置: {}", workspace_file.display());
         }
 
         Some(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn slice_size_outside_range_is_an_error() {
+        let mut config = Config::default();
+        assert_eq!(config.export.max_slice_size_bytes(), DEFAULT_MAX_SLICE_SIZE);
+        for mb in [0.05, 64.5, f64::NAN] {
+            config.export.max_slice_size_mb = mb;
+            assert_eq!(config.errors().len(), 1, "{}", mb);
+        }
+        config.export.max_slice_size_mb = 0.1;
+        assert!(config.errors().is_empty());
+    }
+
+    #[test]
+    fn empty_chunk_policy_accepts_pascal_case_names() {
+        for (name, policy) in [
+            ("RequireSections", EmptyChunkPolicy::RequireSections),
+            (
+                "RequireSectionsOrBlockEntities",
+                EmptyChunkPolicy::RequireSectionsOrBlockEntities,
+            ),
+            ("RequireAnyField", EmptyChunkPolicy::RequireAnyField),
+            ("Never", EmptyChunkPolicy::Never),
+            ("require_any_field", EmptyChunkPolicy::RequireAnyField),
+        ] {
+            let config: Config =
+                toml::from_str(&format!("[export]\nempty_chunk_policy = \"{}\"", name)).unwrap();
+            assert_eq!(config.export.empty_chunk_policy, policy);
+        }
+        let value = serde_json::to_value(EmptyChunkPolicy::RequireAnyField).unwrap();
+        assert_eq!(value, "require_any_field");
+    }
+
+    #[test]
+    fn profile_overriding_one_field_keeps_the_rest() {
+        let config: Config = toml::from_str(
+            r#"
+            [export]
+            chunks_per_slice = 7
+            included_statuses = ["minecraft:full", "minecraft:features"]
+
+            [profiles.ci.export]
+            aggressive = true
+            "#,
+        )
+        .unwrap();
+        assert!(!config.export.aggressive);
+
+        let ci = config.with_profile("ci").unwrap();
+        assert!(ci.export.aggressive);
+        assert_eq!(ci.export.chunks_per_slice, 7);
+        assert_eq!(ci.export.included_statuses, config.export.included_statuses);
+        assert_eq!(ci.export.max_slice_size_mb, config.export.max_slice_size_mb);
+        assert!(ci.profiles.is_empty());
+    }
+
+    #[test]
+    fn dimension_section_fields_replace_when_set() {
+        let config = Config::default()
+            .overlaid(json!({
+                "denoise": {
+                    "chunk": { "section_fields": ["SkyLight"] },
+                    "dimensions": {
+                        "DIM-1": { "section_fields": [] },
+                        "DIM1": { "section_fields": ["BlockLight"], "fields": ["CarvingMasks"] },
+                        "custom": { "strip_timestamp": true },
+                    },
+                },
+            }))
+            .unwrap();
+        let denoise = &config.denoise;
+        assert!(denoise
+            .chunk_config(Some("DIM-1"))
+            .section_fields
+            .is_empty());
+        assert_eq!(
+            denoise.chunk_config(Some("DIM1")).section_fields,
+            ["BlockLight"]
+        );
+
+        // 未写出的项沿用基础配置，字段列表不会混入默认值
+        let custom = denoise.chunk_config(Some("custom"));
+        assert_eq!(custom.section_fields, ["SkyLight"]);
+        assert_eq!(custom.fields, denoise.chunk.fields);
+        assert!(custom.strip_timestamp);
+
+        let end = denoise.chunk_config(Some("DIM1"));
+        assert_eq!(end.fields.last().map(String::as_str), Some("CarvingMasks"));
+        assert_eq!(end.fields.len(), denoise.chunk.fields.len() + 1);
+    }
+
+    #[test]
+    fn whitelist_fields_are_reported() {
+        let config = Config::default()
+            .overlaid(json!({ "denoise": { "chunk": { "mode": "whitelist" } } }))
+            .unwrap();
+        assert!(config.errors().is_empty(), "{:?}", config.errors());
+
+        let config = Config::default()
+            .overlaid(json!({
+                "denoise": {
+                    "chunk": { "mode": "whitelist", "fields": ["LastUpdate"] },
+                    "dimensions": { "DIM1": { "fields": ["InhabitedTime"] } },
+                },
+            }))
+            .unwrap();
+        let errors = config.errors();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].starts_with("denoise.chunk.fields"));
+        assert!(errors[1].starts_with("denoise.dimensions.\"DIM1\".fields"));
+    }
+
+    /// 设置环境变量，离开作用域时恢复原值；持有锁，避免同时运行的测试互相覆盖
+    struct EnvGuard {
+        saved: Vec<(&'static str, Option<std::ffi::OsString>)>,
+        _lock: std::sync::MutexGuard<'static, ()>,
+    }
+
+    impl EnvGuard {
+        fn set(vars: &[(&'static str, &str)]) -> Self {
+            static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+            let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let saved = vars
+                .iter()
+                .map(|&(name, value)| {
+                    let old = std::env::var_os(name);
+                    std::env::set_var(name, value);
+                    (name, old)
+                })
+                .collect();
+            EnvGuard { saved, _lock: lock }
+        }
+    }
+
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            for (name, old) in self.saved.drain(..) {
+                match old {
+                    Some(value) => std::env::set_var(name, value),
+                    None => std::env::remove_var(name),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn env_overrides_replace_config_values() {
+        let _env = EnvGuard::set(&[
+            (ENV_EXPORT_DENOISE, "off"),
+            (ENV_EXPORT_AGGRESSIVE, " Yes "),
+            (ENV_RESTORE_DEFAULTS, "maybe"),
+            (ENV_THREADS, "3"),
+        ]);
+        let mut config = Config::default();
+        config.export.denoise = true;
+        config.export.aggressive = false;
+        config.restore.restore_defaults = true;
+        config.apply_env_overrides();
+        assert!(!config.export.denoise);
+        assert!(config.export.aggressive);
+        // 无法解析的值保留配置中的值
+        assert!(config.restore.restore_defaults);
+        assert_eq!(config.performance.threads, 3);
+    }
+
+    fn write_config(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    const USER_CONFIG: &str = r#"
+        [export]
+        chunks_per_slice = 7
+        aggressive = true
+
+        [denoise.chunk]
+        fields = ["A", "B"]
+
+        [performance]
+        threads = 2
+    "#;
+
+    #[test]
+    fn local_config_fields_override_user_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = write_config(dir.path(), "config.toml", USER_CONFIG);
+        // 改回默认值、替换列表
+        let local = write_config(
+            dir.path(),
+            "mcj.json",
+            r#"{ "export": { "aggressive": false }, "performance": { "threads": 4 }, "denoise": { "chunk": { "fields": ["C"] } } }"#,
+        );
+        let paths = [user, local];
+        for config in [
+            Config::load_layered(&paths).unwrap(),
+            Config::load_layered_lenient(&paths),
+        ] {
+            assert_eq!(config.export.chunks_per_slice, 7);
+            assert!(!config.export.aggressive);
+            assert_eq!(config.denoise.chunk.fields, ["C"]);
+            assert_eq!(config.performance.threads, 4);
+        }
+    }
+
+    #[test]
+    fn invalid_local_config_falls_back_to_user_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = write_config(dir.path(), "config.toml", USER_CONFIG);
+        let expected = Config::load_from_file(&user).unwrap();
+        for local in [
+            "[export]\naggressive = \"nope\"",
+            "[denoise.chunk]\nfields = [\"re:(\"]",
+            "[export",
+        ] {
+            let local = write_config(dir.path(), "mcj.toml", local);
+            let paths = [user.clone(), local];
+            assert!(Config::load_layered(&paths).is_err());
+            let config = Config::load_layered_lenient(&paths);
+            assert_eq!(
+                config.export.chunks_per_slice,
+                expected.export.chunks_per_slice
+            );
+            assert!(config.export.aggressive);
+            assert_eq!(config.denoise.chunk.fields, expected.denoise.chunk.fields);
+        }
+        let missing = dir.path().join("missing.toml");
+        let config = Config::load_layered_lenient(&[missing, user]);
+        assert_eq!(config.export.chunks_per_slice, 7);
+    }
+
+    #[test]
+    fn merge_overrides_fields_and_replaces_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let user_path = write_config(dir.path(), "config.toml", USER_CONFIG);
+        let local_path = write_config(
+            dir.path(),
+            "mcj.toml",
+            r#"
+            [export]
+            format = "snbt"
+
+            [denoise.chunk]
+            fields = ["C"]
+
+            [performance]
+            threads = 4
+            "#,
+        );
+        let user = Config::load_from_file(&user_path).unwrap();
+        let local = Config::load_from_file(&local_path).unwrap();
+
+        let mut merged = user.clone();
+        merged.merge(local.clone()).unwrap();
+        assert_eq!(merged.export.format, SliceFormat::Snbt);
+        assert_eq!(merged.denoise.chunk.fields, ["C"]);
+        assert_eq!(merged.performance.threads, 4);
+        // local 中未改动的字段沿用 user
+        assert_eq!(merged.export.chunks_per_slice, 7);
+        assert!(merged.export.aggressive);
+        assert_eq!(
+            merged.denoise.chunk.aggressive_fields,
+            user.denoise.chunk.aggressive_fields
+        );
+        // 不改回默认值时与按文件分层加载的结果相同
+        let layered = Config::load_layered(&[user_path, local_path]).unwrap();
+        assert_eq!(
+            serde_json::to_value(&merged).unwrap(),
+            serde_json::to_value(&layered).unwrap()
+        );
+
+        // 反过来合并时 user 优先
+        let mut reversed = local;
+        reversed.merge(user).unwrap();
+        assert_eq!(reversed.denoise.chunk.fields, ["A", "B"]);
+        assert_eq!(reversed.performance.threads, 2);
+        assert_eq!(reversed.export.format, SliceFormat::Snbt);
+
+        // 与默认值相同的字段无法覆盖
+        let mut config = merged.clone();
+        config.merge(Config::default()).unwrap();
+        assert!(config.export.aggressive);
+    }
+}
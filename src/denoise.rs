@@ -1,7 +1,12 @@
 //! 去噪声处理 - 移除运行时变化的字段
 
-use crate::config::DenoiseConfig;
+use crate::config::{DenoiseConfig, DenoiseMode};
+use crate::nbt_json::nbt_to_json;
+use anyhow::{Context, Result};
 use fastnbt::Value;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 
 /// 区块级噪声字段（默认值，用于向后兼容）
 pub const CHUNK_NOISE_FIELDS: &[&str] = &[
@@ -21,12 +26,192 @@ pub const CHUNK_NOISE_FIELDS: &[&str] = &[
 pub const CHUNK_AGGRESSIVE_FIELDS: &[&str] =
     &["Heightmaps", "fluid_ticks", "block_ticks", "structures"];
 
-/// Section 级别需要移除的光照字段（默认移除，让游戏重新计算）
+/// Section 级别需要移除的光照字段（默认移除，让游戏重新计算，即 `section_fields` 的默认值）
 pub const SECTION_LIGHT_FIELDS: &[&str] = &["BlockLight", "SkyLight"];
 
-/// Section 级激进去噪字段（额外的）
+/// Section 级激进去噪字段（额外的，即 `section_aggressive_fields` 的默认值）
 pub const SECTION_AGGRESSIVE_FIELDS: &[&str] = &[];
 
+/// 实体级噪声字段（每刻都会变化的运动状态，即 `[denoise.entity] fields` 的默认值）
+pub const ENTITY_NOISE_FIELDS: &[&str] = &[
+    "Motion",
+    "FallDistance",
+    "Air",
+    "PortalCooldown",
+    "HurtTime",
+];
+
+/// 实体级激进去噪字段（随机生成的 UUID，即 `[denoise.entity] aggressive_fields` 的默认值），
+/// 移除后游戏加载时会重新生成
+pub const ENTITY_AGGRESSIVE_FIELDS: &[&str] = &["UUID", "UUIDMost", "UUIDLeast"];
+
+/// 区块（或实体区块）中实体列表的字段名
+const ENTITIES_KEY: &str = "Entities";
+
+/// 白名单模式下始终保留的结构性字段
+pub const CHUNK_STRUCTURAL_FIELDS: &[&str] = &[
+    "DataVersion",
+    "sections",
+    "block_entities",
+    "Status",
+    "xPos",
+    "zPos",
+    "yPos",
+    // 1.13 之前的区块没有 Status，由它判断是否完整生成
+    "TerrainPopulated",
+];
+
+/// 1.18 之前的区块数据位于该复合标签中：`{DataVersion, Level: {xPos, Sections, ...}}`
+pub const LEGACY_LEVEL_KEY: &str = "Level";
+
+/// 1.18 之前区块中字段的旧名称（新名称 -> 旧名称），其余字段名称相同
+const LEGACY_FIELD_NAMES: &[(&str, &str)] = &[
+    ("sections", "Sections"),
+    ("block_entities", "TileEntities"),
+    ("block_ticks", "TileTicks"),
+    ("fluid_ticks", "LiquidTicks"),
+    ("structures", "Structures"),
+];
+
+/// 字段在 1.18 之前区块中的名称
+pub(crate) fn legacy_field_name(field: &str) -> &str {
+    LEGACY_FIELD_NAMES
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map_or(field, |(_, legacy)| legacy)
+}
+
+/// 区块中存放实际数据的复合标签，以及是否为 1.18 之前的格式
+///
+/// 1.18 之前为 `Level` 标签，之后为区块根标签
+pub(crate) fn chunk_body_mut(
+    root: &mut HashMap<String, Value>,
+) -> (&mut HashMap<String, Value>, bool) {
+    if matches!(root.get(LEGACY_LEVEL_KEY), Some(Value::Compound(_))) {
+        match root.get_mut(LEGACY_LEVEL_KEY) {
+            Some(Value::Compound(level)) => (level, true),
+            _ => unreachable!(),
+        }
+    } else {
+        (root, false)
+    }
+}
+
+/// 只读版本的 [`chunk_body_mut`]
+pub(crate) fn chunk_body(root: &HashMap<String, Value>) -> (&HashMap<String, Value>, bool) {
+    match root.get(LEGACY_LEVEL_KEY) {
+        Some(Value::Compound(level)) => (level, true),
+        _ => (root, false),
+    }
+}
+
+/// 字段列表中以该前缀开头的项为正则表达式，如 `re:^cofh_\d+$`
+pub const REGEX_FIELD_PREFIX: &str = "re:";
+
+/// 去噪字段列表中的一项
+///
+/// - `re:` 开头：正则表达式，在字段名中搜索匹配（需要整体匹配时使用 `^...$`）
+/// - 包含 `*`：通配符，`*` 匹配任意字符，如 `forge:cap_*`
+/// - 其余：精确匹配字段名（1.18 之前的区块使用旧名称），带 `.` 的字段名为嵌套路径，
+///   如 `starlight.skylight_state`（白名单模式下只比较顶层字段）
+#[derive(Debug, Clone)]
+pub enum FieldPattern {
+    Exact(String),
+    Pattern(Regex),
+}
+
+impl FieldPattern {
+    /// 解析字段列表中的一项，正则表达式无效时返回错误
+    pub fn parse(field: &str) -> Result<Self> {
+        if let Some(pattern) = field.strip_prefix(REGEX_FIELD_PREFIX) {
+            let regex = Regex::new(pattern)
+                .with_context(|| format!("字段 \"{}\" 不是有效的正则表达式", field))?;
+            Ok(Self::Pattern(regex))
+        } else if field.contains('*') {
+            let pattern = field
+                .split('*')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".*");
+            let regex = Regex::new(&format!("^{}$", pattern))
+                .with_context(|| format!("字段 \"{}\" 不是有效的通配符", field))?;
+            Ok(Self::Pattern(regex))
+        } else {
+            Ok(Self::Exact(field.to_string()))
+        }
+    }
+
+    /// 字段名是否与该项匹配
+    pub fn matches(&self, key: &str) -> bool {
+        match self {
+            Self::Exact(field) => field == key,
+            Self::Pattern(regex) => regex.is_match(key),
+        }
+    }
+}
+
+/// 检查字段列表中的正则表达式和通配符是否都有效
+pub fn check_field_patterns<'a>(fields: impl IntoIterator<Item = &'a String>) -> Result<()> {
+    for field in fields {
+        FieldPattern::parse(field)?;
+    }
+    Ok(())
+}
+
+/// 编译字段列表中的所有项，任一正则表达式或通配符无效时返回错误
+fn compile_fields(fields: &[String]) -> Result<Vec<FieldPattern>> {
+    fields
+        .iter()
+        .map(|field| FieldPattern::parse(field))
+        .collect()
+}
+
+/// 字段名是否与字段列表中的一项匹配，`name` 为精确字段名在当前区块中的实际名称
+fn pattern_matches(pattern: &FieldPattern, key: &str, name: impl Fn(&str) -> String) -> bool {
+    match pattern {
+        FieldPattern::Exact(field) => name(field) == key,
+        FieldPattern::Pattern(regex) => regex.is_match(key),
+    }
+}
+
+/// 移除与字段列表中的一项匹配的字段，返回用于统计的字段名
+///
+/// 精确字段名按配置中的名称统计，模式按实际移除的字段名统计
+fn remove_matching(
+    map: &mut HashMap<String, Value>,
+    pattern: &FieldPattern,
+    name: impl Fn(&str) -> String,
+) -> Vec<String> {
+    match pattern {
+        FieldPattern::Pattern(regex) => {
+            let keys: Vec<String> = map.keys().filter(|k| regex.is_match(k)).cloned().collect();
+            for key in &keys {
+                map.remove(key);
+            }
+            keys
+        }
+        FieldPattern::Exact(field) if remove_field(map, &name(field)) => vec![field.clone()],
+        FieldPattern::Exact(_) => Vec::new(),
+    }
+}
+
+/// 移除字段，返回是否存在该字段
+///
+/// 字段名中带 `.` 且没有同名字段时按路径进入嵌套的复合标签，
+/// 如 `starlight.skylight_state` 移除 `starlight` 中的 `skylight_state`
+fn remove_field(map: &mut HashMap<String, Value>, field: &str) -> bool {
+    if map.remove(field).is_some() {
+        return true;
+    }
+    let Some((parent, rest)) = field.split_once('.') else {
+        return false;
+    };
+    match map.get_mut(parent) {
+        Some(Value::Compound(child)) => remove_field(child, rest),
+        _ => false,
+    }
+}
+
 /// 存档级噪声字段（默认值）
 pub const LEVEL_NOISE_FIELDS: &[&str] = &[
     "Time",
@@ -44,52 +229,339 @@ pub const LEVEL_NOISE_FIELDS: &[&str] = &[
 ];
 
 /// 对区块进行去噪处理（使用默认字段）
+///
+/// 1.18 之前的区块对 `Level` 中的字段去噪，字段使用旧名称（如 `Sections`、`TileTicks`）
 pub fn denoise_chunk(value: &mut Value, aggressive: bool) {
-    if let Value::Compound(map) = value {
+    if let Value::Compound(root) = value {
+        let (map, legacy) = chunk_body_mut(root);
+        let name = |field: &'static str| {
+            if legacy {
+                legacy_field_name(field)
+            } else {
+                field
+            }
+        };
         for field in CHUNK_NOISE_FIELDS {
-            map.remove(*field);
+            remove_field(map, name(field));
         }
 
         // 默认移除 section 级别的光照数据（让游戏重新计算）
-        if let Some(Value::List(sections)) = map.get_mut("sections") {
+        let section_aggressive: &[&str] = if aggressive {
+            SECTION_AGGRESSIVE_FIELDS
+        } else {
+            &[]
+        };
+        if let Some(Value::List(sections)) = map.get_mut(name("sections")) {
             for section in sections.iter_mut() {
                 if let Value::Compound(sec_map) = section {
-                    for field in SECTION_LIGHT_FIELDS {
-                        sec_map.remove(*field);
+                    for field in SECTION_LIGHT_FIELDS.iter().chain(section_aggressive) {
+                        remove_field(sec_map, field);
                     }
                 }
             }
         }
 
+        if let Some(Value::List(entities)) = map.get_mut(ENTITIES_KEY) {
+            let entity_aggressive: &[&str] = if aggressive {
+                ENTITY_AGGRESSIVE_FIELDS
+            } else {
+                &[]
+            };
+            for_each_entity(entities, &mut |entity| {
+                for field in ENTITY_NOISE_FIELDS.iter().chain(entity_aggressive) {
+                    remove_field(entity, field);
+                }
+            });
+        }
+
         if aggressive {
             for field in CHUNK_AGGRESSIVE_FIELDS {
-                map.remove(*field);
+                remove_field(map, name(field));
             }
         }
     }
 }
 
+/// 去噪统计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DenoiseStats {
+    /// 各字段被移除的次数（section 字段以 `sections.` 为前缀）
+    pub fields_removed: BTreeMap<String, usize>,
+    /// 被过滤的空 section 数量
+    pub sections_removed: usize,
+    /// 被跳过的区块数量（无法去噪或未导出的区块）
+    pub chunks_skipped: usize,
+}
+
+impl DenoiseStats {
+    /// 记录一次字段移除
+    fn record(&mut self, field: &str) {
+        *self.fields_removed.entry(field.to_string()).or_insert(0) += 1;
+    }
+
+    /// 合并另一份统计
+    pub fn merge(&mut self, other: &DenoiseStats) {
+        for (field, count) in &other.fields_removed {
+            *self.fields_removed.entry(field.clone()).or_insert(0) += count;
+        }
+        self.sections_removed += other.sections_removed;
+        self.chunks_skipped += other.chunks_skipped;
+    }
+
+    /// 按移除次数降序排列的字段列表
+    pub fn sorted_fields(&self) -> Vec<(&str, usize)> {
+        let mut fields: Vec<(&str, usize)> = self
+            .fields_removed
+            .iter()
+            .map(|(k, &v)| (k.as_str(), v))
+            .collect();
+        fields.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        fields
+    }
+}
+
 /// 对区块进行去噪处理（使用配置）
-pub fn denoise_chunk_with_config(value: &mut Value, aggressive: bool, config: &DenoiseConfig) {
-    if let Value::Compound(map) = value {
-        for field in &config.chunk.fields {
-            map.remove(field);
+///
+/// `dimension` 为维度路径（如 `DIM-1`），存在对应的 `[denoise.dimensions]` 覆盖时合并使用；
+/// 字段列表中的正则表达式或通配符无效时返回错误。处理多个区块时使用 [`ChunkDenoiser`]，只编译一次
+pub fn denoise_chunk_with_config(
+    value: &mut Value,
+    aggressive: bool,
+    config: &DenoiseConfig,
+    dimension: Option<&str>,
+) -> Result<()> {
+    ChunkDenoiser::new(config, dimension)?.denoise(value, aggressive, None);
+    Ok(())
+}
+
+/// 对区块进行去噪处理（使用配置），并记录被移除的字段
+pub fn denoise_chunk_with_stats(
+    value: &mut Value,
+    aggressive: bool,
+    config: &DenoiseConfig,
+    dimension: Option<&str>,
+    stats: &mut DenoiseStats,
+) -> Result<()> {
+    ChunkDenoiser::new(config, dimension)?.denoise(value, aggressive, Some(stats));
+    Ok(())
+}
+
+/// 编译后的区块去噪配置：字段列表中的模式只编译一次，供同一维度的所有区块使用
+#[derive(Debug, Clone)]
+pub struct ChunkDenoiser {
+    mode: DenoiseMode,
+    fields: Vec<FieldPattern>,
+    keep_fields: Vec<FieldPattern>,
+    aggressive_fields: Vec<FieldPattern>,
+    section_fields: Vec<FieldPattern>,
+    section_aggressive_fields: Vec<FieldPattern>,
+    keep_heightmaps: Vec<String>,
+    entity_fields: Vec<FieldPattern>,
+    entity_aggressive_fields: Vec<FieldPattern>,
+    strip_timestamp: bool,
+}
+
+impl ChunkDenoiser {
+    /// 编译指定维度生效的区块去噪配置（见 [`DenoiseConfig::chunk_config`]）和实体去噪配置，
+    /// 正则表达式或通配符无效时返回错误
+    pub fn new(config: &DenoiseConfig, dimension: Option<&str>) -> Result<Self> {
+        let section = match dimension {
+            Some(dim) if config.dimensions.contains_key(dim) => {
+                format!("denoise.dimensions.\"{}\"", dim)
+            }
+            _ => "denoise.chunk".to_string(),
+        };
+        let chunk = config.chunk_config(dimension);
+        let compile = |name: String, fields: &[String]| {
+            compile_fields(fields).with_context(|| format!("{} 配置错误", name))
+        };
+        Ok(Self {
+            mode: chunk.mode,
+            fields: compile(format!("{}.fields", section), &chunk.fields)?,
+            keep_fields: compile(format!("{}.keep_fields", section), &chunk.keep_fields)?,
+            aggressive_fields: compile(
+                format!("{}.aggressive_fields", section),
+                &chunk.aggressive_fields,
+            )?,
+            section_fields: compile(format!("{}.section_fields", section), &chunk.section_fields)?,
+            section_aggressive_fields: compile(
+                format!("{}.section_aggressive_fields", section),
+                &chunk.section_aggressive_fields,
+            )?,
+            keep_heightmaps: chunk.keep_heightmaps.clone(),
+            entity_fields: compile("denoise.entity.fields".to_string(), &config.entity.fields)?,
+            entity_aggressive_fields: compile(
+                "denoise.entity.aggressive_fields".to_string(),
+                &config.entity.aggressive_fields,
+            )?,
+            strip_timestamp: chunk.strip_timestamp,
+        })
+    }
+
+    /// 导出时是否移除区块的时间戳（`strip_timestamp`）
+    pub fn strip_timestamp(&self) -> bool {
+        self.strip_timestamp
+    }
+
+    /// 对区块进行去噪处理，`stats` 不为 `None` 时记录被移除的字段
+    ///
+    /// 1.18 之前的区块对 `Level` 中的字段去噪，精确字段名使用旧名称
+    pub fn denoise(
+        &self,
+        value: &mut Value,
+        aggressive: bool,
+        mut stats: Option<&mut DenoiseStats>,
+    ) {
+        let Value::Compound(root) = value else {
+            // 不是 compound 的区块无法去噪
+            if let Some(stats) = stats {
+                stats.chunks_skipped += 1;
+            }
+            return;
+        };
+        let (map, legacy) = chunk_body_mut(root);
+        let name = |field: &str| -> String {
+            if legacy {
+                legacy_field_name(field).to_string()
+            } else {
+                field.to_string()
+            }
+        };
+        let mut record = |field: String| {
+            if let Some(stats) = stats.as_deref_mut() {
+                stats.record(&field);
+            }
+        };
+        match self.mode {
+            DenoiseMode::Blacklist => {
+                for pattern in &self.fields {
+                    remove_matching(map, pattern, name)
+                        .into_iter()
+                        .for_each(&mut record);
+                }
+            }
+            DenoiseMode::Whitelist => {
+                map.retain(|key, _| {
+                    let keep = CHUNK_STRUCTURAL_FIELDS.iter().any(|f| name(f) == *key)
+                        || self
+                            .keep_fields
+                            .iter()
+                            .any(|p| pattern_matches(p, key, name));
+                    if !keep {
+                        record(key.clone());
+                    }
+                    keep
+                });
+            }
         }
 
-        // 默认移除 section 级别的光照数据
-        if let Some(Value::List(sections)) = map.get_mut("sections") {
+        // section 级字段（默认为光照数据），激进模式下再加上 section_aggressive_fields
+        let section_aggressive: &[FieldPattern] = if aggressive {
+            &self.section_aggressive_fields
+        } else {
+            &[]
+        };
+        if let Some(Value::List(sections)) = map.get_mut(&name("sections")) {
             for section in sections.iter_mut() {
                 if let Value::Compound(sec_map) = section {
-                    for field in SECTION_LIGHT_FIELDS {
-                        sec_map.remove(*field);
+                    for pattern in self.section_fields.iter().chain(section_aggressive) {
+                        for removed in remove_matching(sec_map, pattern, str::to_string) {
+                            record(format!("sections.{}", removed));
+                        }
                     }
                 }
             }
         }
 
+        // 实体（及其乘客）的运动状态，激进模式下再加上 UUID
+        if let Some(Value::List(entities)) = map.get_mut(ENTITIES_KEY) {
+            self.denoise_entities(entities, aggressive, &mut record);
+        }
+
         if aggressive {
-            for field in &config.chunk.aggressive_fields {
-                map.remove(field);
+            // keep_heightmaps 不为空时只移除其中没有的高度图，而不是整个 Heightmaps
+            let heightmaps_key = name("Heightmaps");
+            let mut kept_heightmaps = None;
+            if !self.keep_heightmaps.is_empty()
+                && self
+                    .aggressive_fields
+                    .iter()
+                    .any(|p| pattern_matches(p, &heightmaps_key, name))
+            {
+                if let Some(Value::Compound(mut heightmaps)) = map.remove(&heightmaps_key) {
+                    heightmaps.retain(|key, _| {
+                        let keep = self.keep_heightmaps.contains(key);
+                        if !keep {
+                            record(format!("Heightmaps.{}", key));
+                        }
+                        keep
+                    });
+                    kept_heightmaps = Some(heightmaps);
+                }
+            }
+
+            for pattern in &self.aggressive_fields {
+                remove_matching(map, pattern, name)
+                    .into_iter()
+                    .for_each(&mut record);
+            }
+            if let Some(heightmaps) = kept_heightmaps.filter(|h| !h.is_empty()) {
+                map.insert(heightmaps_key, Value::Compound(heightmaps));
+            }
+        }
+    }
+
+    /// 对 `entities/` 中的实体区块去噪，只移除 `Entities` 中每个实体（及其乘客）的噪声字段
+    pub fn denoise_entity_chunk(
+        &self,
+        value: &mut Value,
+        aggressive: bool,
+        mut stats: Option<&mut DenoiseStats>,
+    ) {
+        let Value::Compound(root) = value else {
+            if let Some(stats) = stats {
+                stats.chunks_skipped += 1;
+            }
+            return;
+        };
+        if let Some(Value::List(entities)) = root.get_mut(ENTITIES_KEY) {
+            self.denoise_entities(entities, aggressive, &mut |field| {
+                if let Some(stats) = stats.as_deref_mut() {
+                    stats.record(&field);
+                }
+            });
+        }
+    }
+
+    /// 移除实体列表中每个实体（及其乘客）的噪声字段
+    fn denoise_entities(
+        &self,
+        entities: &mut [Value],
+        aggressive: bool,
+        record: &mut impl FnMut(String),
+    ) {
+        let entity_aggressive: &[FieldPattern] = if aggressive {
+            &self.entity_aggressive_fields
+        } else {
+            &[]
+        };
+        for_each_entity(entities, &mut |entity| {
+            for pattern in self.entity_fields.iter().chain(entity_aggressive) {
+                for removed in remove_matching(entity, pattern, str::to_string) {
+                    record(format!("Entities.{}", removed));
+                }
+            }
+        });
+    }
+}
+
+/// 对实体列表中的每个实体及其乘客（`Passengers`，可多层嵌套）调用 `f`
+fn for_each_entity(entities: &mut [Value], f: &mut impl FnMut(&mut HashMap<String, Value>)) {
+    for entity in entities {
+        if let Value::Compound(entity) = entity {
+            f(entity);
+            if let Some(Value::List(passengers)) = entity.get_mut("Passengers") {
+                for_each_entity(passengers, f);
             }
         }
     }
@@ -109,12 +581,13 @@ pub fn denoise_level(value: &mut Value) {
     }
 }
 
-/// 对 level.dat 进行去噪处理（使用配置）
-pub fn denoise_level_with_config(value: &mut Value, config: &DenoiseConfig) {
+/// 对 level.dat 进行去噪处理（使用配置），字段列表中的正则表达式或通配符无效时返回错误
+pub fn denoise_level_with_config(value: &mut Value, config: &DenoiseConfig) -> Result<()> {
+    let fields = compile_fields(&config.level.fields).context("denoise.level.fields 配置错误")?;
     if let Value::Compound(map) = value {
         if let Some(Value::Compound(data)) = map.get_mut("Data") {
-            for field in &config.level.fields {
-                data.remove(field);
+            for pattern in &fields {
+                remove_matching(data, pattern, str::to_string);
             }
             // 重置天气
             if config.level.reset_weather {
@@ -123,16 +596,397 @@ pub fn denoise_level_with_config(value: &mut Value, config: &DenoiseConfig) {
             }
         }
     }
+    Ok(())
 }
 
-/// 恢复区块的默认值（还原时使用）
+/// 还原时默认补全的区块字段及默认值（`[restore.defaults]` 的默认值）
+pub fn default_restore_values() -> Vec<(String, Value)> {
+    // isLightOn=0 让游戏重新计算光照（因为激进模式可能移除了光照数据）
+    vec![
+        ("LastUpdate".to_string(), Value::Long(0)),
+        ("InhabitedTime".to_string(), Value::Long(0)),
+        ("isLightOn".to_string(), Value::Byte(0)),
+    ]
+}
+
+/// 白名单模式移除的、区块加载时需要的字段，还原时补全为空值
+pub fn whitelist_restore_values() -> Vec<(String, Value)> {
+    let empty_compound = || Value::Compound(HashMap::new());
+    vec![
+        ("Heightmaps".to_string(), empty_compound()),
+        (
+            "structures".to_string(),
+            Value::Compound(HashMap::from([
+                ("References".to_string(), empty_compound()),
+                ("starts".to_string(), empty_compound()),
+            ])),
+        ),
+        ("block_ticks".to_string(), Value::List(Vec::new())),
+        ("fluid_ticks".to_string(), Value::List(Vec::new())),
+        ("PostProcessing".to_string(), Value::List(Vec::new())),
+    ]
+}
+
+/// 恢复区块的默认值（还原时使用，1.18 之前的区块写入 `Level` 中）
+///
+/// 区块中已有的字段保持不变；需要知道补全了哪些字段时使用 [`restore_defaults_with_values`]
 pub fn restore_defaults(value: &mut Value) {
-    if let Value::Compound(map) = value {
-        map.entry("LastUpdate".to_string())
-            .or_insert(Value::Long(0));
-        map.entry("InhabitedTime".to_string())
-            .or_insert(Value::Long(0));
-        // isLightOn=0 让游戏重新计算光照（因为激进模式可能移除了光照数据）
-        map.entry("isLightOn".to_string()).or_insert(Value::Byte(0));
+    restore_defaults_with_values(value, &default_restore_values());
+}
+
+/// 按给定的字段和默认值恢复区块的默认值，返回实际补全的字段
+///
+/// 1.18 之前的区块使用字段的旧名称（如 `block_ticks` 写为 `TileTicks`）
+pub fn restore_defaults_with_values(
+    value: &mut Value,
+    defaults: &[(String, Value)],
+) -> Vec<String> {
+    let mut injected = Vec::new();
+    if let Value::Compound(root) = value {
+        let (map, legacy) = chunk_body_mut(root);
+        for (field, default) in defaults {
+            let field = if legacy {
+                legacy_field_name(field)
+            } else {
+                field
+            };
+            if !map.contains_key(field) {
+                map.insert(field.to_string(), default.clone());
+                injected.push(field.to_string());
+            }
+        }
+    }
+    injected
+}
+
+/// 预览中单个值的最大显示长度
+const PREVIEW_MAX_CHARS: usize = 60;
+
+/// 去噪预览结果
+#[derive(Debug, Clone, Default)]
+pub struct DenoiseDiff {
+    /// 将被移除的字段
+    pub removed: Vec<RemovedField>,
+    /// 将被修改的字段（如重置天气）
+    pub changed: Vec<ChangedField>,
+}
+
+/// 将被移除的字段
+#[derive(Debug, Clone)]
+pub struct RemovedField {
+    /// 字段路径，如 `sections[0].SkyLight`
+    pub path: String,
+    /// 被移除值的截断预览
+    pub preview: String,
+}
+
+/// 将被修改的字段
+#[derive(Debug, Clone)]
+pub struct ChangedField {
+    /// 字段路径
+    pub path: String,
+    /// 修改前的值预览
+    pub before: String,
+    /// 修改后的值预览
+    pub after: String,
+}
+
+impl DenoiseDiff {
+    /// 是否没有任何变化
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// 预览区块去噪结果（不修改原数据）
+///
+/// `dimension` 用于选用按维度覆盖的去噪配置（与导出时相同）；
+/// 配置中的字段列表无效时返回错误，因此返回 `Result`
+pub fn preview_denoise_chunk(
+    value: &Value,
+    aggressive: bool,
+    config: &DenoiseConfig,
+    dimension: Option<&str>,
+) -> Result<DenoiseDiff> {
+    let mut denoised = value.clone();
+    denoise_chunk_with_config(&mut denoised, aggressive, config, dimension)?;
+    Ok(diff_values(value, &denoised))
+}
+
+/// 预览 level.dat 去噪结果（不修改原数据），字段列表无效时返回错误
+pub fn preview_denoise_level(value: &Value, config: &DenoiseConfig) -> Result<DenoiseDiff> {
+    let mut denoised = value.clone();
+    denoise_level_with_config(&mut denoised, config)?;
+    Ok(diff_values(value, &denoised))
+}
+
+/// 比较去噪前后的值
+fn diff_values(before: &Value, after: &Value) -> DenoiseDiff {
+    let mut diff = DenoiseDiff::default();
+    collect_diff("", before, after, &mut diff);
+    diff.removed.sort_by(|a, b| a.path.cmp(&b.path));
+    diff.changed.sort_by(|a, b| a.path.cmp(&b.path));
+    diff
+}
+
+fn collect_diff(path: &str, before: &Value, after: &Value, diff: &mut DenoiseDiff) {
+    match (before, after) {
+        (Value::Compound(a), Value::Compound(b)) => {
+            for (key, a_value) in a {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match b.get(key) {
+                    Some(b_value) => collect_diff(&child, a_value, b_value, diff),
+                    None => diff.removed.push(RemovedField {
+                        path: child,
+                        preview: preview_value(a_value),
+                    }),
+                }
+            }
+        }
+        (Value::List(a), Value::List(b)) if a.len() == b.len() => {
+            for (i, (a_value, b_value)) in a.iter().zip(b).enumerate() {
+                collect_diff(&format!("{}[{}]", path, i), a_value, b_value, diff);
+            }
+        }
+        _ => {
+            if before != after {
+                diff.changed.push(ChangedField {
+                    path: path.to_string(),
+                    before: preview_value(before),
+                    after: preview_value(after),
+                });
+            }
+        }
+    }
+}
+
+/// 生成值的截断预览
+fn preview_value(value: &Value) -> String {
+    let text = nbt_to_json(value).to_string();
+    if text.chars().count() > PREVIEW_MAX_CHARS {
+        format!(
+            "{}...",
+            text.chars().take(PREVIEW_MAX_CHARS).collect::<String>()
+        )
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_util::compound;
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let Value::Compound(map) = value else {
+            panic!("不是 compound");
+        };
+        let mut keys: Vec<&str> = map.keys().map(String::as_str).collect();
+        keys.sort();
+        keys
+    }
+
+    fn config_with_fields(fields: &[&str]) -> DenoiseConfig {
+        let mut config = DenoiseConfig::default();
+        config.chunk.fields = fields.iter().map(|f| f.to_string()).collect();
+        config
+    }
+
+    #[test]
+    fn exact_glob_and_regex_fields_are_removed() {
+        let config = config_with_fields(&["InhabitedTime", "forge:cap_*", r"re:^cofh_\d+$"]);
+        let denoiser = ChunkDenoiser::new(&config, None).unwrap();
+        let mut chunk = compound([
+            ("xPos", Value::Int(0)),
+            ("InhabitedTime", Value::Long(100)),
+            ("forge:cap_energy", Value::Int(1)),
+            ("forge:capabilities", Value::Int(1)),
+            ("cofh_12", Value::Int(1)),
+            ("cofh_data", Value::Int(1)),
+        ]);
+        let mut stats = DenoiseStats::default();
+        denoiser.denoise(&mut chunk, false, Some(&mut stats));
+        assert_eq!(keys(&chunk), ["cofh_data", "forge:capabilities", "xPos"]);
+        assert_eq!(stats.fields_removed.len(), 3);
+    }
+
+    #[test]
+    fn exact_fields_use_legacy_names_in_old_chunks() {
+        let config = config_with_fields(&["block_ticks"]);
+        let denoiser = ChunkDenoiser::new(&config, None).unwrap();
+        let mut chunk = compound([(
+            LEGACY_LEVEL_KEY,
+            compound([
+                ("xPos", Value::Int(0)),
+                ("TileTicks", Value::List(Vec::new())),
+            ]),
+        )]);
+        denoiser.denoise(&mut chunk, false, None);
+        let Value::Compound(root) = &chunk else {
+            unreachable!()
+        };
+        assert_eq!(keys(&root[LEGACY_LEVEL_KEY]), ["xPos"]);
+    }
+
+    #[test]
+    fn invalid_pattern_is_a_config_error() {
+        let config = config_with_fields(&["re:(unclosed"]);
+        let err = ChunkDenoiser::new(&config, None).unwrap_err();
+        assert!(format!("{:#}", err).contains("denoise.chunk.fields"));
+        assert!(denoise_chunk_with_config(&mut Value::Int(0), false, &config, None).is_err());
+
+        let config = Config {
+            denoise: config,
+            ..Config::default()
+        };
+        let errors = config.errors();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].starts_with("denoise.chunk.fields"));
+    }
+
+    #[test]
+    fn default_config_removes_nested_starlight_fields() {
+        let mut chunk = compound([
+            ("xPos", Value::Int(0)),
+            (
+                "starlight",
+                compound([
+                    ("light_version", Value::Int(9)),
+                    ("blocklight_state", Value::String("full".to_string())),
+                    ("skylight_state", Value::String("full".to_string())),
+                    ("custom", Value::Int(1)),
+                ]),
+            ),
+        ]);
+        let mut stats = DenoiseStats::default();
+        denoise_chunk_with_stats(
+            &mut chunk,
+            false,
+            &DenoiseConfig::default(),
+            None,
+            &mut stats,
+        )
+        .unwrap();
+        let Value::Compound(root) = &chunk else {
+            unreachable!()
+        };
+        assert_eq!(keys(&root["starlight"]), ["custom"]);
+        assert_eq!(stats.fields_removed["starlight.skylight_state"], 1);
+    }
+
+    #[test]
+    fn dotted_paths_prefer_literal_keys_and_ignore_missing_parents() {
+        let mut map = HashMap::from([
+            ("a.b".to_string(), Value::Int(1)),
+            ("a".to_string(), compound([("b", Value::Int(2))])),
+            (
+                "deep".to_string(),
+                compound([("x", compound([("y", Value::Int(3))]))]),
+            ),
+            ("leaf".to_string(), Value::Int(4)),
+        ]);
+        // 存在同名字段时只移除该字段
+        assert!(remove_field(&mut map, "a.b"));
+        assert!(!map.contains_key("a.b"));
+        assert_eq!(keys(&map["a"]), ["b"]);
+        assert!(remove_field(&mut map, "a.b"));
+        assert!(keys(&map["a"]).is_empty());
+
+        assert!(remove_field(&mut map, "deep.x.y"));
+        assert!(keys(&map["deep"]).contains(&"x"));
+        assert!(!remove_field(&mut map, "missing.y"));
+        assert!(!remove_field(&mut map, "leaf.y"));
+        assert_eq!(map["leaf"], Value::Int(4));
+    }
+
+    #[test]
+    fn keep_heightmaps_removes_only_unlisted_types() {
+        let denoise = |keep: &[&str]| {
+            let mut config = DenoiseConfig::default();
+            config.chunk.keep_heightmaps = keep.iter().map(|k| k.to_string()).collect();
+            let mut chunk = compound([
+                ("xPos", Value::Int(0)),
+                (
+                    "Heightmaps",
+                    compound([
+                        ("MOTION_BLOCKING", Value::Int(1)),
+                        ("WORLD_SURFACE", Value::Int(2)),
+                    ]),
+                ),
+            ]);
+            let mut stats = DenoiseStats::default();
+            ChunkDenoiser::new(&config, None)
+                .unwrap()
+                .denoise(&mut chunk, true, Some(&mut stats));
+            let Value::Compound(mut root) = chunk else {
+                unreachable!()
+            };
+            let mut removed: Vec<String> = stats.fields_removed.into_keys().collect();
+            removed.sort();
+            (root.remove("Heightmaps"), removed)
+        };
+
+        // 为空时移除整个 Heightmaps
+        let (heightmaps, removed) = denoise(&[]);
+        assert!(heightmaps.is_none());
+        assert_eq!(removed, ["Heightmaps"]);
+
+        // 只保留列出的高度图
+        let (heightmaps, removed) = denoise(&["MOTION_BLOCKING"]);
+        assert_eq!(keys(&heightmaps.unwrap()), ["MOTION_BLOCKING"]);
+        assert_eq!(removed, ["Heightmaps.WORLD_SURFACE"]);
+
+        // 全部列出时 Heightmaps 原样保留
+        let (heightmaps, removed) = denoise(&["MOTION_BLOCKING", "WORLD_SURFACE"]);
+        assert_eq!(
+            keys(&heightmaps.unwrap()),
+            ["MOTION_BLOCKING", "WORLD_SURFACE"]
+        );
+        assert!(removed.is_empty(), "{:?}", removed);
+    }
+
+    #[test]
+    fn preview_reports_removed_paths_without_modifying_input() {
+        let mut config = DenoiseConfig::default();
+        config.chunk.keep_heightmaps = vec!["MOTION_BLOCKING".to_string()];
+        let mut chunk = crate::test_util::sample_chunk(0, 0);
+        if let Value::Compound(root) = &mut chunk {
+            if let Some(Value::Compound(heightmaps)) = root.get_mut("Heightmaps") {
+                heightmaps.insert("WORLD_SURFACE".to_string(), Value::Int(7));
+            }
+        }
+        let original = chunk.clone();
+
+        let diff = preview_denoise_chunk(&chunk, true, &config, None).unwrap();
+        assert_eq!(chunk, original);
+        let removed = |path: &str| diff.removed.iter().find(|f| f.path == path);
+        assert_eq!(removed("Heightmaps.WORLD_SURFACE").unwrap().preview, "7");
+        assert!(removed("Heightmaps.MOTION_BLOCKING").is_none());
+        assert!(removed("LastUpdate").is_some());
+        // 列表元素带下标，过长的值截断
+        let light = removed("sections[3].SkyLight").unwrap();
+        assert!(light.preview.ends_with("..."));
+        assert_eq!(light.preview.chars().count(), PREVIEW_MAX_CHARS + 3);
+        assert!(removed("sections[3].block_states").is_none());
+
+        let level = compound([(
+            "Data",
+            compound([
+                ("LastPlayed", Value::Long(123)),
+                ("LevelName", Value::String("world".to_string())),
+            ]),
+        )]);
+        let original = level.clone();
+        let diff = preview_denoise_level(&level, &config).unwrap();
+        assert_eq!(level, original);
+        let paths: Vec<&str> = diff.removed.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["Data.LastPlayed"]);
+        assert!(diff.changed.is_empty());
     }
 }
@@ -0,0 +1,187 @@
+//! 比较两个导出目录 - 按区块坐标匹配，列出新增、删除和修改的区块
+
+use crate::config::Config;
+use crate::denoise::ChunkDenoiser;
+use crate::export::{build_thread_pool, dimensions};
+use crate::mca::ChunkData;
+use crate::nbt_json::FieldMapper;
+use crate::restore::{collect_region_files, read_region_chunks, ChunkReadOptions};
+use anyhow::{Context, Result};
+use fastnbt::Value;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// 两个导出目录之间的区块差异（均按维度和坐标排序）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportDiff {
+    /// 只存在于新导出（B）中的区块
+    pub added: Vec<ChunkPos>,
+    /// 只存在于旧导出（A）中的区块
+    pub removed: Vec<ChunkPos>,
+    /// 两边都存在但内容不同的区块
+    pub modified: Vec<ModifiedChunk>,
+}
+
+impl ExportDiff {
+    /// 两个导出是否完全相同
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// 区块位置（全局区块坐标）
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ChunkPos {
+    /// 维度目录（如 `DIM-1`），主世界为空
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub dimension: String,
+    pub x: i32,
+    pub z: i32,
+}
+
+/// 内容不同的区块
+#[derive(Debug, Clone, Serialize)]
+pub struct ModifiedChunk {
+    #[serde(flatten)]
+    pub pos: ChunkPos,
+    /// 值不同（或只在一边存在）的顶层字段，按名称排序
+    pub changed_keys: Vec<String>,
+}
+
+/// 比较两个导出目录
+///
+/// 使用 `config.restore.format` 读取区块文件，按 NBT 内容比较（忽略时间戳和字段顺序），
+/// 切片方式、布局和字段名缩写不同的导出也能正确比较；
+/// `denoise` 为 true 时先对两边的区块去噪，去噪设置不同的导出之间不会因噪声字段产生差异
+pub fn diff_exports(
+    a: &Path,
+    b: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+) -> Result<ExportDiff> {
+    let format = config.restore.format;
+    let field_mapper = FieldMapper::from_config(&config.field_mapping);
+    let pool = build_thread_pool(config.performance.threads)?;
+    let mut diff = ExportDiff::default();
+
+    // 两边的维度可能不同（如只有一边含有自定义维度）
+    let dim_folders: BTreeSet<String> = dimensions(a)
+        .into_iter()
+        .chain(dimensions(b))
+        .map(|(folder, _)| folder)
+        .collect();
+    for dim_folder in &dim_folders {
+        let dimension = (!dim_folder.is_empty()).then_some(dim_folder.as_str());
+        let denoiser = denoise
+            .then(|| ChunkDenoiser::new(&config.denoise, dimension))
+            .transpose()?;
+        let region_files = |root: &Path| -> Result<BTreeMap<(i32, i32), Vec<PathBuf>>> {
+            let path = root.join(dim_folder).join("region");
+            if path.exists() {
+                collect_region_files(&path, format)
+            } else {
+                Ok(BTreeMap::new())
+            }
+        };
+        let files_a = region_files(a)?;
+        let files_b = region_files(b)?;
+        let regions: BTreeSet<_> = files_a.keys().chain(files_b.keys()).copied().collect();
+
+        let region_diffs = pool.install(|| {
+            regions
+                .into_par_iter()
+                .map(|(rx, rz)| {
+                    let load =
+                        |files: Option<&Vec<PathBuf>>| -> Result<BTreeMap<(i32, i32), Value>> {
+                            let chunks = match files {
+                                Some(files) => {
+                                    read_region_chunks(
+                                        (rx, rz),
+                                        files,
+                                        format,
+                                        &field_mapper,
+                                        ChunkReadOptions::lenient(),
+                                    )?
+                                    .chunks
+                                }
+                                None => Vec::new(),
+                            };
+                            Ok(chunks
+                                .into_iter()
+                                .map(|ChunkData { x, z, mut data, .. }| {
+                                    if let Some(denoiser) = &denoiser {
+                                        denoiser.denoise(&mut data, aggressive, None);
+                                    }
+                                    ((rx * 32 + x, rz * 32 + z), data)
+                                })
+                                .collect())
+                        };
+                    let chunks_a = load(files_a.get(&(rx, rz)))
+                        .with_context(|| format!("读取 {} 失败", a.display()))?;
+                    let chunks_b = load(files_b.get(&(rx, rz)))
+                        .with_context(|| format!("读取 {} 失败", b.display()))?;
+                    Ok(diff_chunks(dim_folder, chunks_a, chunks_b))
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        for region_diff in region_diffs {
+            diff.added.extend(region_diff.added);
+            diff.removed.extend(region_diff.removed);
+            diff.modified.extend(region_diff.modified);
+        }
+    }
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.modified.sort_by(|l, r| l.pos.cmp(&r.pos));
+    Ok(diff)
+}
+
+/// 比较同一 region 中的区块
+fn diff_chunks(
+    dimension: &str,
+    mut chunks_a: BTreeMap<(i32, i32), Value>,
+    chunks_b: BTreeMap<(i32, i32), Value>,
+) -> ExportDiff {
+    let pos = |(x, z): (i32, i32)| ChunkPos {
+        dimension: dimension.to_string(),
+        x,
+        z,
+    };
+    let mut diff = ExportDiff::default();
+    for (key, chunk_b) in chunks_b {
+        match chunks_a.remove(&key) {
+            None => diff.added.push(pos(key)),
+            Some(chunk_a) => {
+                let changed_keys = changed_keys(&chunk_a, &chunk_b);
+                if !changed_keys.is_empty() {
+                    diff.modified.push(ModifiedChunk {
+                        pos: pos(key),
+                        changed_keys,
+                    });
+                }
+            }
+        }
+    }
+    diff.removed.extend(chunks_a.into_keys().map(pos));
+    diff
+}
+
+/// 值不同的顶层字段
+fn changed_keys(a: &Value, b: &Value) -> Vec<String> {
+    match (a, b) {
+        (Value::Compound(a), Value::Compound(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            keys.into_iter()
+                .filter(|key| a.get(*key) != b.get(*key))
+                .cloned()
+                .collect()
+        }
+        _ if a != b => vec![String::new()],
+        _ => Vec::new(),
+    }
+}
@@ -1,45 +1,347 @@
 //! 导出世界为 JSON 格式
 
-use crate::config::{Area, Config, DenoiseConfig, ExportConfig, FieldMappingConfig};
+use crate::block_states::{unpack_biomes, unpack_block_states, unpack_heightmaps};
+use crate::config::{
+    Area, Config, DenoiseConfig, EmptyChunkPolicy, ExportConfig, ExportLayout, FieldMappingConfig,
+    SliceFormat, SliceMode, DEFAULT_EMPTY_BLOCKS, DEFAULT_INCLUDED_STATUSES,
+};
 use crate::denoise::{
-    denoise_chunk, denoise_chunk_with_config, denoise_level, denoise_level_with_config,
+    chunk_body, denoise_chunk, denoise_level, denoise_level_with_config, legacy_field_name,
+    preview_denoise_chunk, preview_denoise_level, ChunkDenoiser, DenoiseDiff, DenoiseStats,
+    LEGACY_LEVEL_KEY,
+};
+use crate::filter::{AndFilter, BoundsFilter, ChunkFilter, HasDataFilter, StatusFilter};
+use crate::incremental::{settings_fingerprint, source_state, IncrementalManifest, MANIFEST_FILE};
+use crate::manifest::{ExportManifest, EXPORT_MANIFEST_FILE};
+use crate::mca::{
+    gzip_decompress, parse_region_filename, read_mca, ChunkData, ChunkFormat, InvalidChunk,
+    McaReader, RegionFormat, CHUNKS_PER_REGION,
 };
-use crate::mca::{parse_mca_filename, read_mca};
-use crate::nbt_json::{nbt_to_json, shorten_json_keys, FieldMapper};
+use crate::nbt_json::{
+    insert_root_name, json_to_nbt, nbt_root_name, nbt_to_json, nbt_to_json_with_options,
+    nbt_to_snbt, shorten_json_keys, FieldMapper, NbtJsonOptions,
+};
+use crate::progress;
+use crate::util::{write_atomically, write_atomically_with};
 use anyhow::{Context, Result};
 use fastnbt::Value;
+use log::{debug, error, info, warn};
 use rayon::prelude::*;
-use serde_json::{json, Value as JsonValue};
-use std::fs::{self, File};
-use std::io::Read;
-use std::path::Path;
-use std::sync::Arc;
-
-/// 维度定义
-const DIMENSIONS: &[(&str, &str)] = &[
+use serde::Serialize;
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// 原版维度定义
+const VANILLA_DIMENSIONS: &[(&str, &str)] = &[
     ("", "主世界"),    // 主世界 region/
     ("DIM-1", "地狱"), // 地狱 DIM-1/region/
     ("DIM1", "末地"),  // 末地 DIM1/region/
 ];
 
+/// 数据包添加的维度所在目录
+const CUSTOM_DIMENSIONS_DIR: &str = "dimensions";
+
+/// 世界（或导出目录）中的所有维度：相对目录（主世界为空）和名称
+///
+/// 包括原版的三个维度，以及 `dimensions/` 下递归找到的含有 `region/` 或 `data/` 的目录
+/// （如 `dimensions/<命名空间>/<名称>`），自定义维度的名称为 `命名空间:名称`
+pub(crate) fn dimensions(root: &Path) -> Vec<(String, String)> {
+    let mut result: Vec<(String, String)> = VANILLA_DIMENSIONS
+        .iter()
+        .map(|(folder, name)| (folder.to_string(), name.to_string()))
+        .collect();
+    let mut custom = Vec::new();
+    find_custom_dimensions(
+        &root.join(CUSTOM_DIMENSIONS_DIR),
+        &mut Vec::new(),
+        &mut custom,
+    );
+    custom.sort();
+    result.extend(custom.into_iter().map(|parts| {
+        let folder = format!("{}/{}", CUSTOM_DIMENSIONS_DIR, parts.join("/"));
+        let name = match parts.split_first() {
+            Some((namespace, rest)) if !rest.is_empty() => {
+                format!("{}:{}", namespace, rest.join("/"))
+            }
+            _ => parts.join("/"),
+        };
+        (folder, name)
+    }));
+    result
+}
+
+/// 递归查找含有 `region/` 或 `data/` 的目录，`parts` 为相对 `dimensions/` 的路径
+fn find_custom_dimensions(dir: &Path, parts: &mut Vec<String>, found: &mut Vec<Vec<String>>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        parts.push(name.to_string());
+        if path.join("region").is_dir() || path.join("data").is_dir() {
+            found.push(parts.clone());
+        } else {
+            find_custom_dimensions(&path, parts, found);
+        }
+        parts.pop();
+    }
+}
+
+/// region 目录中要导出的区域文件（按文件名排序）
+///
+/// 包括所有 `.mca` 文件，以及没有同坐标 `.mca` 的 `.mcr` 文件
+/// （游戏将 McRegion 世界转换为 Anvil 后会保留原来的 `.mcr` 文件）
+pub(crate) fn region_files(region_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(region_path)? {
+        let path = entry?.path();
+        let Some(filename) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some((rx, rz, format)) = parse_region_filename(filename) else {
+            continue;
+        };
+        match files.entry((rx, rz)) {
+            Entry::Vacant(entry) => {
+                entry.insert(path);
+            }
+            Entry::Occupied(mut entry) if format == RegionFormat::Anvil => {
+                entry.insert(path);
+            }
+            Entry::Occupied(_) => {}
+        }
+    }
+    let mut files: Vec<PathBuf> = files.into_values().collect();
+    files.sort();
+    Ok(files)
+}
+
+/// 导出结果汇总（可序列化为 JSON 供自动化使用）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportReport {
+    /// 处理的 region 文件数量
+    pub regions_total: usize,
+    /// 导出失败的 region 文件数量
+    pub regions_failed: usize,
+    /// 增量导出时因源文件未变化而跳过的 region 文件数量
+    pub regions_unchanged: usize,
+    /// 导出的区块数量
+    pub chunks_exported: usize,
+    /// 因没有实际数据而跳过的区块数量
+    pub chunks_skipped_empty: usize,
+    /// 因未完整生成而跳过的区块数量
+    pub chunks_skipped_not_full: usize,
+    /// 被区块过滤器（`filters`）排除的区块数量
+    pub chunks_skipped_filtered: usize,
+    /// 因数据损坏而无法读取的区块数量
+    pub chunks_failed: usize,
+    /// 写入的切片文件总字节数
+    pub bytes_written: usize,
+    /// 导出耗时（秒）
+    pub elapsed_secs: f64,
+    /// 去噪统计
+    pub denoise: DenoiseStats,
+    /// 各 region 的导出结果（按路径排序）
+    pub per_region: Vec<RegionReport>,
+}
+
+/// 单个 region 文件的导出结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RegionReport {
+    /// MCA 文件路径
+    pub path: PathBuf,
+    /// 导出的区块数量
+    pub chunks_exported: usize,
+    /// 因没有实际数据而跳过的区块数量
+    pub chunks_skipped_empty: usize,
+    /// 因未完整生成而跳过的区块数量
+    pub chunks_skipped_not_full: usize,
+    /// 被区块过滤器（`filters`）排除的区块数量
+    pub chunks_skipped_filtered: usize,
+    /// 因数据损坏而无法读取的区块数量
+    pub chunks_failed: usize,
+    /// 写入的切片文件数量（每区块一个文件的布局下为区块文件数量）
+    pub slices_written: usize,
+    /// 写入的切片文件总字节数
+    pub bytes_written: usize,
+    /// 写入的文件（试运行时为将要写入的文件）
+    pub files: Vec<SliceFile>,
+    /// 去噪统计
+    pub denoise: DenoiseStats,
+    /// 增量导出时源文件未变化，未重新导出
+    pub unchanged: bool,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+/// 导出写入的文件
+#[derive(Debug, Clone, Serialize)]
+pub struct SliceFile {
+    /// 文件路径
+    pub path: PathBuf,
+    /// 文件大小（字节）
+    pub bytes: usize,
+}
+
+impl RegionReport {
+    fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    /// 记录一个写入的文件
+    fn add_file(&mut self, file: SliceFile) {
+        self.slices_written += 1;
+        self.bytes_written += file.bytes;
+        self.files.push(file);
+    }
+}
+
+impl ExportReport {
+    /// 汇总一个 region 的结果
+    pub fn add_region(&mut self, region: RegionReport) {
+        self.regions_total += 1;
+        if region.error.is_some() {
+            self.regions_failed += 1;
+        }
+        if region.unchanged {
+            self.regions_unchanged += 1;
+        }
+        self.chunks_exported += region.chunks_exported;
+        self.chunks_skipped_empty += region.chunks_skipped_empty;
+        self.chunks_skipped_not_full += region.chunks_skipped_not_full;
+        self.chunks_skipped_filtered += region.chunks_skipped_filtered;
+        self.chunks_failed += region.chunks_failed;
+        self.bytes_written += region.bytes_written;
+        self.denoise.merge(&region.denoise);
+        self.per_region.push(region);
+    }
+
+    /// 导出失败的 region
+    pub fn failed_regions(&self) -> impl Iterator<Item = &RegionReport> {
+        self.per_region.iter().filter(|r| r.error.is_some())
+    }
+}
+
+/// 导出中止时的错误，带有中止前已完成部分的汇总结果
+///
+/// 可通过 [`anyhow::Error::downcast_ref`] 取出，用于在失败时也写出导出报告
+#[derive(Debug)]
+pub struct ExportError {
+    /// 中止前已处理的 region 的汇总结果
+    pub report: ExportReport,
+    error: anyhow::Error,
+}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for ExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// 区块的 `DataVersion` 与 `require_data_version` 不一致，无论是否启用 `fail_fast` 都中止整个导出
+#[derive(Debug, Clone)]
+pub struct DataVersionMismatch {
+    /// region 内坐标
+    pub x: i32,
+    pub z: i32,
+    /// 区块的 `DataVersion`，缺少时为 `None`
+    pub found: Option<i32>,
+    pub required: i32,
+}
+
+impl std::fmt::Display for DataVersionMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "区块 ({}, {}) 的 DataVersion 为 {}，与 require_data_version = {} 不一致",
+            self.x,
+            self.z,
+            self.found.map_or("(无)".to_string(), |v| v.to_string()),
+            self.required
+        )
+    }
+}
+
+impl std::error::Error for DataVersionMismatch {}
+
+/// 记录单个 region 的导出结果并输出进度
+///
+/// `fail_fast` 为 true 或错误为 [`DataVersionMismatch`] 时记录失败后返回该错误，用于中止整个导出
+fn record_region(
+    report: &Mutex<ExportReport>,
+    mca_path: &Path,
+    result: Result<RegionReport>,
+    fail_fast: bool,
+) -> Result<()> {
+    progress::inc();
+    let region = match result {
+        Ok(region) => {
+            if region.unchanged {
+                debug!("  未变化 {:?}", mca_path.file_name().unwrap());
+            } else {
+                debug!("  完成 {:?}", mca_path.file_name().unwrap());
+            }
+            region
+        }
+        Err(e) => {
+            error!("  失败 {:?}: {}", mca_path.file_name().unwrap(), e);
+            let region = RegionReport {
+                error: Some(e.to_string()),
+                ..RegionReport::new(mca_path)
+            };
+            if fail_fast || e.downcast_ref::<DataVersionMismatch>().is_some() {
+                report.lock().unwrap().add_region(region);
+                return Err(e.context(format!("导出 {} 失败", mca_path.display())));
+            }
+            region
+        }
+    };
+    report.lock().unwrap().add_region(region);
+    Ok(())
+}
+
 /// 导出整个世界（使用默认去噪字段）
 pub fn export_world(
     world_path: &Path,
     output_path: &Path,
     denoise: bool,
     aggressive: bool,
-) -> Result<()> {
+) -> Result<ExportReport> {
+    let start = Instant::now();
     fs::create_dir_all(output_path)?;
 
     // 导出 level.dat
     let level_dat = world_path.join("level.dat");
     if level_dat.exists() {
-        println!("导出 level.dat");
+        info!("导出 level.dat");
         export_level_dat(&level_dat, &output_path.join("level.json"), denoise)?;
     }
 
+    let report = Mutex::new(ExportReport::default());
+
     // 导出所有维度
-    for (dim_folder, dim_name) in DIMENSIONS {
+    for (dim_folder, dim_name) in &dimensions(world_path) {
         let (region_path, region_output) = if dim_folder.is_empty() {
             (world_path.join("region"), output_path.join("region"))
         } else {
@@ -53,29 +355,33 @@ pub fn export_world(
             continue;
         }
 
-        let mca_files: Vec<_> = fs::read_dir(&region_path)?
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map_or(false, |ext| ext == "mca"))
-            .collect();
+        let mca_files = region_files(&region_path)?;
 
         if mca_files.is_empty() {
             continue;
         }
 
-        println!("导出 {} ({} 个 region 文件)", dim_name, mca_files.len());
+        info!("导出 {} ({} 个 region 文件)", dim_name, mca_files.len());
+        progress::start(dim_name, mca_files.len());
 
-        mca_files.par_iter().for_each(|entry| {
-            let mca_path = entry.path();
-            if let Err(e) = export_mca(&mca_path, &region_output, denoise, aggressive) {
-                eprintln!("  失败 {:?}: {}", mca_path.file_name().unwrap(), e);
-            } else {
-                println!("  完成 {:?}", mca_path.file_name().unwrap());
-            }
+        let result = mca_files.par_iter().try_for_each(|mca_path| {
+            let result = export_mca(mca_path, &region_output, denoise, aggressive);
+            record_region(&report, mca_path, result, false)
         });
+        progress::finish();
+        result?;
     }
 
-    println!("导出完成");
-    Ok(())
+    info!("导出完成");
+    Ok(finish_report(report, start))
+}
+
+/// 取出汇总结果，并按路径排序各 region
+fn finish_report(report: Mutex<ExportReport>, start: Instant) -> ExportReport {
+    let mut report = report.into_inner().unwrap();
+    report.elapsed_secs = start.elapsed().as_secs_f64();
+    report.per_region.sort_by(|a, b| a.path.cmp(&b.path));
+    report
 }
 
 /// 导出整个世界（使用配置）
@@ -85,137 +391,329 @@ pub fn export_world_with_config(
     denoise: bool,
     aggressive: bool,
     config: &Config,
-) -> Result<()> {
-    fs::create_dir_all(output_path)?;
+) -> Result<ExportReport> {
+    export_world_with_area(world_path, output_path, denoise, aggressive, config, None)
+}
 
-    // 导出 level.dat
-    let level_dat = world_path.join("level.dat");
-    if level_dat.exists() {
-        println!("导出 level.dat");
-        export_level_dat_with_config(
-            &level_dat,
-            &output_path.join("level.json"),
-            denoise,
-            &config.denoise,
-            &config.field_mapping,
-        )?;
-    }
+/// 导出整个世界（使用配置，支持区域过滤）
+pub fn export_world_with_area(
+    world_path: &Path,
+    output_path: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+    area: Option<&Area>,
+) -> Result<ExportReport> {
+    export_world_impl(
+        world_path,
+        output_path,
+        denoise,
+        aggressive,
+        config,
+        area,
+        false,
+    )
+}
 
-    let denoise_config = Arc::new(config.denoise.clone());
-    let export_config = Arc::new(config.export.clone());
-    let field_mapper = Arc::new(FieldMapper::from_config(&config.field_mapping));
+/// 增量导出整个世界（使用配置，支持区域过滤）
+///
+/// 在导出目录中维护 `.mcj-manifest.json`，记录每个源 MCA 文件的修改时间和内容哈希，
+/// 内容未变化且导出设置相同的 region 会被跳过，只重写发生变化的 region 的切片
+pub fn export_world_incremental(
+    world_path: &Path,
+    output_path: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+    area: Option<&Area>,
+) -> Result<ExportReport> {
+    export_world_impl(
+        world_path,
+        output_path,
+        denoise,
+        aggressive,
+        config,
+        area,
+        true,
+    )
+}
 
-    // 导出所有维度
-    for (dim_folder, dim_name) in DIMENSIONS {
-        let (region_path, region_output) = if dim_folder.is_empty() {
-            (world_path.join("region"), output_path.join("region"))
+/// 清理导出目录中导出会生成的内容，用于覆盖导出（`export --overwrite`）
+///
+/// 删除 `level.json`/`level.snbt`、两种清单、`playerdata/`，以及各维度的 `data/` 和
+/// `region/`、`entities/`、`poi/`；保留 `.git` 等其它文件。
+/// 本次导出不再生成的内容（如关闭 `include_playerdata` 后的 `playerdata/`）因此不会残留
+pub fn clean_export_output(output_path: &Path) -> Result<()> {
+    let remove = |path: PathBuf| -> Result<()> {
+        if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else if path.exists() {
+            fs::remove_file(&path)
         } else {
-            (
-                world_path.join(dim_folder).join("region"),
-                output_path.join(dim_folder).join("region"),
-            )
-        };
-
-        if !region_path.exists() {
-            continue;
+            return Ok(());
         }
-
-        let mca_files: Vec<_> = fs::read_dir(&region_path)?
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map_or(false, |ext| ext == "mca"))
-            .collect();
-
-        if mca_files.is_empty() {
-            continue;
+        .with_context(|| format!("删除 {} 失败", path.display()))
+    };
+    for format in [SliceFormat::Json, SliceFormat::Snbt] {
+        remove(output_path.join(format!("level.{}", format.extension())))?;
+    }
+    remove(output_path.join(MANIFEST_FILE))?;
+    remove(output_path.join(EXPORT_MANIFEST_FILE))?;
+    remove(output_path.join("playerdata"))?;
+    for (dim_folder, _) in &dimensions(output_path) {
+        let dim_path = output_path.join(dim_folder);
+        remove(dim_path.join("data"))?;
+        for kind in RegionKind::ALL {
+            remove(dim_path.join(kind.folder()))?;
         }
+    }
+    Ok(())
+}
 
-        println!("导出 {} ({} 个 region 文件)", dim_name, mca_files.len());
+/// 创建并行处理 region 的线程池，`threads` 为 0 时使用所有 CPU
+pub(crate) fn build_thread_pool(threads: usize) -> Result<rayon::ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .context("创建线程池失败")
+}
 
-        mca_files.par_iter().for_each(|entry| {
-            let mca_path = entry.path();
-            if let Err(e) = export_mca_with_config(
-                &mca_path,
-                &region_output,
-                denoise,
-                aggressive,
-                &denoise_config,
-                &export_config,
-                &field_mapper,
-            ) {
-                eprintln!("  失败 {:?}: {}", mca_path.file_name().unwrap(), e);
-            } else {
-                println!("  完成 {:?}", mca_path.file_name().unwrap());
-            }
-        });
+/// 在 `[performance] threads` 指定大小的线程池中导出
+fn export_world_impl(
+    world_path: &Path,
+    output_path: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+    area: Option<&Area>,
+    incremental: bool,
+) -> Result<ExportReport> {
+    let sink = if config.export.dry_run {
+        OutputSink::DryRun
+    } else {
+        OutputSink::Disk
+    };
+    let pool = build_thread_pool(config.performance.threads)?;
+    pool.install(|| {
+        export_world_in_pool(
+            world_path,
+            output_path,
+            denoise,
+            aggressive,
+            config,
+            area,
+            incremental,
+            &sink,
+        )
+    })
+}
+
+/// 将世界导出到内存，返回相对路径（如 `level.json`、`region/r.0.0.0.json`）到文件内容的映射
+///
+/// 输出与 [`export_world_with_config`] 写入导出目录的文件完全相同，但不写入磁盘，
+/// 适合测试和 Web 服务等场景；任一 region 导出失败时返回错误
+pub fn export_world_to_memory(
+    world_path: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+) -> Result<HashMap<String, String>> {
+    let sink = OutputSink::Memory(Mutex::default());
+    let pool = build_thread_pool(config.performance.threads)?;
+    let report = pool.install(|| {
+        export_world_in_pool(
+            world_path,
+            Path::new(""),
+            denoise,
+            aggressive,
+            config,
+            None,
+            false,
+            &sink,
+        )
+    })?;
+    if report.regions_failed > 0 {
+        anyhow::bail!(
+            "{} 个 region 导出失败:\n    {}",
+            report.regions_failed,
+            report
+                .failed_regions()
+                .map(|r| format!("{}: {}", r.path.display(), r.error.as_deref().unwrap_or("")))
+                .collect::<Vec<_>>()
+                .join("\n    ")
+        );
+    }
+    match sink {
+        OutputSink::Memory(files) => Ok(files.into_inner().unwrap()),
+        _ => unreachable!(),
     }
+}
 
-    println!("导出完成");
-    Ok(())
+#[allow(clippy::too_many_arguments)]
+fn export_world_in_pool(
+    world_path: &Path,
+    output_path: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+    area: Option<&Area>,
+    incremental: bool,
+    sink: &OutputSink,
+) -> Result<ExportReport> {
+    let start = Instant::now();
+    let report = Mutex::new(ExportReport::default());
+    let result = export_world_into(
+        world_path,
+        output_path,
+        denoise,
+        aggressive,
+        config,
+        area,
+        incremental,
+        sink,
+        &report,
+    );
+    let report = finish_report(report, start);
+
+    // 写入导出清单（试运行时跳过），增量导出中未变化的 region 沿用上一次的记录
+    let result = result.and_then(|previous_manifest| {
+        if config.export.manifest && !matches!(sink, OutputSink::DryRun) {
+            let level_dat = world_path.join("level.dat");
+            let data_version = level_dat
+                .exists()
+                .then(|| level_data_version(&level_dat))
+                .flatten();
+            let manifest = ExportManifest::new(
+                world_path,
+                data_version,
+                &report.per_region,
+                previous_manifest.as_ref(),
+            );
+            sink.write(&output_path.join(EXPORT_MANIFEST_FILE), manifest.to_json()?)?;
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => {
+            info!("导出完成");
+            Ok(report)
+        }
+        Err(error) => Err(ExportError { report, error }.into()),
+    }
 }
 
-/// 导出整个世界（使用配置，支持区域过滤）
-pub fn export_world_with_area(
+/// 导出 level.dat、数据文件和所有 region，结果汇总到 `report`，返回增量导出前的导出清单
+#[allow(clippy::too_many_arguments)]
+fn export_world_into(
     world_path: &Path,
     output_path: &Path,
     denoise: bool,
     aggressive: bool,
     config: &Config,
     area: Option<&Area>,
-) -> Result<()> {
-    fs::create_dir_all(output_path)?;
+    incremental: bool,
+    sink: &OutputSink,
+    report: &Mutex<ExportReport>,
+) -> Result<Option<ExportManifest>> {
+    let dry_run = matches!(sink, OutputSink::DryRun);
+    if dry_run && incremental {
+        anyhow::bail!("试运行不支持增量导出");
+    }
+    check_slice_size(&config.export)?;
+    if matches!(sink, OutputSink::Disk) {
+        fs::create_dir_all(output_path)?;
+    }
 
-    // 导出 level.dat
+    // 导出 level.dat（试运行时跳过）
     let level_dat = world_path.join("level.dat");
-    if level_dat.exists() {
-        println!("导出 level.dat");
-        export_level_dat_with_config(
-            &level_dat,
-            &output_path.join("level.json"),
-            denoise,
-            &config.denoise,
-            &config.field_mapping,
+    if level_dat.exists() && !dry_run {
+        info!("导出 level.dat");
+        let format = config.export.format;
+        let output = level_dat_output(&level_dat, denoise, config, format)?;
+        sink.write(
+            &output_path.join(format!("level.{}", format.extension())),
+            output,
         )?;
     }
 
+    // 导出 data/ 目录（地图、记分板、袭击等，试运行时跳过）
+    if !dry_run {
+        for (dim_folder, _) in &dimensions(world_path) {
+            let data_dir = world_path.join(dim_folder).join("data");
+            if data_dir.is_dir() {
+                export_data_dir(
+                    &data_dir,
+                    &output_path.join(dim_folder).join("data"),
+                    config,
+                    sink,
+                )?;
+            }
+        }
+
+        // 导出 playerdata/（每个玩家一个 <uuid>.dat）
+        let playerdata_dir = world_path.join("playerdata");
+        if config.export.include_playerdata && playerdata_dir.is_dir() {
+            export_data_dir(
+                &playerdata_dir,
+                &output_path.join("playerdata"),
+                config,
+                sink,
+            )?;
+        }
+    }
+
     if let Some(a) = area {
-        println!(
+        info!(
             "工作区域: ({}, {}) ~ ({}, {})",
             a.min.x as i32, a.min.z as i32, a.max.x as i32, a.max.z as i32
         );
     }
+    if let Some(b) = &config.export.bounds {
+        info!(
+            "区块范围: ({}, {}) ~ ({}, {})",
+            b.min_x, b.min_z, b.max_x, b.max_z
+        );
+    }
 
     let denoise_config = Arc::new(config.denoise.clone());
     let export_config = Arc::new(config.export.clone());
     let field_mapper = Arc::new(FieldMapper::from_config(&config.field_mapping));
+    let filter = AndFilter::from_config(&export_config);
+    let incremental = if incremental {
+        Some(IncrementalState::load(
+            output_path,
+            settings_fingerprint(config, denoise, aggressive)?,
+        ))
+    } else {
+        None
+    };
 
-    // 导出所有维度
-    for (dim_folder, dim_name) in DIMENSIONS {
-        let (region_path, region_output) = if dim_folder.is_empty() {
-            (world_path.join("region"), output_path.join("region"))
-        } else {
-            (
-                world_path.join(dim_folder).join("region"),
-                output_path.join(dim_folder).join("region"),
-            )
-        };
+    // 导出所有维度的 region/、entities/ 和 poi/
+    let folders = dimensions(world_path)
+        .into_iter()
+        .flat_map(|dim| RegionKind::ALL.map(|kind| (dim.clone(), kind)));
+    for ((dim_folder, dim_name), kind) in folders {
+        let region_path = world_path.join(&dim_folder).join(kind.folder());
+        let region_output = output_path.join(&dim_folder).join(kind.folder());
 
         if !region_path.exists() {
             continue;
         }
 
-        let mca_files: Vec<_> = fs::read_dir(&region_path)?
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map_or(false, |ext| ext == "mca"))
-            .filter(|e| {
-                // 如果有区域过滤，跳过不在区域内的 region
-                if let Some(area) = area {
-                    let filename = e.path();
-                    let filename = filename.file_name().unwrap().to_str().unwrap();
-                    if let Some((rx, rz)) = parse_mca_filename(filename) {
-                        return area.may_contain_region(rx, rz);
-                    }
-                }
-                true
+        let mca_files: Vec<_> = region_files(&region_path)?
+            .into_iter()
+            .filter(|path| {
+                let filename = path.file_name().unwrap().to_str().unwrap();
+                let Some((rx, rz, _)) = parse_region_filename(filename) else {
+                    return true;
+                };
+                // 如果有区域过滤，跳过不在区域内的 region；
+                // 跳过完全位于区块范围之外的 region，不读取文件
+                area.is_none_or(|area| area.may_contain_region(rx, rz))
+                    && config
+                        .export
+                        .bounds
+                        .is_none_or(|bounds| bounds.may_contain_region(rx, rz))
             })
             .collect();
 
@@ -223,320 +721,1597 @@ pub fn export_world_with_area(
             continue;
         }
 
-        println!("导出 {} ({} 个 region 文件)", dim_name, mca_files.len());
+        info!(
+            "导出 {} {}/ ({} 个 region 文件)",
+            dim_name,
+            kind.folder(),
+            mca_files.len()
+        );
+        progress::start(&dim_name, mca_files.len());
 
-        mca_files.par_iter().for_each(|entry| {
-            let mca_path = entry.path();
-            if let Err(e) = export_mca_with_config(
-                &mca_path,
-                &region_output,
+        let dimension = (!dim_folder.is_empty()).then_some(dim_folder.as_str());
+        let processor = ChunkProcessor {
+            kind,
+            ..ChunkProcessor::new(
+                dimension,
                 denoise,
                 aggressive,
                 &denoise_config,
                 &export_config,
                 &field_mapper,
-            ) {
-                eprintln!("  失败 {:?}: {}", mca_path.file_name().unwrap(), e);
-            } else {
-                println!("  完成 {:?}", mca_path.file_name().unwrap());
-            }
+                &filter,
+            )?
+        };
+        // region 数量少于线程数时再在 region 内按区块并行，避免两层并行争抢线程
+        let parallel_chunks = mca_files.len() < rayon::current_num_threads();
+        let result = mca_files.par_iter().try_for_each(|mca_path| {
+            let export =
+                || export_mca_impl(mca_path, &region_output, &processor, parallel_chunks, sink);
+            let result = match &incremental {
+                Some(state) => state.export_region(world_path, mca_path, &region_output, export),
+                None => export(),
+            };
+            record_region(report, mca_path, result, export_config.fail_fast)
         });
+        progress::finish();
+        result?;
     }
 
-    println!("导出完成");
-    Ok(())
+    Ok(match incremental {
+        Some(state) => {
+            state.finish(world_path, output_path)?;
+            ExportManifest::load(output_path).ok().flatten()
+        }
+        None => None,
+    })
 }
 
-/// 导出 level.dat 文件（使用默认去噪字段）
-pub fn export_level_dat(level_path: &Path, output_path: &Path, denoise: bool) -> Result<()> {
-    let file = File::open(level_path)?;
-    let mut decoder = flate2::read::GzDecoder::new(file);
-    let mut data = Vec::new();
-    decoder.read_to_end(&mut data)?;
-
-    let mut value: Value = fastnbt::from_bytes(&data)?;
-
-    if denoise {
-        denoise_level(&mut value);
+/// level.dat 中的数据版本（`Data.DataVersion`），无法读取时返回 `None`
+fn level_data_version(level_dat: &Path) -> Option<i32> {
+    let Value::Compound(root) = read_gzip_nbt(level_dat).ok()? else {
+        return None;
+    };
+    match root.get("Data") {
+        Some(Value::Compound(data)) => match data.get("DataVersion") {
+            Some(Value::Int(version)) => Some(*version),
+            _ => None,
+        },
+        _ => None,
     }
+}
 
-    let json = json!({
-        "_gzip": 1,
-        "_data": nbt_to_json(&value)
-    });
-
-    let output = serde_json::to_string_pretty(&json)?;
-    fs::write(output_path, output)?;
-    Ok(())
+/// 增量导出过程中的清单状态
+struct IncrementalState {
+    /// 上一次导出的清单
+    previous: IncrementalManifest,
+    /// 导出设置是否与上一次相同
+    settings_match: bool,
+    /// 本次导出生成的清单
+    current: Mutex<IncrementalManifest>,
+    /// 本次处理过的源文件（包括失败的）
+    processed: Mutex<BTreeSet<String>>,
 }
 
-/// 导出 level.dat 文件（使用配置）
-pub fn export_level_dat_with_config(
-    level_path: &Path,
-    output_path: &Path,
-    denoise: bool,
-    denoise_config: &DenoiseConfig,
-    field_mapping_config: &FieldMappingConfig,
-) -> Result<()> {
-    let file = File::open(level_path)?;
-    let mut decoder = flate2::read::GzDecoder::new(file);
-    let mut data = Vec::new();
-    decoder.read_to_end(&mut data)?;
+impl IncrementalState {
+    fn load(output_path: &Path, settings: String) -> Self {
+        let previous = IncrementalManifest::load(output_path);
+        Self {
+            settings_match: previous.settings == settings,
+            previous,
+            current: Mutex::new(IncrementalManifest::new(settings)),
+            processed: Mutex::new(BTreeSet::new()),
+        }
+    }
 
-    let mut value: Value = fastnbt::from_bytes(&data)?;
+    /// 源文件未变化时跳过导出，否则清除该 region 的旧切片后重新导出
+    fn export_region(
+        &self,
+        world_path: &Path,
+        mca_path: &Path,
+        region_output: &Path,
+        export: impl FnOnce() -> Result<RegionReport>,
+    ) -> Result<RegionReport> {
+        let key = source_key(world_path, mca_path);
+        self.processed.lock().unwrap().insert(key.clone());
+
+        let previous = self.previous.regions.get(&key);
+        let state = source_state(mca_path, previous)?;
+        if self.settings_match && previous.is_some_and(|p| p.hash == state.hash) {
+            self.current.lock().unwrap().regions.insert(key, state);
+            return Ok(RegionReport {
+                unchanged: true,
+                ..RegionReport::new(mca_path)
+            });
+        }
 
-    if denoise {
-        denoise_level_with_config(&mut value, denoise_config);
+        remove_region_slices(region_output, mca_path)?;
+        let report = export()?;
+        self.current.lock().unwrap().regions.insert(key, state);
+        Ok(report)
     }
 
-    let mut json_data = nbt_to_json(&value);
-
-    // 应用字段名映射
-    let mapper = FieldMapper::from_config(field_mapping_config);
-    mapper.shorten_json_keys(&mut json_data);
+    /// 保留未处理（如不在工作区域内）的 region 记录，清除源文件已删除的 region 的切片，并写入清单
+    fn finish(self, world_path: &Path, output_path: &Path) -> Result<()> {
+        let mut current = self.current.into_inner().unwrap();
+        let processed = self.processed.into_inner().unwrap();
 
-    let json = json!({
-        "_gzip": 1,
-        "_data": json_data
-    });
+        for (key, state) in self.previous.regions {
+            if processed.contains(&key) {
+                continue;
+            }
+            if world_path.join(&key).exists() {
+                if self.settings_match {
+                    current.regions.insert(key, state);
+                }
+            } else {
+                let output_mca = output_path.join(&key);
+                if let Some(region_output) = output_mca.parent() {
+                    remove_region_slices(region_output, &output_mca)?;
+                }
+                info!("  移除 {}", key);
+            }
+        }
 
-    let output = serde_json::to_string_pretty(&json)?;
-    fs::write(output_path, output)?;
-    Ok(())
+        current.save(output_path)
+    }
 }
 
-/// 单个切片的最大大小（字节）
-const MAX_SLICE_SIZE: usize = 8 * 1024 * 1024; // 8MB
+/// 源 MCA 文件相对世界目录的路径（统一使用 `/` 分隔）
+pub(crate) fn source_key(world_path: &Path, mca_path: &Path) -> String {
+    mca_path
+        .strip_prefix(world_path)
+        .unwrap_or(mca_path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
 
-/// 导出单个 MCA 文件（使用默认去噪字段）
-/// 超过 8MB 自动切片
-pub fn export_mca(
-    mca_path: &Path,
-    output_dir: &Path,
-    denoise: bool,
-    aggressive: bool,
-) -> Result<()> {
+/// 删除某个 region 已导出的所有切片文件（包括每区块一个文件的目录和方块实体文件）
+pub(crate) fn remove_region_slices(region_output: &Path, mca_path: &Path) -> Result<()> {
     let filename = mca_path.file_name().unwrap().to_str().unwrap();
-    let (rx, rz) = parse_mca_filename(filename).context("无效的 MCA 文件名")?;
-
-    let mut chunks = read_mca(mca_path)?;
-    if chunks.is_empty() {
+    let (rx, rz, _) = parse_region_filename(filename).context("无效的区域文件名")?;
+    if !region_output.exists() {
         return Ok(());
     }
+    let be_dir = region_output.join(BLOCK_ENTITIES_DIR);
+    if be_dir.is_dir() {
+        remove_region_slices(&be_dir, mca_path)?;
+    }
 
-    fs::create_dir_all(output_dir)?;
+    let chunk_dir = region_output.join(format!("r.{}.{}", rx, rz));
+    if chunk_dir.is_dir() {
+        fs::remove_dir_all(&chunk_dir)?;
+    }
+    for format in [SliceFormat::Json, SliceFormat::Snbt] {
+        // 旧版本导出的方块实体文件（在 `be/` 中或 region 目录本身）
+        for path in [
+            region_output.join(block_entities_file_name(rx, rz, format)),
+            region_output.join(noise_file_name(rx, rz, format)),
+        ] {
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+
+    let prefix = format!("r.{}.{}.", rx, rz);
+    for entry in fs::read_dir(region_output)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        // 切片 `N.{ext}` 或分层切片 `N.{layer}.{ext}`
+        let is_slice = name.strip_prefix(&prefix).is_some_and(|rest| {
+            [SliceFormat::Json, SliceFormat::Snbt].iter().any(|format| {
+                rest.strip_suffix(&format!(".{}", format.extension()))
+                    .map(|id| {
+                        ChunkLayer::ALL
+                            .iter()
+                            .find_map(|layer| id.strip_suffix(&format!(".{}", layer.name())))
+                            .unwrap_or(id)
+                    })
+                    .is_some_and(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+            })
+        });
+        if is_slice {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// 预览整个世界的去噪效果（只读，不写入任何文件）
+///
+/// 对 level.dat 和每个 region 中第一个完整区块执行去噪预览，
+/// 返回 `(标签, 预览结果)` 列表
+pub fn preview_world_denoise(
+    world_path: &Path,
+    aggressive: bool,
+    config: &Config,
+) -> Result<Vec<(String, DenoiseDiff)>> {
+    let mut previews = Vec::new();
+
+    let level_dat = world_path.join("level.dat");
+    if level_dat.exists() {
+        let value = read_gzip_nbt(&level_dat)?;
+        previews.push((
+            "level.dat".to_string(),
+            preview_denoise_level(&value, &config.denoise)?,
+        ));
+    }
+
+    for (dim_folder, _) in &dimensions(world_path) {
+        let dimension = (!dim_folder.is_empty()).then_some(dim_folder.as_str());
+        let region_path = if dim_folder.is_empty() {
+            world_path.join("region")
+        } else {
+            world_path.join(dim_folder).join("region")
+        };
+
+        if !region_path.exists() {
+            continue;
+        }
+
+        for mca_path in region_files(&region_path)? {
+            let chunks = read_mca(&mca_path)?;
+            let statuses = &config.export.included_statuses;
+            if let Some(chunk) = chunks
+                .iter()
+                .find(|c| has_included_status(&c.data, statuses))
+            {
+                let label = format!(
+                    "{} ({}, {})",
+                    mca_path
+                        .strip_prefix(world_path)
+                        .unwrap_or(&mca_path)
+                        .display(),
+                    chunk.x,
+                    chunk.z
+                );
+                previews.push((
+                    label,
+                    preview_denoise_chunk(&chunk.data, aggressive, &config.denoise, dimension)?,
+                ));
+            }
+        }
+    }
+
+    Ok(previews)
+}
 
-    let mut all_chunks = Vec::new();
+/// 读取 gzip 压缩的 NBT 文件
+///
+/// 根复合标签的名称非空时记录在顶层的 `_root_name` 中，还原时据此写回
+pub(crate) fn read_gzip_nbt(path: &Path) -> Result<Value> {
+    let data = gzip_decompress(&fs::read(path)?)?;
+    let mut value = fastnbt::from_bytes(&data)?;
+    if let Some(root_name) = nbt_root_name(&data) {
+        insert_root_name(&mut value, &root_name);
+    }
+    Ok(value)
+}
+
+/// 导出 data/、playerdata/ 等目录中的所有 `.dat` 文件（gzip 压缩的 NBT，与 level.dat 格式相同，不去噪）
+///
+/// 每个文件导出为同名的 `.json` 或 `.snbt`，无法解析的文件输出警告后跳过
+fn export_data_dir(
+    data_dir: &Path,
+    output_dir: &Path,
+    config: &Config,
+    sink: &OutputSink,
+) -> Result<()> {
+    let mut dat_files: Vec<_> = fs::read_dir(data_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "dat"))
+        .collect();
+    dat_files.sort();
+    if dat_files.is_empty() {
+        return Ok(());
+    }
+
+    info!("导出 {} ({} 个文件)", data_dir.display(), dat_files.len());
+    let format = config.export.format;
+    for dat_path in dat_files {
+        let stem = dat_path.file_stem().unwrap().to_string_lossy();
+        let path = output_dir.join(format!("{}.{}", stem, format.extension()));
+        match level_dat_output(&dat_path, false, config, format) {
+            Ok(output) => sink.write(&path, output)?,
+            Err(e) => warn!("跳过 {}: {}", dat_path.display(), e),
+        }
+    }
+    Ok(())
+}
+
+/// 按输出格式转换 level.dat 或 data/ 中的 `.dat` 文件
+fn level_dat_output(
+    dat_path: &Path,
+    denoise: bool,
+    config: &Config,
+    format: SliceFormat,
+) -> Result<String> {
+    match format {
+        SliceFormat::Json => {
+            level_dat_json(dat_path, denoise, &config.denoise, &config.field_mapping)
+        }
+        SliceFormat::Snbt => level_dat_snbt(dat_path, denoise, &config.denoise),
+    }
+}
+
+/// 导出 level.dat 文件（使用默认去噪字段）
+pub fn export_level_dat(level_path: &Path, output_path: &Path, denoise: bool) -> Result<()> {
+    let mut value = read_gzip_nbt(level_path)?;
+
+    if denoise {
+        denoise_level(&mut value);
+    }
+
+    let json = json!({
+        "_gzip": 1,
+        "_data": nbt_to_json(&value)
+    });
+
+    let output = serde_json::to_string_pretty(&json)?;
+    write_atomically(output_path, output)?;
+    Ok(())
+}
+
+/// 导出 level.dat 文件（使用配置）
+pub fn export_level_dat_with_config(
+    level_path: &Path,
+    output_path: &Path,
+    denoise: bool,
+    denoise_config: &DenoiseConfig,
+    field_mapping_config: &FieldMappingConfig,
+) -> Result<()> {
+    let output = level_dat_json(level_path, denoise, denoise_config, field_mapping_config)?;
+    write_atomically(output_path, output)?;
+    Ok(())
+}
+
+/// 将 level.dat 转换为 JSON 文本（`{"_gzip": 1, "_data": ...}`）
+fn level_dat_json(
+    level_path: &Path,
+    denoise: bool,
+    denoise_config: &DenoiseConfig,
+    field_mapping_config: &FieldMappingConfig,
+) -> Result<String> {
+    let mut value = read_gzip_nbt(level_path)?;
+
+    if denoise {
+        denoise_level_with_config(&mut value, denoise_config)?;
+    }
+
+    let mut json_data = nbt_to_json(&value);
+
+    // 应用字段名映射
+    let mapper = FieldMapper::from_config(field_mapping_config);
+    mapper.shorten_json_keys(&mut json_data);
+
+    let json = json!({
+        "_gzip": 1,
+        "_data": json_data
+    });
+
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
+/// 导出 level.dat 文件为 SNBT
+pub fn export_level_dat_snbt(
+    level_path: &Path,
+    output_path: &Path,
+    denoise: bool,
+    denoise_config: &DenoiseConfig,
+) -> Result<()> {
+    let output = level_dat_snbt(level_path, denoise, denoise_config)?;
+    write_atomically(output_path, output)?;
+    Ok(())
+}
+
+/// 将 level.dat 转换为 SNBT 文本（以换行结尾）
+fn level_dat_snbt(
+    level_path: &Path,
+    denoise: bool,
+    denoise_config: &DenoiseConfig,
+) -> Result<String> {
+    let mut value = read_gzip_nbt(level_path)?;
+
+    if denoise {
+        denoise_level_with_config(&mut value, denoise_config)?;
+    }
+
+    let mut output = nbt_to_snbt(&value);
+    output.push('\n');
+    Ok(output)
+}
+
+/// 导出单个 MCA 文件（使用默认去噪字段）
+/// 超过 8MB 自动切片
+pub fn export_mca(
+    mca_path: &Path,
+    output_dir: &Path,
+    denoise: bool,
+    aggressive: bool,
+) -> Result<RegionReport> {
+    let filename = mca_path.file_name().unwrap().to_str().unwrap();
+    let (rx, rz, _) = parse_region_filename(filename).context("无效的区域文件名")?;
+
+    let mut report = RegionReport::new(mca_path);
+    let Some(reader) = McaReader::open(mca_path)? else {
+        return Ok(report);
+    };
+
+    let export_config = ExportConfig::default();
+    let mut writer = SliceWriter::new(output_dir, rx, rz, &export_config, &OutputSink::Disk);
+
+    // 逐个区块读取、处理并写入，内存中只保留当前区块和当前切片
+    let mut chunks = reader.into_chunks_in_order(chunk_order(&export_config));
+    for chunk in chunks.by_ref() {
+        let mut chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => match e.downcast::<InvalidChunk>() {
+                Ok(invalid) => {
+                    warn!("{} {}，跳过", filename, invalid);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            },
+        };
+        let index = (chunk.z * 32 + chunk.x) as usize;
 
-    for chunk in &mut chunks {
         // 跳过非完整区块
         if !is_full_chunk(&chunk.data) {
+            report.chunks_skipped_not_full += 1;
+            report.denoise.chunks_skipped += 1;
             continue;
         }
 
         if denoise {
             denoise_chunk(&mut chunk.data, aggressive);
         }
+        report.denoise.sections_removed +=
+            filter_empty_sections(&mut chunk.data, DEFAULT_EMPTY_BLOCKS);
 
         let mut json = nbt_to_json(&chunk.data);
-        // 添加坐标到 JSON
+        // 添加坐标（和未去噪时的时间戳）到 JSON
         if let JsonValue::Object(ref mut obj) = json {
             obj.insert("x".to_string(), json!(chunk.x));
             obj.insert("z".to_string(), json!(chunk.z));
+            if !denoise {
+                obj.insert("_ts".to_string(), json!(chunk.timestamp));
+            }
         }
 
-        // 过滤空 sections 和空值
-        filter_empty_sections(&mut json);
+        // 过滤空值
         filter_empty_values(&mut json);
 
         // 跳过没有实际数据的区块
-        if !has_chunk_data(&json) {
+        if !has_chunk_data(&json, export_config.empty_chunk_policy) {
+            report.chunks_skipped_empty += 1;
+            report.denoise.chunks_skipped += 1;
             continue;
         }
 
         // 缩短字段名（最后一步，在所有检查之后）
         shorten_json_keys(&mut json);
 
-        all_chunks.push(json);
+        writer.push(index, serde_json::to_string(&json)?)?;
+        report.chunks_exported += 1;
     }
 
-    if all_chunks.is_empty() {
-        return Ok(());
+    report.chunks_failed = chunks.reader().map_or(0, McaReader::failed_chunks);
+    for file in writer.finish()? {
+        report.add_file(file);
     }
-
-    // 按大小切片写入
-    write_region_sliced(output_dir, rx, rz, &all_chunks)?;
-
-    Ok(())
+    Ok(report)
 }
 
 /// 导出单个 MCA 文件（使用配置）
+///
+/// `dimension` 为维度路径（如 `DIM-1`），主世界传 `None`；
+/// `filter` 返回 false 的区块不导出（按配置创建见 [`AndFilter::from_config`]）
+#[allow(clippy::too_many_arguments)]
 pub fn export_mca_with_config(
     mca_path: &Path,
     output_dir: &Path,
+    dimension: Option<&str>,
     denoise: bool,
     aggressive: bool,
     denoise_config: &DenoiseConfig,
     export_config: &ExportConfig,
     field_mapper: &FieldMapper,
-) -> Result<()> {
+    filter: &dyn ChunkFilter,
+) -> Result<RegionReport> {
+    check_slice_size(export_config)?;
+    let processor = ChunkProcessor::new(
+        dimension,
+        denoise,
+        aggressive,
+        denoise_config,
+        export_config,
+        field_mapper,
+        filter,
+    )?;
+    let sink = if export_config.dry_run {
+        OutputSink::DryRun
+    } else {
+        OutputSink::Disk
+    };
+    // 单独导出一个 region 时没有外层并行，区块级并行可以充分利用多核
+    export_mca_impl(mca_path, output_dir, &processor, true, &sink)
+}
+
+/// 区块级并行时每批读取的区块数量
+///
+/// 批内区块并行处理后按顺序写入，内存中最多保留一批区块
+const CHUNK_BATCH_SIZE: usize = 64;
+
+fn export_mca_impl(
+    mca_path: &Path,
+    output_dir: &Path,
+    processor: &ChunkProcessor,
+    parallel: bool,
+    sink: &OutputSink,
+) -> Result<RegionReport> {
     let filename = mca_path.file_name().unwrap().to_str().unwrap();
-    let (rx, rz) = parse_mca_filename(filename).context("无效的 MCA 文件名")?;
+    let (rx, rz, _) = parse_region_filename(filename).context("无效的区域文件名")?;
 
-    let mut chunks = read_mca(mca_path)?;
-    if chunks.is_empty() {
-        return Ok(());
+    let mut report = RegionReport::new(mca_path);
+    let bounds = processor.export_config.bounds;
+    if bounds.is_some_and(|b| !b.may_contain_region(rx, rz)) {
+        return Ok(report);
+    }
+    let reader = if processor.export_config.use_mmap {
+        McaReader::open_mmap(mca_path)?
+    } else {
+        McaReader::open(mca_path)?
+    };
+    let Some(mut reader) = reader else {
+        return Ok(report);
+    };
+
+    // 只保留区块范围内的区块（编号 `z * 32 + x` 为 region 内坐标）
+    let mut order = chunk_order(processor.export_config);
+    if let Some(bounds) = bounds {
+        order.retain(|&index| {
+            bounds.contains(rx * 32 + (index % 32) as i32, rz * 32 + (index / 32) as i32)
+        });
     }
 
-    fs::create_dir_all(output_dir)?;
+    let mut writer = SliceWriter::new(output_dir, rx, rz, processor.export_config, sink);
+    // 拆分出的方块实体按相同的切片设置写入 `be/`
+    let be_dir = output_dir.join(BLOCK_ENTITIES_DIR);
+    let mut be_writer = SliceWriter::new(&be_dir, rx, rz, processor.export_config, sink);
+    let mut layer_writers = ChunkLayer::ALL.map(|layer| {
+        SliceWriter::for_layer(output_dir, rx, rz, processor.export_config, sink, layer)
+    });
+    let mut noise_entries = Vec::new();
+    let mut formats = BTreeSet::new();
+    let batch_size = if parallel { CHUNK_BATCH_SIZE } else { 1 };
+
+    // 顺序读取一批区块，并行处理后再按写入顺序交给切片写入器
+    for batch in order.chunks(batch_size) {
+        let mut chunks = Vec::with_capacity(batch.len());
+        for &index in batch {
+            if let Some(chunk) = reader.read_chunk(index)? {
+                formats.insert(ChunkFormat::from_data_version(chunk.data_version()));
+                chunks.push((index, chunk));
+            }
+        }
 
-    let mut all_chunks = Vec::new();
+        let process = |(index, chunk): (usize, ChunkData)| {
+            let mut stats = DenoiseStats::default();
+            processor
+                .process(chunk, &mut stats)
+                .map(|outcome| (index, outcome, stats))
+        };
+        let results: Vec<_> = if parallel {
+            chunks.into_par_iter().map(process).collect()
+        } else {
+            chunks.into_iter().map(process).collect()
+        };
 
-    for chunk in &mut chunks {
-        // 跳过非完整区块
-        if !is_full_chunk(&chunk.data) {
-            continue;
+        for result in results {
+            let (index, outcome, stats) = result?;
+            let (x, z) = (index % 32, index / 32);
+            report.denoise.merge(&stats);
+            match outcome {
+                ChunkOutcome::NotFull => {
+                    debug!("  {} 区块 ({}, {}) 生成状态未包含，跳过", filename, x, z);
+                    report.chunks_skipped_not_full += 1;
+                    report.denoise.chunks_skipped += 1;
+                }
+                ChunkOutcome::Empty => {
+                    debug!("  {} 区块 ({}, {}) 没有数据，跳过", filename, x, z);
+                    report.chunks_skipped_empty += 1;
+                    report.denoise.chunks_skipped += 1;
+                }
+                ChunkOutcome::Filtered => {
+                    debug!("  {} 区块 ({}, {}) 被过滤器排除，跳过", filename, x, z);
+                    report.chunks_skipped_filtered += 1;
+                    report.denoise.chunks_skipped += 1;
+                }
+                ChunkOutcome::Exported {
+                    chunk,
+                    block_entities,
+                    noise,
+                    layers,
+                } => {
+                    debug!("  {} 区块 ({}, {}) {} 字节", filename, x, z, chunk.len());
+                    writer.push(index, chunk)?;
+                    for (layer_writer, fragment) in layer_writers.iter_mut().zip(layers) {
+                        if let Some(fragment) = fragment {
+                            layer_writer.push(index, fragment)?;
+                        }
+                    }
+                    if let Some(entry) = block_entities {
+                        be_writer.push(index, entry)?;
+                    }
+                    if let Some(entry) = noise {
+                        noise_entries.push((index, entry));
+                    }
+                    report.chunks_exported += 1;
+                }
+            }
         }
+    }
 
-        if denoise {
-            denoise_chunk_with_config(&mut chunk.data, aggressive, denoise_config);
+    if formats.len() > 1 {
+        let formats: Vec<&str> = formats.iter().map(ChunkFormat::description).collect();
+        warn!(
+            "{} 中混有不同格式的区块（{}），可能是升级世界时部分区块未被转换",
+            filename,
+            formats.join("、")
+        );
+    }
+
+    report.chunks_failed = reader.failed_chunks();
+    for writer in [writer, be_writer].into_iter().chain(layer_writers) {
+        for file in writer.finish()? {
+            report.add_file(file);
         }
+    }
 
-        let mut json = nbt_to_json(&chunk.data);
-        // 添加坐标到 JSON
-        if let JsonValue::Object(ref mut obj) = json {
-            obj.insert("x".to_string(), json!(chunk.x));
-            obj.insert("z".to_string(), json!(chunk.z));
+    // 被移除的值按 (x, z) 排序，与切片设置无关
+    if !noise_entries.is_empty() {
+        let format = processor.export_config.format;
+        noise_entries.sort_by_key(|(index, _)| (index % 32, index / 32));
+        let entries: Vec<&str> = noise_entries
+            .iter()
+            .map(|(_, entry)| entry.as_str())
+            .collect();
+        report.add_file(write_chunks_direct(
+            &output_dir.join(noise_file_name(rx, rz, format)),
+            &entries,
+            format,
+            sink,
+        )?);
+    }
+    Ok(report)
+}
+
+/// 拆分出的方块实体文件所在的子目录 `region/be/`，可以单独加入 `.gitignore`
+pub(crate) const BLOCK_ENTITIES_DIR: &str = "be";
+
+/// 旧版本导出的方块实体文件名 `r.{rx}.{rz}.entities.{ext}`（现在按切片写入 [`BLOCK_ENTITIES_DIR`]）
+fn block_entities_file_name(rx: i32, rz: i32, format: SliceFormat) -> String {
+    format!("r.{}.{}.entities.{}", rx, rz, format.extension())
+}
+
+/// 无损去噪记录被移除的值的文件名 `r.{rx}.{rz}.noise.{ext}`
+fn noise_file_name(rx: i32, rz: i32, format: SliceFormat) -> String {
+    format!("r.{}.{}.noise.{}", rx, rz, format.extension())
+}
+
+/// 导出前依次检查的区块过滤器，在去噪和过滤空值之后的区块 JSON 上执行
+struct ChunkChecks<'a> {
+    /// `included_statuses`，未通过的区块计为未完整生成
+    status: StatusFilter,
+    /// `skip_empty_chunks` 启用时按 `empty_chunk_policy` 检查，未通过的区块计为空区块
+    has_data: Option<HasDataFilter>,
+    /// `bounds` 和调用方传入的过滤器，未通过的区块计为被过滤
+    filter: AndFilter<'a>,
+}
+
+impl<'a> ChunkChecks<'a> {
+    fn new(config: &ExportConfig, filter: &'a dyn ChunkFilter) -> Self {
+        let bounds = config
+            .bounds
+            .map(|bounds| -> Box<dyn ChunkFilter> { Box::new(BoundsFilter(bounds)) });
+        Self {
+            status: StatusFilter {
+                statuses: config.included_statuses.clone(),
+            },
+            has_data: config
+                .skip_empty_chunks
+                .then_some(HasDataFilter(config.empty_chunk_policy)),
+            filter: AndFilter(bounds.into_iter().chain([Box::new(filter) as _]).collect()),
+        }
+    }
+
+    /// 区块未通过检查时返回对应的处理结果
+    fn rejects(&self, json: &JsonValue) -> Option<ChunkOutcome> {
+        if !self.status.should_export(json) {
+            Some(ChunkOutcome::NotFull)
+        } else if self.has_data.is_some_and(|f| !f.should_export(json)) {
+            Some(ChunkOutcome::Empty)
+        } else if !self.filter.should_export(json) {
+            Some(ChunkOutcome::Filtered)
+        } else {
+            None
+        }
+    }
+}
+
+/// 单个区块的处理结果
+enum ChunkOutcome {
+    /// 生成状态不在 `included_statuses` 中的区块，跳过
+    NotFull,
+    /// 没有实际数据的区块，跳过
+    Empty,
+    /// 被区块过滤器排除的区块，跳过
+    Filtered,
+    /// 序列化后的区块，以及拆分出的方块实体（启用 `split_block_entities` 时）、
+    /// 被移除的值（启用无损去噪时）和各图层片段（启用 `layered` 时，按 [`ChunkLayer::ALL`] 顺序）
+    Exported {
+        chunk: String,
+        block_entities: Option<String>,
+        noise: Option<String>,
+        layers: [Option<String>; 3],
+    },
+}
+
+/// 维度中使用区域文件格式的目录，导出和还原时保持相同的目录结构
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RegionKind {
+    /// `region/`：方块区块
+    Chunks,
+    /// `entities/`：实体（1.17+）
+    Entities,
+    /// `poi/`：兴趣点（村民工作站点、床等）
+    Poi,
+}
+
+impl RegionKind {
+    pub(crate) const ALL: [RegionKind; 3] =
+        [RegionKind::Chunks, RegionKind::Entities, RegionKind::Poi];
+
+    /// 维度中的目录名
+    pub(crate) fn folder(self) -> &'static str {
+        match self {
+            RegionKind::Chunks => "region",
+            RegionKind::Entities => "entities",
+            RegionKind::Poi => "poi",
+        }
+    }
+}
+
+/// 分层导出的图层，每个图层写入独立的切片集合 `r.{rx}.{rz}.{N}.{layer}.{ext}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChunkLayer {
+    /// sections 中除 biomes 外的部分（block_states、光照等）
+    Blocks,
+    /// sections 中的 biomes
+    Biomes,
+    /// block_entities
+    BlockEntities,
+}
+
+impl ChunkLayer {
+    pub(crate) const ALL: [ChunkLayer; 3] = [
+        ChunkLayer::Blocks,
+        ChunkLayer::Biomes,
+        ChunkLayer::BlockEntities,
+    ];
+
+    /// 文件名中的图层名
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ChunkLayer::Blocks => "blocks",
+            ChunkLayer::Biomes => "biomes",
+            ChunkLayer::BlockEntities => "be",
+        }
+    }
+}
+
+/// 将区块 NBT 拆分为各图层片段，与 [`split_chunk_layers`] 相同，用于 SNBT 输出
+fn split_chunk_layers_nbt(chunk: &mut Value) -> [Option<Value>; 3] {
+    let Value::Compound(map) = chunk else {
+        return [None, None, None];
+    };
+    let (x, z) = (map.get("x").cloned(), map.get("z").cloned());
+    let fragment = |key: &str, value: Value| {
+        let mut fragment = HashMap::new();
+        fragment.extend(x.clone().map(|x| ("x".to_string(), x)));
+        fragment.extend(z.clone().map(|z| ("z".to_string(), z)));
+        fragment.insert(key.to_string(), value);
+        Value::Compound(fragment)
+    };
+
+    let mut block_sections = Vec::new();
+    let mut biome_sections = Vec::new();
+    if let Some(Value::List(sections)) = map.remove("sections") {
+        for mut section in sections {
+            if let Value::Compound(sec) = &mut section {
+                if let Some(biomes) = sec.remove("biomes") {
+                    let mut entry = HashMap::new();
+                    if let Some(y) = sec.get("Y") {
+                        entry.insert("Y".to_string(), y.clone());
+                    }
+                    entry.insert("biomes".to_string(), biomes);
+                    biome_sections.push(Value::Compound(entry));
+                }
+            }
+            block_sections.push(section);
+        }
+    }
+
+    let non_empty = |sections: Vec<Value>| {
+        (!sections.is_empty()).then(|| fragment("sections", Value::List(sections)))
+    };
+    [
+        non_empty(block_sections),
+        non_empty(biome_sections),
+        map.remove("block_entities")
+            .map(|entities| fragment("block_entities", entities)),
+    ]
+}
+
+/// 展开后的区块转换为 JSON 并添加坐标和时间戳
+fn chunk_json(
+    data: &Value,
+    options: &NbtJsonOptions,
+    x: i32,
+    z: i32,
+    timestamp: Option<u32>,
+) -> JsonValue {
+    let mut json = nbt_to_json_with_options(data, options);
+    if let JsonValue::Object(ref mut obj) = json {
+        obj.insert("x".to_string(), json!(x));
+        obj.insert("z".to_string(), json!(z));
+        if let Some(timestamp) = timestamp {
+            obj.insert("_ts".to_string(), json!(timestamp));
+        }
+    }
+    json
+}
+
+/// 时间戳的 NBT 值，与 JSON 整数转换为 NBT 时的类型一致
+fn timestamp_value(timestamp: u32) -> Value {
+    i32::try_from(timestamp).map_or(Value::Long(timestamp as i64), Value::Int)
+}
+
+/// 将区块 JSON 拆分为各图层片段，返回值按 [`ChunkLayer::ALL`] 顺序
+///
+/// 每个片段都带有 `x`/`z`，sections 片段保留 `Y` 用于还原时合并；
+/// 剩余字段留在 `chunk` 中写入主切片
+fn split_chunk_layers(chunk: &mut JsonValue) -> [Option<JsonValue>; 3] {
+    let JsonValue::Object(obj) = chunk else {
+        return [None, None, None];
+    };
+    let (x, z) = (obj.get("x").cloned(), obj.get("z").cloned());
+    let fragment = |key: &str, value: JsonValue| {
+        let mut map = JsonMap::new();
+        map.insert("x".to_string(), x.clone().unwrap_or(JsonValue::Null));
+        map.insert("z".to_string(), z.clone().unwrap_or(JsonValue::Null));
+        map.insert(key.to_string(), value);
+        JsonValue::Object(map)
+    };
+
+    let mut block_sections = Vec::new();
+    let mut biome_sections = Vec::new();
+    if let Some(JsonValue::Array(sections)) = obj.remove("sections") {
+        for mut section in sections {
+            if let JsonValue::Object(sec) = &mut section {
+                if let Some(biomes) = sec.remove("biomes") {
+                    let mut entry = JsonMap::new();
+                    if let Some(y) = sec.get("Y") {
+                        entry.insert("Y".to_string(), y.clone());
+                    }
+                    entry.insert("biomes".to_string(), biomes);
+                    biome_sections.push(JsonValue::Object(entry));
+                }
+            }
+            block_sections.push(section);
+        }
+    }
+
+    let non_empty = |sections: Vec<JsonValue>| {
+        (!sections.is_empty()).then(|| fragment("sections", JsonValue::Array(sections)))
+    };
+    [
+        non_empty(block_sections),
+        non_empty(biome_sections),
+        obj.remove("block_entities")
+            .map(|entities| fragment("block_entities", entities)),
+    ]
+}
+
+/// 区块处理参数（同一维度内的所有区块共享）
+struct ChunkProcessor<'a> {
+    /// 编译后的去噪配置，不去噪时为 `None`
+    denoiser: Option<ChunkDenoiser>,
+    aggressive: bool,
+    denoise_config: &'a DenoiseConfig,
+    export_config: &'a ExportConfig,
+    field_mapper: &'a FieldMapper,
+    json_options: NbtJsonOptions,
+    checks: ChunkChecks<'a>,
+    /// 区域文件的种类，只有方块区块按生成状态、空区块和过滤器筛选（去噪见 [`denoise_region_chunk`]）
+    kind: RegionKind,
+}
+
+impl<'a> ChunkProcessor<'a> {
+    fn new(
+        dimension: Option<&'a str>,
+        denoise: bool,
+        aggressive: bool,
+        denoise_config: &'a DenoiseConfig,
+        export_config: &'a ExportConfig,
+        field_mapper: &'a FieldMapper,
+        filter: &'a dyn ChunkFilter,
+    ) -> Result<Self> {
+        let denoiser = denoise
+            .then(|| ChunkDenoiser::new(denoise_config, dimension))
+            .transpose()?;
+        Ok(Self {
+            denoiser,
+            aggressive,
+            denoise_config,
+            export_config,
+            field_mapper,
+            checks: ChunkChecks::new(export_config, filter),
+            json_options: NbtJsonOptions::from_config(export_config),
+            kind: RegionKind::Chunks,
+        })
+    }
+
+    /// 去噪、转换、过滤并序列化单个区块
+    fn process(&self, mut chunk: ChunkData, stats: &mut DenoiseStats) -> Result<ChunkOutcome> {
+        if let Some(required) = self.export_config.require_data_version {
+            let found = chunk.data_version();
+            if found != Some(required) {
+                return Err(DataVersionMismatch {
+                    x: chunk.x,
+                    z: chunk.z,
+                    found,
+                    required,
+                }
+                .into());
+            }
+        }
+
+        let terrain = self.kind == RegionKind::Chunks;
+
+        // 在去噪移除空 section 之前展开，此时 section 数与世界高度一致
+        if terrain && self.export_config.readable_heightmaps {
+            unpack_heightmaps(&mut chunk.data);
         }
 
-        // 过滤空 sections 和空值
-        filter_empty_sections(&mut json);
+        // 无损去噪时保留去噪前的区块，之后与输出比较得到被移除的值
+        let original = self.denoise_config.lossless.then(|| chunk.data.clone());
+
+        if let Some(denoiser) = &self.denoiser {
+            denoise_region_chunk(
+                denoiser,
+                self.kind,
+                &mut chunk.data,
+                self.aggressive,
+                Some(stats),
+            );
+        }
+
+        // 过滤空 sections
+        stats.sections_removed +=
+            filter_empty_sections(&mut chunk.data, &self.export_config.empty_blocks);
+
+        let strip_timestamp = self
+            .denoiser
+            .as_ref()
+            .is_some_and(ChunkDenoiser::strip_timestamp);
+        let timestamp = (!strip_timestamp).then_some(chunk.timestamp);
+        self.expand(&mut chunk.data);
+        let mut json = chunk_json(&chunk.data, &self.json_options, chunk.x, chunk.z, timestamp);
+        let original = original.map(|mut data| {
+            self.expand(&mut data);
+            chunk_json(
+                &data,
+                &self.json_options,
+                chunk.x,
+                chunk.z,
+                Some(chunk.timestamp),
+            )
+        });
+
+        // SNBT 直接由区块的 NBT 写出，保留空值；无损去噪时按保留的内容计算被移除的值
+        let snbt = self.export_config.format == SliceFormat::Snbt;
+        let kept = (snbt && original.is_some()).then(|| json.clone());
+
+        // 过滤空值
         filter_empty_values(&mut json);
 
-        // 跳过没有实际数据的区块（可配置）
-        if export_config.skip_empty_chunks && !has_chunk_data(&json) {
-            continue;
+        // 跳过生成状态未包含、没有实际数据或被过滤器排除的区块
+        if let Some(skipped) = terrain.then(|| self.checks.rejects(&json)).flatten() {
+            return Ok(skipped);
         }
 
-        // 缩短字段名（最后一步，在所有检查之后）
-        field_mapper.shorten_json_keys(&mut json);
+        // 无损去噪：去噪和过滤移除的值写入 noise 文件
+        let noise = match original
+            .and_then(|original| removed_values(&original, kept.as_ref().unwrap_or(&json)))
+        {
+            Some(JsonValue::Object(removed)) => {
+                let mut entry = JsonMap::new();
+                entry.insert("x".to_string(), json!(chunk.x));
+                entry.insert("z".to_string(), json!(chunk.z));
+                entry.extend(removed);
+                Some(self.serialize(&mut JsonValue::Object(entry))?)
+            }
+            _ => None,
+        };
+
+        if snbt {
+            let mut value = chunk.data;
+            if let Value::Compound(map) = &mut value {
+                map.insert("x".to_string(), Value::Int(chunk.x));
+                map.insert("z".to_string(), Value::Int(chunk.z));
+                if let Some(timestamp) = timestamp {
+                    map.insert("_ts".to_string(), timestamp_value(timestamp));
+                }
+            }
+            return Ok(self.split_nbt(value, chunk.x, chunk.z, noise));
+        }
 
-        all_chunks.push(json);
+        // 分层导出：sections 和方块实体拆到各图层，主切片只保留其余字段
+        let mut layers = [None, None, None];
+        if self.export_config.layered {
+            for (slot, fragment) in layers.iter_mut().zip(split_chunk_layers(&mut json)) {
+                *slot = fragment.map(|mut f| self.serialize(&mut f)).transpose()?;
+            }
+        }
+
+        // 方块实体单独写入 entities 文件
+        let block_entities = match &mut json {
+            JsonValue::Object(obj) if self.export_config.split_block_entities => obj
+                .remove("block_entities")
+                .map(|entities| {
+                    let mut entry = json!({
+                        "x": chunk.x,
+                        "z": chunk.z,
+                        "block_entities": entities,
+                    });
+                    self.serialize(&mut entry)
+                })
+                .transpose()?,
+            _ => None,
+        };
+
+        Ok(ChunkOutcome::Exported {
+            chunk: self.serialize(&mut json)?,
+            block_entities,
+            noise,
+            layers,
+        })
     }
 
-    if all_chunks.is_empty() {
-        return Ok(());
+    /// 按配置展开方块状态和生物群系
+    fn expand(&self, data: &mut Value) {
+        if self.export_config.readable_block_states {
+            unpack_block_states(data);
+        }
+        if self.export_config.readable_biomes {
+            unpack_biomes(data);
+        }
     }
 
-    // 按大小切片写入
-    write_region_sliced(output_dir, rx, rz, &all_chunks)?;
+    /// 将区块 NBT（已带有坐标和时间戳）按配置拆分后写为 SNBT
+    fn split_nbt(&self, mut value: Value, x: i32, z: i32, noise: Option<String>) -> ChunkOutcome {
+        let mut layers = [None, None, None];
+        if self.export_config.layered {
+            for (slot, fragment) in layers.iter_mut().zip(split_chunk_layers_nbt(&mut value)) {
+                *slot = fragment.map(|f| nbt_to_snbt(&f));
+            }
+        }
+        let block_entities = match &mut value {
+            Value::Compound(map) if self.export_config.split_block_entities => {
+                map.remove("block_entities").map(|entities| {
+                    nbt_to_snbt(&Value::Compound(HashMap::from([
+                        ("x".to_string(), Value::Int(x)),
+                        ("z".to_string(), Value::Int(z)),
+                        ("block_entities".to_string(), entities),
+                    ])))
+                })
+            }
+            _ => None,
+        };
+        ChunkOutcome::Exported {
+            chunk: nbt_to_snbt(&value),
+            block_entities,
+            noise,
+            layers,
+        }
+    }
 
-    Ok(())
+    /// 按输出格式序列化
+    fn serialize(&self, json: &mut JsonValue) -> Result<String> {
+        Ok(match self.export_config.format {
+            SliceFormat::Json => {
+                // 缩短字段名（最后一步，在所有检查之后）
+                self.field_mapper.shorten_json_keys(json);
+                serde_json::to_string(json)?
+            }
+            // SNBT 自带完整类型信息，不缩短字段名
+            SliceFormat::Snbt => nbt_to_snbt(&json_to_nbt(json)?),
+        })
+    }
 }
 
-/// 按大小切片写入 region 文件
-fn write_region_sliced(output_dir: &Path, rx: i32, rz: i32, chunks: &[JsonValue]) -> Result<()> {
-    // 序列化所有区块
-    let serialized: Vec<String> = chunks
-        .iter()
-        .map(|c| serde_json::to_string(c).unwrap_or_default())
-        .collect();
+/// 区块的写入顺序（region 内编号 `z * 32 + x`）
+///
+/// 切片内的区块按 (x, z) 排序，使输出顺序只由坐标决定；
+/// 稳定切片模式下先按切片分组，再在每个切片内排序
+fn chunk_order(export_config: &ExportConfig) -> Vec<usize> {
+    let group_size = match export_config.slice_mode {
+        SliceMode::Stable => export_config.chunks_per_slice.max(1),
+        SliceMode::Size => CHUNKS_PER_REGION,
+    };
+    let mut order: Vec<usize> = (0..CHUNKS_PER_REGION).collect();
+    for group in order.chunks_mut(group_size) {
+        group.sort_by_key(|&index| (index % 32, index / 32));
+    }
+    order
+}
 
-    let mut slice_id = 0;
-    let mut current_slice: Vec<&str> = Vec::new();
-    let mut current_size = 0usize;
+/// 流式切片写入器
+///
+/// 区块按 [`chunk_order`] 的顺序逐个追加，只缓存当前打开的切片，
+/// 根据切片方式决定何时写出当前切片并开始下一个；
+/// [`ExportLayout::PerChunk`] 布局下每个区块直接写入单独的文件
+struct SliceWriter<'a> {
+    output_dir: &'a Path,
+    rx: i32,
+    rz: i32,
+    config: &'a ExportConfig,
+    sink: &'a OutputSink,
+    /// 图层文件名后缀（如 `.blocks`），主切片为空
+    suffix: String,
+    slice_id: usize,
+    current: Vec<String>,
+    current_size: usize,
+    /// 单个切片文件的最大大小（字节）
+    max_slice_size: usize,
+    files: Vec<SliceFile>,
+}
 
-    for chunk_str in &serialized {
+impl<'a> SliceWriter<'a> {
+    fn new(
+        output_dir: &'a Path,
+        rx: i32,
+        rz: i32,
+        config: &'a ExportConfig,
+        sink: &'a OutputSink,
+    ) -> Self {
+        Self {
+            output_dir,
+            rx,
+            rz,
+            config,
+            sink,
+            suffix: String::new(),
+            slice_id: 0,
+            current: Vec::new(),
+            current_size: 0,
+            max_slice_size: config.max_slice_size_bytes(),
+            files: Vec::new(),
+        }
+    }
+
+    /// 写入分层导出中某一图层的切片写入器
+    fn for_layer(
+        output_dir: &'a Path,
+        rx: i32,
+        rz: i32,
+        config: &'a ExportConfig,
+        sink: &'a OutputSink,
+        layer: ChunkLayer,
+    ) -> Self {
+        Self {
+            suffix: format!(".{}", layer.name()),
+            ..Self::new(output_dir, rx, rz, config, sink)
+        }
+    }
+
+    /// 追加一个已序列化的区块，`index` 为区块在 region 内的编号（`z * 32 + x`）
+    fn push(&mut self, index: usize, chunk_str: String) -> Result<()> {
+        if self.config.layout == ExportLayout::PerChunk {
+            return self.write_chunk_file(index, &chunk_str);
+        }
+
+        let max_slice_size = self.max_slice_size;
         let chunk_size = chunk_str.len();
+        if chunk_size > max_slice_size {
+            anyhow::bail!(
+                "切片大小上限 {} 字节不足以容纳单个区块（{} 字节），请增大 max_slice_size_mb",
+                max_slice_size,
+                chunk_size
+            );
+        }
 
-        // 如果当前切片加上这个区块会超过限制，先写入当前切片
-        if !current_slice.is_empty() && current_size + chunk_size > MAX_SLICE_SIZE {
-            let file_path = output_dir.join(format!("r.{}.{}.{}.json", rx, rz, slice_id));
-            write_chunks_direct(&file_path, &current_slice)?;
-            slice_id += 1;
-            current_slice.clear();
-            current_size = 0;
+        match self.config.slice_mode {
+            // 按区块编号固定分桶（空桶不生成文件）
+            SliceMode::Stable => {
+                let slice_id = index / self.config.chunks_per_slice.max(1);
+                if slice_id != self.slice_id {
+                    self.flush()?;
+                    self.slice_id = slice_id;
+                }
+            }
+            // 按大小贪心切片，当前切片放不下时开始新切片
+            SliceMode::Size => {
+                if !self.current.is_empty() && self.current_size + chunk_size > max_slice_size {
+                    self.flush()?;
+                    self.slice_id += 1;
+                }
+            }
         }
 
-        current_slice.push(chunk_str);
-        current_size += chunk_size;
+        self.current.push(chunk_str);
+        self.current_size += chunk_size;
+        Ok(())
     }
 
-    // 写入最后一个切片
-    if !current_slice.is_empty() {
-        let file_path = output_dir.join(format!("r.{}.{}.{}.json", rx, rz, slice_id));
-        write_chunks_direct(&file_path, &current_slice)?;
+    /// 写出当前切片
+    fn flush(&mut self) -> Result<()> {
+        if self.current.is_empty() {
+            return Ok(());
+        }
+
+        let format = self.config.format;
+        let ext = format.extension();
+        if self.current_size > self.max_slice_size {
+            warn!(
+                "r.{}.{}.{}{}.{} 大小 {} 字节超过 max_slice_size_mb，可减小 chunks_per_slice",
+                self.rx, self.rz, self.slice_id, self.suffix, ext, self.current_size
+            );
+        }
+
+        let file_path = self.output_dir.join(format!(
+            "r.{}.{}.{}{}.{}",
+            self.rx, self.rz, self.slice_id, self.suffix, ext
+        ));
+        let chunks: Vec<&str> = self.current.iter().map(String::as_str).collect();
+        self.files
+            .push(write_chunks_direct(&file_path, &chunks, format, self.sink)?);
+        self.current.clear();
+        self.current_size = 0;
+        Ok(())
     }
 
-    Ok(())
+    /// 将单个区块写入 `r.{rx}.{rz}/c.{cx}.{cz}.{ext}`（与只含一个区块的切片格式相同），
+    /// 图层文件为 `c.{cx}.{cz}.{layer}.{ext}`
+    fn write_chunk_file(&mut self, index: usize, chunk_str: &str) -> Result<()> {
+        let cx = self.rx * 32 + (index % 32) as i32;
+        let cz = self.rz * 32 + (index / 32) as i32;
+        let dir = self.output_dir.join(format!("r.{}.{}", self.rx, self.rz));
+        let format = self.config.format;
+        let file_path = dir.join(format!(
+            "c.{}.{}{}.{}",
+            cx,
+            cz,
+            self.suffix,
+            format.extension()
+        ));
+        self.files.push(write_chunks_direct(
+            &file_path,
+            &[chunk_str],
+            format,
+            self.sink,
+        )?);
+        Ok(())
+    }
+
+    /// 写出最后一个切片，返回写入的所有文件
+    fn finish(mut self) -> Result<Vec<SliceFile>> {
+        self.flush()?;
+        Ok(self.files)
+    }
+}
+
+/// JSON 切片文件的开头和结尾
+const SLICE_JSON_HEADER: &str = "{\"chunks\":[\n";
+const SLICE_JSON_FOOTER: &str = "]}\n";
+
+/// 直接写入已序列化的区块，逐个区块写入文件，不先拼接成完整的切片内容
+///
+/// JSON 格式写为 `{"chunks":[...]}`，SNBT 格式每行一个区块
+fn write_chunks_direct(
+    path: &Path,
+    chunks: &[&str],
+    format: SliceFormat,
+    sink: &OutputSink,
+) -> Result<SliceFile> {
+    let content_size: usize = chunks.iter().map(|s| s.len()).sum();
+    let bytes = match format {
+        SliceFormat::Json => {
+            SLICE_JSON_HEADER.len()
+                + content_size
+                + chunks.len().saturating_sub(1)
+                + chunks.len()
+                + SLICE_JSON_FOOTER.len()
+        }
+        SliceFormat::Snbt => content_size + chunks.len(),
+    };
+
+    sink.write_with(path, |out| {
+        match format {
+            SliceFormat::Json => {
+                out.write_all(SLICE_JSON_HEADER.as_bytes())?;
+                for (i, chunk) in chunks.iter().enumerate() {
+                    out.write_all(chunk.as_bytes())?;
+                    if i < chunks.len() - 1 {
+                        out.write_all(b",")?;
+                    }
+                    out.write_all(b"\n")?;
+                }
+                out.write_all(SLICE_JSON_FOOTER.as_bytes())?;
+            }
+            SliceFormat::Snbt => {
+                for chunk in chunks {
+                    out.write_all(chunk.as_bytes())?;
+                    out.write_all(b"\n")?;
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok(SliceFile {
+        path: path.to_path_buf(),
+        bytes,
+    })
+}
+
+/// 导出文件的写入目标
+enum OutputSink {
+    /// 写入磁盘（自动创建目录）
+    Disk,
+    /// 试运行：不创建目录和文件
+    DryRun,
+    /// 收集到内存，键为以 `/` 分隔的相对路径
+    Memory(Mutex<HashMap<String, String>>),
 }
 
-/// 直接写入已序列化的区块
-fn write_chunks_direct(path: &Path, chunks: &[&str]) -> Result<()> {
-    let total_size: usize = chunks.iter().map(|s| s.len()).sum();
-    let mut output = String::with_capacity(total_size + 100);
+impl OutputSink {
+    fn write(&self, path: &Path, content: String) -> Result<()> {
+        match self {
+            OutputSink::Disk => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                write_atomically(path, content)
+            }
+            OutputSink::DryRun => Ok(()),
+            OutputSink::Memory(files) => {
+                let key = path
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.lock().unwrap().insert(key, content);
+                Ok(())
+            }
+        }
+    }
 
-    output.push_str("{\"chunks\":[\n");
-    for (i, chunk) in chunks.iter().enumerate() {
-        output.push_str(chunk);
-        if i < chunks.len() - 1 {
-            output.push(',');
+    /// 通过 `write` 逐步写入文件内容，写入磁盘时不在内存中拼出完整内容
+    fn write_with(
+        &self,
+        path: &Path,
+        write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>,
+    ) -> Result<()> {
+        match self {
+            OutputSink::Disk => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                write_atomically_with(path, write)
+            }
+            OutputSink::DryRun => Ok(()),
+            OutputSink::Memory(_) => {
+                let mut content = Vec::new();
+                write(&mut content)?;
+                self.write(path, String::from_utf8(content)?)
+            }
         }
-        output.push('\n');
     }
-    output.push_str("]}\n");
+}
 
-    fs::write(path, output)?;
+/// 检查切片大小上限是否在允许范围内（每区块一个文件的布局不使用切片）
+fn check_slice_size(config: &ExportConfig) -> Result<()> {
+    if config.layout == ExportLayout::Sliced {
+        config.check_slice_size()?;
+    }
     Ok(())
 }
 
+/// 区块导出后应保留的内容（NBT，不含坐标和时间戳），用于校验往返结果
+///
+/// 与导出相同地筛选方块区块、去噪并移除空 section；JSON 格式还会移除空列表和空复合标签，
+/// SNBT 格式保留空值；无损去噪时被移除的值会在还原时插回，返回原区块。
+/// `denoiser` 为 `None` 时不去噪；
+/// 导出会跳过该区块（生成状态不符、没有实际数据或未通过过滤器）时返回 `None`
+///
+/// 返回值的第二项为该区块是否写入 noise 文件（无损去噪且有被移除的值，还原时不再恢复默认值）
+pub(crate) fn exported_chunk(
+    data: &Value,
+    kind: RegionKind,
+    denoiser: Option<&ChunkDenoiser>,
+    aggressive: bool,
+    config: &Config,
+) -> Option<(Value, bool)> {
+    let terrain = kind == RegionKind::Chunks;
+    let mut chunk = data.clone();
+    if let Some(denoiser) = denoiser {
+        denoise_region_chunk(denoiser, kind, &mut chunk, aggressive, None);
+    }
+    filter_empty_sections(&mut chunk, &config.export.empty_blocks);
+    if terrain {
+        let mut json = nbt_to_json(&chunk);
+        filter_empty_values(&mut json);
+        let filter = AndFilter::from_config(&config.export);
+        if ChunkChecks::new(&config.export, &filter)
+            .rejects(&json)
+            .is_some()
+        {
+            return None;
+        }
+    }
+    if config.export.format == SliceFormat::Json {
+        filter_empty_nbt_values(&mut chunk);
+    }
+    if config.denoise.lossless {
+        let noise = chunk != *data || denoiser.is_some_and(ChunkDenoiser::strip_timestamp);
+        return Some((data.clone(), noise));
+    }
+    Some((chunk, false))
+}
+
+/// 按区域文件的种类去噪：方块区块完整去噪，实体区块只对实体去噪，兴趣点区块不去噪
+fn denoise_region_chunk(
+    denoiser: &ChunkDenoiser,
+    kind: RegionKind,
+    data: &mut Value,
+    aggressive: bool,
+    stats: Option<&mut DenoiseStats>,
+) {
+    match kind {
+        RegionKind::Chunks => denoiser.denoise(data, aggressive, stats),
+        RegionKind::Entities => denoiser.denoise_entity_chunk(data, aggressive, stats),
+        RegionKind::Poi => {}
+    }
+}
+
 /// 检查区块是否完整生成
 fn is_full_chunk(data: &Value) -> bool {
-    if let Value::Compound(map) = data {
-        if let Some(Value::String(status)) = map.get("Status") {
-            // 只导出完整的区块
-            return status == "minecraft:full" || status == "full";
-        }
+    has_included_status(data, DEFAULT_INCLUDED_STATUSES)
+}
+
+/// 检查区块的生成状态是否在 `statuses` 中（规则见 [`status_included`]）
+///
+/// 1.18 之前的区块读取 `Level.Status`
+fn has_included_status<S: AsRef<str>>(data: &Value, statuses: &[S]) -> bool {
+    let Value::Compound(root) = data else {
+        return status_included(statuses, Some(None), || false);
+    };
+    let body = chunk_body(root).0;
+    match body.get("Status") {
+        Some(Value::String(status)) => status_included(statuses, Some(Some(status)), || false),
+        Some(_) => status_included(statuses, Some(None), || false),
+        None => status_included(statuses, None, || {
+            matches!(body.get("TerrainPopulated"), Some(Value::Byte(1)))
+        }),
     }
-    false
 }
 
-/// 过滤空 sections（只有空气的 section）
-fn filter_empty_sections(chunk: &mut JsonValue) {
-    if let JsonValue::Object(ref mut obj) = chunk {
-        if let Some(JsonValue::Array(sections)) = obj.get_mut("sections") {
-            sections.retain(|sec| !is_empty_section(sec));
+/// 检查生成状态是否在 `statuses` 中，`"*"` 匹配所有区块（包括没有 `Status` 的区块）
+///
+/// `status` 为区块的 `Status` 字段（`Some(None)` 表示不是字符串）；1.13 之前（包括 McRegion）的区块
+/// 没有 `Status`，地形装饰完成（`terrain_populated`）的区块视为 `minecraft:full`
+pub(crate) fn status_included<S: AsRef<str>>(
+    statuses: &[S],
+    status: Option<Option<&str>>,
+    terrain_populated: impl FnOnce() -> bool,
+) -> bool {
+    if statuses.iter().any(|s| s.as_ref() == "*") {
+        return true;
+    }
+    match status {
+        Some(Some(status)) => statuses.iter().any(|s| s.as_ref() == status),
+        Some(None) => false,
+        None => terrain_populated() && statuses.iter().any(|s| s.as_ref() == "minecraft:full"),
+    }
+}
+
+/// 过滤空 sections（只由 `empty_blocks` 中的一种方块填满的 section）
+///
+/// 在区块 NBT 上进行，须在展开方块状态之前调用
+fn filter_empty_sections<S: AsRef<str>>(chunk: &mut Value, empty_blocks: &[S]) -> usize {
+    if let Value::Compound(map) = chunk {
+        if let Some(Value::List(sections)) = map.get_mut("sections") {
+            let before = sections.len();
+            sections.retain(|sec| !is_empty_section(sec, empty_blocks));
+            return before - sections.len();
         }
     }
+    0
 }
 
-/// 检查 section 是否为空（只有空气，不管 biome）
-fn is_empty_section(sec: &JsonValue) -> bool {
-    if let JsonValue::Object(obj) = sec {
-        // 只检查 block_states 是否为空气
-        if let Some(block_states) = obj.get("block_states") {
-            // 如果有 data 字段，说明不是简单的单一方块
-            if block_states.get("data").is_some() {
-                return false;
-            }
-            if let Some(palette) = block_states.get("palette") {
-                if let JsonValue::Array(arr) = palette {
-                    // palette 只有一个元素且是空气
-                    if arr.len() == 1 {
-                        if let Some(first) = arr.first() {
-                            let name = first.get("Name").and_then(|n| n.as_str()).unwrap_or("");
-                            return name == "air" || name == "minecraft:air";
-                        }
-                    }
-                }
-            }
+/// 取 compound 中的字段
+fn nbt_field<'v>(value: &'v Value, key: &str) -> Option<&'v Value> {
+    match value {
+        Value::Compound(map) => map.get(key),
+        _ => None,
+    }
+}
+
+/// 检查 section 是否为空（只有空气类方块，且生物群系都是默认的平原）
+///
+/// 单一方块的 section 只有一个 palette 条目且没有 `data`；
+/// 现代区块中方块名带 `minecraft:` 前缀，比较时忽略该前缀。
+/// 游戏会把缺失的 section 生成为平原，含有其它生物群系的 section 必须保留
+fn is_empty_section<S: AsRef<str>>(sec: &Value, empty_blocks: &[S]) -> bool {
+    let Some(block_states) = nbt_field(sec, "block_states") else {
+        return false;
+    };
+    if !has_default_biomes(sec) {
+        return false;
+    }
+    // 如果有 data 字段，说明不是简单的单一方块
+    if nbt_field(block_states, "data").is_some() {
+        return false;
+    }
+    match nbt_field(block_states, "palette") {
+        // palette 只有一个元素且是空气类方块
+        Some(Value::List(palette)) if palette.len() == 1 => {
+            let name = match nbt_field(&palette[0], "Name") {
+                Some(Value::String(name)) => name.as_str(),
+                _ => "",
+            };
+            empty_blocks
+                .iter()
+                .any(|block| same_block_id(block.as_ref(), name))
         }
-        false
-    } else {
-        false
+        _ => false,
     }
 }
 
+/// 缺失的 section 在游戏中使用的生物群系
+const DEFAULT_SECTION_BIOME: &str = "minecraft:plains";
+
+/// 检查 section 的生物群系调色板是否只含默认生物群系（没有 `biomes` 时视为默认）
+///
+/// 调色板条目通常是生物群系名字符串，也兼容 `{Name: ...}` 形式
+fn has_default_biomes(sec: &Value) -> bool {
+    match nbt_field(sec, "biomes").and_then(|b| nbt_field(b, "palette")) {
+        None => true,
+        Some(Value::List(palette)) => palette.iter().all(|entry| {
+            let name = match entry {
+                Value::String(name) => name.as_str(),
+                _ => match nbt_field(entry, "Name") {
+                    Some(Value::String(name)) => name.as_str(),
+                    _ => "",
+                },
+            };
+            same_block_id(DEFAULT_SECTION_BIOME, name)
+        }),
+        Some(_) => false,
+    }
+}
+
+/// 比较方块 ID，省略命名空间时视为 `minecraft:`
+pub(crate) fn same_block_id(a: &str, b: &str) -> bool {
+    let a = a.strip_prefix("minecraft:").unwrap_or(a);
+    let b = b.strip_prefix("minecraft:").unwrap_or(b);
+    !a.is_empty() && a == b
+}
+
 /// 过滤空值（空对象、空列表、空列表标记）
 fn filter_empty_values(value: &mut JsonValue) {
     match value {
@@ -557,6 +2332,76 @@ fn filter_empty_values(value: &mut JsonValue) {
     }
 }
 
+/// 在 NBT 上进行与 [`filter_empty_values`] 相同的过滤：移除复合标签中的空列表和空复合标签
+fn filter_empty_nbt_values(value: &mut Value) {
+    match value {
+        Value::Compound(map) => {
+            for v in map.values_mut() {
+                filter_empty_nbt_values(v);
+            }
+            map.retain(|_, v| !is_empty_nbt_value(v));
+        }
+        Value::List(list) => {
+            for v in list.iter_mut() {
+                filter_empty_nbt_values(v);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 空列表或空复合标签
+fn is_empty_nbt_value(v: &Value) -> bool {
+    match v {
+        Value::Compound(map) => map.is_empty(),
+        Value::List(list) => list.is_empty(),
+        _ => false,
+    }
+}
+
+/// 去噪和过滤前后的区块 JSON 中被移除的值（无损去噪时写入 noise 文件）
+///
+/// 对象只保留被移除的字段和含有被移除内容的子对象；元素都带有 `Y` 的数组（sections）
+/// 只保留有变化的元素（附带 `Y`，整个被移除的元素原样保留）；其余有变化的值（包括实体等其它数组）
+/// 记录整个原值，还原时直接替换
+fn removed_values(before: &JsonValue, after: &JsonValue) -> Option<JsonValue> {
+    match (before, after) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            let mut removed = JsonMap::new();
+            for (key, a_value) in a {
+                let value = match b.get(key) {
+                    Some(b_value) => removed_values(a_value, b_value),
+                    None => Some(a_value.clone()),
+                };
+                if let Some(value) = value {
+                    removed.insert(key.clone(), value);
+                }
+            }
+            (!removed.is_empty()).then_some(JsonValue::Object(removed))
+        }
+        (JsonValue::Array(a), JsonValue::Array(b))
+            if a.iter().chain(b).all(|v| v.get("Y").is_some()) =>
+        {
+            let mut removed = Vec::new();
+            for a_value in a {
+                let value = match b
+                    .iter()
+                    .find(|b_value| b_value.get("Y") == a_value.get("Y"))
+                {
+                    Some(b_value) => removed_values(a_value, b_value).map(|mut value| {
+                        value["Y"] = a_value["Y"].clone();
+                        value
+                    }),
+                    None => Some(a_value.clone()),
+                };
+                removed.extend(value);
+            }
+            (!removed.is_empty()).then_some(JsonValue::Array(removed))
+        }
+        _ => (before != after).then(|| before.clone()),
+    }
+}
+
 /// 检查 JSON 值是否为空
 fn is_empty_json_value(v: &JsonValue) -> bool {
     match v {
@@ -572,21 +2417,356 @@ fn is_empty_json_value(v: &JsonValue) -> bool {
     }
 }
 
-/// 检查区块是否有实际数据（sections 或 block_entities）
-fn has_chunk_data(chunk: &JsonValue) -> bool {
-    if let JsonValue::Object(obj) = chunk {
-        // 检查 sections 是否有内容
-        if let Some(JsonValue::Array(sections)) = obj.get("sections") {
-            if !sections.is_empty() {
-                return true;
-            }
+/// 不算作实际数据的区块基本字段（[`EmptyChunkPolicy::RequireAnyField`] 使用）
+const CHUNK_BASIC_FIELDS: &[&str] = &[
+    "x",
+    "z",
+    "_ts",
+    "xPos",
+    "yPos",
+    "zPos",
+    "Status",
+    "DataVersion",
+    "LastUpdate",
+    "InhabitedTime",
+    "isLightOn",
+];
+
+/// 按 `policy` 检查区块是否有实际数据（在过滤空 section 和空值之后调用）
+///
+/// 1.18 之前的区块检查 `Level` 中使用旧名称的字段（如 `Sections`、`TileEntities`）
+pub(crate) fn has_chunk_data(chunk: &JsonValue, policy: EmptyChunkPolicy) -> bool {
+    let JsonValue::Object(root) = chunk else {
+        return false;
+    };
+    let (obj, legacy) = match root.get(LEGACY_LEVEL_KEY) {
+        Some(JsonValue::Object(level)) => (level, true),
+        _ => (root, false),
+    };
+    let non_empty = |key: &str| {
+        let key = if legacy { legacy_field_name(key) } else { key };
+        matches!(obj.get(key), Some(JsonValue::Array(a)) if !a.is_empty())
+    };
+    let has_data_field = |map: &JsonMap<String, JsonValue>| {
+        map.keys()
+            .any(|key| key != LEGACY_LEVEL_KEY && !CHUNK_BASIC_FIELDS.contains(&key.as_str()))
+    };
+    // McRegion 区块没有 section，方块直接存放在 `Level.Blocks` 中
+    let has_sections = || non_empty("sections") || (legacy && obj.contains_key("Blocks"));
+    match policy {
+        EmptyChunkPolicy::RequireSections => has_sections(),
+        EmptyChunkPolicy::RequireSectionsOrBlockEntities => {
+            has_sections() || non_empty("block_entities")
         }
-        // 检查 block_entities 是否有内容
-        if let Some(JsonValue::Array(entities)) = obj.get("block_entities") {
-            if !entities.is_empty() {
-                return true;
+        EmptyChunkPolicy::RequireAnyField => {
+            has_data_field(obj) || (legacy && has_data_field(root))
+        }
+        EmptyChunkPolicy::Never => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ChunkBounds, ChunkDenoiseConfig, DenoiseMode};
+    use crate::filter::AllFilter;
+    use crate::test_util::{compound, sample_chunk, sample_chunks, uniform_section, write_world};
+    use fastnbt::LongArray;
+
+    /// 按 [`chunk_order`] 将 `chunks` 写入内存中的切片，返回路径到内容的映射
+    fn write_slices(config: &ExportConfig, chunks: &[String]) -> HashMap<String, String> {
+        let sink = OutputSink::Memory(Mutex::default());
+        let mut writer = SliceWriter::new(Path::new(""), 0, 0, config, &sink);
+        for index in chunk_order(config) {
+            writer.push(index, chunks[index].clone()).unwrap();
+        }
+        writer.finish().unwrap();
+        match sink {
+            OutputSink::Memory(files) => files.into_inner().unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn stable_slices_change_one_file_per_edited_chunk() {
+        let config = ExportConfig {
+            slice_mode: SliceMode::Stable,
+            chunks_per_slice: 128,
+            ..ExportConfig::default()
+        };
+        let mut chunks: Vec<String> = (0..CHUNKS_PER_REGION)
+            .map(|i| format!("{{\"i\":{}}}", i))
+            .collect();
+        let before = write_slices(&config, &chunks);
+        assert_eq!(before.len(), CHUNKS_PER_REGION / 128);
+
+        chunks[300] = format!("{{\"i\":300,\"pad\":\"{}\"}}", "x".repeat(4096));
+        let after = write_slices(&config, &chunks);
+        assert_eq!(before.len(), after.len());
+        let changed: Vec<&String> = after
+            .iter()
+            .filter(|(path, content)| before.get(*path) != Some(content))
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(changed, vec!["r.0.0.2.json"]);
+    }
+
+    #[test]
+    fn slice_content_depends_only_on_coordinates() {
+        let coords = [(3, 1), (0, 0), (1, 0), (0, 2), (31, 3)];
+        let mut reversed = coords;
+        reversed.reverse();
+        let (a, b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        write_world(a.path(), &sample_chunks(&coords));
+        // 同一世界的两次导出完全相同（清单中的导出时间除外）
+        let config = Config::default();
+        let export = || {
+            let mut files = export_world_to_memory(a.path(), true, false, &config).unwrap();
+            files.remove(EXPORT_MANIFEST_FILE).unwrap();
+            files
+        };
+        assert_eq!(export(), export());
+
+        // 区块在 region 文件中的存储顺序不影响切片内容（时间戳不同，去掉 _ts 比较）
+        let config = Config {
+            denoise: DenoiseConfig {
+                chunk: ChunkDenoiseConfig {
+                    strip_timestamp: true,
+                    ..ChunkDenoiseConfig::default()
+                },
+                ..DenoiseConfig::default()
+            },
+            ..Config::default()
+        };
+        write_world(b.path(), &sample_chunks(&reversed));
+        let slice = |world: &Path| {
+            export_world_to_memory(world, true, false, &config).unwrap()["region/r.0.0.0.json"]
+                .clone()
+        };
+        let content = slice(a.path());
+        assert_eq!(content, slice(b.path()));
+
+        let parsed: JsonValue = serde_json::from_str(&content).unwrap();
+        let order: Vec<(i64, i64)> = parsed["chunks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| (c["x"].as_i64().unwrap(), c["z"].as_i64().unwrap()))
+            .collect();
+        assert_eq!(order, vec![(0, 0), (0, 2), (1, 0), (3, 1), (31, 3)]);
+    }
+
+    #[test]
+    fn empty_sections_of_a_modern_chunk_are_removed() {
+        let mut chunk = sample_chunk(0, 0);
+        let removed = filter_empty_sections(&mut chunk, DEFAULT_EMPTY_BLOCKS);
+        assert_eq!(removed, 23);
+        let Some(Value::List(sections)) = nbt_field(&chunk, "sections") else {
+            panic!("缺少 sections");
+        };
+        assert_eq!(sections.len(), 1);
+        assert_eq!(nbt_field(&sections[0], "Y"), Some(&Value::Byte(-4)));
+    }
+
+    #[test]
+    fn empty_section_requires_single_air_palette_without_data() {
+        // 单一空气、没有 data 的 section 为空，不论是否带命名空间
+        assert!(is_empty_section(
+            &uniform_section(0, "minecraft:air"),
+            DEFAULT_EMPTY_BLOCKS
+        ));
+        assert!(is_empty_section(
+            &uniform_section(0, "air"),
+            DEFAULT_EMPTY_BLOCKS
+        ));
+        assert!(!is_empty_section(
+            &uniform_section(0, "minecraft:stone"),
+            DEFAULT_EMPTY_BLOCKS
+        ));
+
+        // 带有 data 的 section 不是单一方块
+        let mut section = uniform_section(0, "minecraft:air");
+        if let Value::Compound(sec) = &mut section {
+            if let Some(Value::Compound(states)) = sec.get_mut("block_states") {
+                states.insert(
+                    "data".to_string(),
+                    Value::LongArray(LongArray::new(vec![0])),
+                );
             }
         }
+        assert!(!is_empty_section(&section, DEFAULT_EMPTY_BLOCKS));
+
+        // 非平原生物群系的 section 必须保留
+        let mut section = uniform_section(0, "minecraft:air");
+        if let Value::Compound(sec) = &mut section {
+            sec.insert(
+                "biomes".to_string(),
+                compound([(
+                    "palette",
+                    Value::List(vec![Value::String("minecraft:desert".to_string())]),
+                )]),
+            );
+        }
+        assert!(!is_empty_section(&section, DEFAULT_EMPTY_BLOCKS));
+    }
+
+    #[test]
+    fn skipped_chunks_are_counted_by_reason() {
+        let with = |x: i32, key: &str, value: Value| {
+            let mut data = sample_chunk(x, 0);
+            if let Value::Compound(map) = &mut data {
+                map.insert(key.to_string(), value);
+            }
+            ChunkData {
+                x,
+                z: 0,
+                timestamp: 0,
+                data,
+            }
+        };
+        let sections = |block: &str| Value::List(vec![uniform_section(0, block)]);
+        let legacy = ChunkData {
+            x: 4,
+            z: 0,
+            timestamp: 0,
+            data: compound([(
+                LEGACY_LEVEL_KEY,
+                compound([
+                    ("xPos", Value::Int(4)),
+                    ("zPos", Value::Int(0)),
+                    ("TerrainPopulated", Value::Byte(1)),
+                    ("LastUpdate", Value::Long(1)),
+                    (
+                        "Sections",
+                        Value::List(vec![compound([
+                            ("Y", Value::Byte(0)),
+                            (
+                                "Blocks",
+                                Value::ByteArray(fastnbt::ByteArray::new(vec![1; 4096])),
+                            ),
+                        ])]),
+                    ),
+                ]),
+            )]),
+        };
+        let chunks = [
+            with(0, "Status", Value::String("minecraft:full".to_string())),
+            with(1, "Status", Value::String("minecraft:features".to_string())),
+            with(2, "sections", sections("minecraft:air")),
+            with(3, "sections", sections("minecraft:dirt")),
+            legacy,
+        ];
+        let world = tempfile::tempdir().unwrap();
+        write_world(world.path(), &chunks);
+        let mca = world.path().join("region/r.0.0.mca");
+
+        let export = |denoise_config: &DenoiseConfig, export_config: &ExportConfig| {
+            let export_config = ExportConfig {
+                dry_run: true,
+                ..export_config.clone()
+            };
+            export_mca_with_config(
+                &mca,
+                world.path(),
+                None,
+                true,
+                false,
+                denoise_config,
+                &export_config,
+                &FieldMapper::identity(),
+                &AndFilter::from_config(&export_config),
+            )
+            .unwrap()
+        };
+
+        // 1.13 之前的区块在白名单模式下仍按 TerrainPopulated 判断为完整生成
+        let whitelist = DenoiseConfig {
+            chunk: ChunkDenoiseConfig {
+                mode: DenoiseMode::Whitelist,
+                ..ChunkDenoiseConfig::default()
+            },
+            ..DenoiseConfig::default()
+        };
+        let report = export(&whitelist, &ExportConfig::default());
+        assert_eq!(report.chunks_exported, 3);
+        assert_eq!(report.chunks_skipped_not_full, 1);
+        assert_eq!(report.chunks_skipped_empty, 1);
+        assert_eq!(report.chunks_skipped_filtered, 0);
+
+        let export_config = ExportConfig {
+            include_blocks: vec!["stone".to_string()],
+            ..ExportConfig::default()
+        };
+        let report = export(&DenoiseConfig::default(), &export_config);
+        assert_eq!(report.chunks_exported, 1);
+        assert_eq!(report.chunks_skipped_not_full, 1);
+        assert_eq!(report.chunks_skipped_empty, 1);
+        assert_eq!(report.chunks_skipped_filtered, 2);
+
+        // 范围外的区块同样计为被过滤
+        let export_config = ExportConfig {
+            bounds: Some(ChunkBounds::new(0, 0, 0, 0)),
+            ..ExportConfig::default()
+        };
+        let checks = ChunkChecks::new(&export_config, &AllFilter);
+        let json = nbt_to_json(&chunks[3].data);
+        assert!(matches!(
+            checks.rejects(&json),
+            Some(ChunkOutcome::Filtered)
+        ));
+        assert!(checks.rejects(&nbt_to_json(&chunks[0].data)).is_none());
+    }
+
+    #[test]
+    fn data_version_mismatch_aborts_without_fail_fast() {
+        let world = tempfile::tempdir().unwrap();
+        write_world(world.path(), &sample_chunks(&[(0, 0), (1, 0)]));
+        let mut config = Config::default();
+        config.export.fail_fast = false;
+        config.export.require_data_version = Some(3700);
+        let output = tempfile::tempdir().unwrap();
+        let err = export_world_with_config(world.path(), output.path(), false, false, &config)
+            .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("require_data_version = 3700"),
+            "{:#}",
+            err
+        );
+        let report = &err.downcast_ref::<ExportError>().unwrap().report;
+        assert_eq!(report.regions_failed, 1);
+
+        config.export.require_data_version = Some(3465);
+        export_world_with_config(world.path(), output.path(), false, false, &config).unwrap();
+    }
+
+    #[test]
+    fn overwrite_removes_outputs_the_next_export_does_not_produce() {
+        let world = tempfile::tempdir().unwrap();
+        write_world(world.path(), &sample_chunks(&[(0, 0)]));
+        let player = compound([("Health", Value::Float(20.0))]);
+        let player = crate::mca::gzip_compress(&fastnbt::to_bytes(&player).unwrap(), 6).unwrap();
+        fs::create_dir_all(world.path().join("playerdata")).unwrap();
+        fs::write(world.path().join("playerdata/player.dat"), player).unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let out = output.path();
+        let mut config = Config::default();
+        config.export.format = SliceFormat::Snbt;
+        export_world_with_config(world.path(), out, true, false, &config).unwrap();
+        assert!(out.join("playerdata").is_dir());
+        // 测试世界没有 level.dat 和下界，手动放入旧导出中的文件
+        fs::write(out.join("level.snbt"), "{}").unwrap();
+        fs::create_dir_all(out.join("DIM-1/region")).unwrap();
+        fs::create_dir_all(out.join(".git")).unwrap();
+
+        clean_export_output(out).unwrap();
+        config.export.format = SliceFormat::Json;
+        config.export.include_playerdata = false;
+        export_world_with_config(world.path(), out, true, false, &config).unwrap();
+        assert!(!out.join("playerdata").exists());
+        assert!(!out.join("level.snbt").exists());
+        assert!(!out.join("DIM-1/region").exists());
+        assert!(out.join("region/r.0.0.0.json").exists());
+        assert!(out.join(".git").is_dir());
     }
-    false
 }
@@ -0,0 +1,191 @@
+//! 区块过滤器 - 决定导出时保留哪些区块
+
+use crate::config::{
+    ChunkBounds, ChunkFilterName, EmptyChunkPolicy, ExportConfig, DEFAULT_INCLUDED_STATUSES,
+};
+use crate::denoise::LEGACY_LEVEL_KEY;
+use crate::export::{has_chunk_data, same_block_id, status_included};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+/// 区块过滤器，返回 false 的区块不导出
+///
+/// 传入的是去噪、过滤空 section 和空值之后、缩短字段名之前的区块 JSON
+/// （包含 region 内坐标 `x`、`z`）
+pub trait ChunkFilter: Send + Sync {
+    fn should_export(&self, chunk: &JsonValue) -> bool;
+}
+
+/// 只导出完整生成（`Status` 为 `minecraft:full`）的区块
+///
+/// 1.13 之前的区块没有 `Status`，`TerrainPopulated` 为 1 时视为完整生成
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FullStatusFilter;
+
+impl ChunkFilter for FullStatusFilter {
+    fn should_export(&self, chunk: &JsonValue) -> bool {
+        has_included_status(chunk, DEFAULT_INCLUDED_STATUSES)
+    }
+}
+
+/// 只导出生成状态在 `statuses` 中的区块（`"*"` 匹配所有区块），其余规则与 [`FullStatusFilter`] 相同
+#[derive(Debug, Clone, Default)]
+pub struct StatusFilter {
+    pub statuses: Vec<String>,
+}
+
+impl ChunkFilter for StatusFilter {
+    fn should_export(&self, chunk: &JsonValue) -> bool {
+        has_included_status(chunk, &self.statuses)
+    }
+}
+
+/// 只导出按 `policy` 判断有实际数据的区块
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HasDataFilter(pub EmptyChunkPolicy);
+
+impl ChunkFilter for HasDataFilter {
+    fn should_export(&self, chunk: &JsonValue) -> bool {
+        has_chunk_data(chunk, self.0)
+    }
+}
+
+/// 只导出范围内的区块（按区块数据中的 `xPos`、`zPos` 判断，缺少时不导出）
+///
+/// 区块 JSON 中的 `x`、`z` 是 region 内坐标，不能单独确定区块位置
+#[derive(Debug, Clone, Copy)]
+pub struct BoundsFilter(pub ChunkBounds);
+
+impl ChunkFilter for BoundsFilter {
+    fn should_export(&self, chunk: &JsonValue) -> bool {
+        let Some(body) = chunk_body(chunk) else {
+            return false;
+        };
+        let pos = |key: &str| body.get(key)?.as_i64()?.try_into().ok();
+        match (pos("xPos"), pos("zPos")) {
+            (Some(x), Some(z)) => self.0.contains(x, z),
+            _ => false,
+        }
+    }
+}
+
+/// 只导出含有任一指定方块的区块（方块 ID 省略命名空间时为 `minecraft:`）
+///
+/// 检查各 section 的方块调色板，没有调色板的 1.13 之前的区块不会被导出
+#[derive(Debug, Clone, Default)]
+pub struct BlockFilter {
+    pub block_ids: Vec<String>,
+}
+
+impl ChunkFilter for BlockFilter {
+    fn should_export(&self, chunk: &JsonValue) -> bool {
+        let Some(body) = chunk_body(chunk) else {
+            return false;
+        };
+        let Some(JsonValue::Array(sections)) =
+            body.get("sections").or_else(|| body.get("Sections"))
+        else {
+            return false;
+        };
+        sections.iter().any(|section| {
+            let palette = section
+                .get("block_states")
+                .and_then(|states| states.get("palette"))
+                .or_else(|| section.get("Palette"));
+            let Some(JsonValue::Array(palette)) = palette else {
+                return false;
+            };
+            palette
+                .iter()
+                .filter_map(|entry| entry.get("Name")?.as_str())
+                .any(|name| self.block_ids.iter().any(|id| same_block_id(id, name)))
+        })
+    }
+}
+
+/// 导出所有区块
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllFilter;
+
+impl ChunkFilter for AllFilter {
+    fn should_export(&self, _chunk: &JsonValue) -> bool {
+        true
+    }
+}
+
+/// 所有过滤器都通过时才导出，列表为空时导出所有区块
+#[derive(Default)]
+pub struct AndFilter<'a>(pub Vec<Box<dyn ChunkFilter + 'a>>);
+
+impl AndFilter<'static> {
+    /// 按 `filters` 中的名称创建过滤器，`include_blocks` 不为空时再加上 [`BlockFilter`]
+    ///
+    /// `has_data` 使用 `empty_chunk_policy`，`bounds` 使用 `bounds`（未设置时不过滤）
+    pub fn from_config(config: &ExportConfig) -> Self {
+        let block_filter = (!config.include_blocks.is_empty()).then(|| -> Box<dyn ChunkFilter> {
+            Box::new(BlockFilter {
+                block_ids: config.include_blocks.clone(),
+            })
+        });
+        Self(
+            config
+                .filters
+                .iter()
+                .map(|name| -> Box<dyn ChunkFilter> {
+                    match name {
+                        ChunkFilterName::FullStatus => Box::new(FullStatusFilter),
+                        ChunkFilterName::HasData => {
+                            Box::new(HasDataFilter(config.empty_chunk_policy))
+                        }
+                        ChunkFilterName::Bounds => match config.bounds {
+                            Some(bounds) => Box::new(BoundsFilter(bounds)),
+                            None => Box::new(AllFilter),
+                        },
+                        ChunkFilterName::All => Box::new(AllFilter),
+                    }
+                })
+                .chain(block_filter)
+                .collect(),
+        )
+    }
+}
+
+impl ChunkFilter for AndFilter<'_> {
+    fn should_export(&self, chunk: &JsonValue) -> bool {
+        self.0.iter().all(|filter| filter.should_export(chunk))
+    }
+}
+
+impl<F: ChunkFilter + ?Sized> ChunkFilter for &F {
+    fn should_export(&self, chunk: &JsonValue) -> bool {
+        (**self).should_export(chunk)
+    }
+}
+
+/// 区块的生成状态是否在 `statuses` 中（规则见 [`status_included`]）
+fn has_included_status<S: AsRef<str>>(chunk: &JsonValue, statuses: &[S]) -> bool {
+    let body = chunk_body(chunk);
+    let status = body.and_then(|body| body.get("Status"));
+    let terrain_populated = || {
+        matches!(
+            body.and_then(|body| body.get("TerrainPopulated")),
+            Some(JsonValue::String(flag)) if flag == "1b"
+        )
+    };
+    match (body, status) {
+        (Some(_), None) => status_included(statuses, None, terrain_populated),
+        (_, status) => {
+            status_included(statuses, Some(status.and_then(JsonValue::as_str)), || false)
+        }
+    }
+}
+
+/// 区块数据所在的对象（1.18 之前的区块为 `Level`）
+fn chunk_body(chunk: &JsonValue) -> Option<&JsonMap<String, JsonValue>> {
+    let JsonValue::Object(root) = chunk else {
+        return None;
+    };
+    match root.get(LEGACY_LEVEL_KEY) {
+        Some(JsonValue::Object(level)) => Some(level),
+        _ => Some(root),
+    }
+}
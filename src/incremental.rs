@@ -0,0 +1,105 @@
+//! 增量导出清单 - 记录源 MCA 文件状态，跳过未变化的 region
+
+use crate::config::Config;
+use crate::util::write_atomically;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// 清单文件名（位于导出目录根部）
+pub const MANIFEST_FILE: &str = ".mcj-manifest.json";
+
+/// 清单格式版本
+const MANIFEST_VERSION: u32 = 1;
+
+/// 增量导出清单
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IncrementalManifest {
+    /// 清单格式版本
+    pub version: u32,
+    /// 导出设置的指纹，设置变化时所有 region 都需要重新导出
+    pub settings: String,
+    /// 各源 MCA 文件的状态，键为相对世界目录的路径（如 `DIM-1/region/r.0.0.mca`）
+    pub regions: BTreeMap<String, SourceState>,
+}
+
+/// 源 MCA 文件状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceState {
+    /// 修改时间（Unix 纳秒，秒级精度会漏掉同一秒内的修改）
+    pub mtime: u64,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 文件内容哈希（BLAKE3）
+    pub hash: String,
+}
+
+impl IncrementalManifest {
+    /// 读取导出目录中的清单，不存在或无法解析时返回空清单
+    pub fn load(output_path: &Path) -> Self {
+        fs::read_to_string(output_path.join(MANIFEST_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|manifest| manifest.version == MANIFEST_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// 写入清单到导出目录
+    pub fn save(&self, output_path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        write_atomically(&output_path.join(MANIFEST_FILE), content + "\n")
+    }
+
+    /// 创建使用指定设置指纹的空清单
+    pub fn new(settings: String) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            settings,
+            regions: BTreeMap::new(),
+        }
+    }
+}
+
+/// 计算导出设置的指纹
+///
+/// 包含完整配置、去噪开关和工具版本，任一变化都会使旧的导出结果失效
+pub fn settings_fingerprint(config: &Config, denoise: bool, aggressive: bool) -> Result<String> {
+    // 先转换为 serde_json::Value，使 HashMap 字段按键排序
+    let config_json = serde_json::to_value(config)?.to_string();
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+    hasher.update(&[denoise as u8, aggressive as u8]);
+    hasher.update(config_json.as_bytes());
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// 读取源文件状态
+///
+/// 修改时间和大小都与 `previous` 相同时直接沿用其哈希，否则重新计算
+pub fn source_state(path: &Path, previous: Option<&SourceState>) -> Result<SourceState> {
+    let metadata = fs::metadata(path)?;
+    let mtime = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let size = metadata.len();
+
+    if let Some(prev) = previous {
+        if prev.mtime == mtime && prev.size == size {
+            return Ok(prev.clone());
+        }
+    }
+
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(SourceState {
+        mtime,
+        size,
+        hash: hasher.finalize().to_hex().to_string(),
+    })
+}
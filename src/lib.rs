@@ -1,24 +1,76 @@
 //! Minecraft 世界 JSON 序列化工具
 //!
 //! 将 Minecraft 世界文件转换为 Git 友好的 JSON 格式
+//!
+//! 所有输出都通过 [`log`] 门面发出（region 级进度为 `info`，区块级细节为 `debug`），
+//! 库不会直接打印任何内容；调用方需要自行初始化 logger（如 `env_logger`）才能看到日志
 
+pub mod analysis;
+pub mod block_states;
+pub mod clone;
+pub mod compare;
 pub mod config;
 pub mod denoise;
+pub mod diff;
 pub mod export;
+pub mod filter;
+pub mod incremental;
+pub mod manifest;
 pub mod mca;
 pub mod nbt_json;
+pub mod progress;
 pub mod restore;
+pub mod stats;
+#[cfg(test)]
+mod test_util;
+pub mod util;
+pub mod verify;
+pub mod watch;
+pub mod world;
 
-pub use config::{Area, Config, FieldMappingConfig, WorkspaceConfig};
+pub use analysis::{detect_world_bounds, WorldBounds};
+pub use clone::{clone_world_raw, CloneReport};
+pub use compare::{compare_chunks, compare_nbt, compare_regions, ChunkDifference, Difference};
+pub use config::{
+    Area, ByteArrayEncoding, ChunkBounds, ChunkFilterName, CompressionCodec, CompressionConfig,
+    Config, ConfigBuilder, ConfigCheck, ConfigFormat, DenoiseMode, DuplicateChunkPolicy,
+    EmptyChunkPolicy, EntityDenoiseConfig, ExistingOutputPolicy, ExportLayout, FieldMappingConfig,
+    PerformanceConfig, SliceFormat, SliceMode, WorkspaceConfig,
+};
 pub use denoise::{
-    denoise_chunk, denoise_chunk_with_config, denoise_level, denoise_level_with_config,
-    restore_defaults,
+    default_restore_values, denoise_chunk, denoise_chunk_with_config, denoise_chunk_with_stats,
+    denoise_level, denoise_level_with_config, preview_denoise_chunk, preview_denoise_level,
+    restore_defaults, restore_defaults_with_values, whitelist_restore_values, ChunkDenoiser,
+    DenoiseDiff, DenoiseStats, FieldPattern,
 };
+pub use diff::{diff_exports, ChunkPos, ExportDiff, ModifiedChunk};
 pub use export::{
-    export_level_dat, export_mca, export_world, export_world_with_area, export_world_with_config,
+    clean_export_output, export_level_dat, export_mca, export_world, export_world_incremental,
+    export_world_to_memory, export_world_with_area, export_world_with_config,
+    preview_world_denoise, DataVersionMismatch, ExportError, ExportReport, RegionReport, SliceFile,
+};
+pub use filter::{
+    AllFilter, AndFilter, BlockFilter, BoundsFilter, ChunkFilter, FullStatusFilter, HasDataFilter,
+    StatusFilter,
+};
+pub use manifest::{ExportManifest, ManifestRegion};
+pub use mca::{
+    read_mca, read_mca_iter, read_mca_mmap, read_mca_raw, read_region_file, read_region_header,
+    write_mca, write_mca_raw, write_mca_with_compression, ChunkData, ChunkFormat, ChunkIter,
+    ChunkLocation, InvalidChunk, McaReader, RawChunk, RegionFormat, RegionHeader,
+};
+pub use nbt_json::{
+    json_to_nbt, json_to_nbt_with_root_name, nbt_root_name, nbt_to_bytes, nbt_to_json,
+    nbt_to_json_with_options, nbt_to_snbt, snbt_to_nbt, ArrayEncoding, FieldMapper, NbtJsonOptions,
+    BUILTIN_FIELD_ALIASES, ROOT_NAME_KEY,
 };
-pub use mca::{read_mca, write_mca, ChunkData};
-pub use nbt_json::{json_to_nbt, nbt_to_json, FieldMapper};
 pub use restore::{
-    restore_level_dat, restore_region_slices, restore_world, restore_world_with_config,
+    restore_level_dat, restore_level_dat_snbt, restore_region_slices, restore_region_slices_snbt,
+    restore_world, restore_world_from_memory, restore_world_merge, restore_world_validate,
+    restore_world_validate_with_config, restore_world_with_config, LevelDatStatus, RegionFailure,
+    RestoreReport, ValidationProblem, ValidationReport,
 };
+pub use stats::{export_stats, world_stats, DimensionStats};
+pub use verify::{verify_world, ChunkMismatch, RegionVerification, VerifyReport};
+pub use watch::watch_world;
+pub use world::WorldReader;
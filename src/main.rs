@@ -1,14 +1,27 @@
 //! Minecraft 世界 JSON 序列化工具 - 用于 Git 存储
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+use clap_complete::{generate, Shell};
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{error, info, warn, Level, LevelFilter, Log, Metadata, Record};
 use std::fs;
-use std::path::PathBuf;
-use std::time::Instant;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
+use mcj::progress::{set_progress_handler, ProgressHandler};
 use mcj::{
-    export_world_with_area, export_world_with_config, restore_world_with_config, Config,
-    WorkspaceConfig,
+    clean_export_output, clone_world_raw, detect_world_bounds, diff_exports, export_stats,
+    export_world_incremental, export_world_with_area, export_world_with_config,
+    preview_world_denoise, restore_world_validate_with_config, restore_world_with_config,
+    verify_world, watch_world, world_stats, ChunkBounds, ChunkPos, CloneReport, Config,
+    ConfigFormat, DimensionStats, DuplicateChunkPolicy, ExistingOutputPolicy, ExportDiff,
+    ExportError, ExportLayout, ExportReport, LevelDatStatus, RestoreReport, SliceFormat,
+    ValidationReport, VerifyReport, WorkspaceConfig, WorldBounds,
 };
 
 /// Minecraft 世界 JSON 序列化工具 - 用于 Git 存储
@@ -19,6 +32,27 @@ struct Cli {
     #[arg(short, long, global = true)]
     config: Option<PathBuf>,
 
+    /// 使用配置中的命名方案（`[profiles.<name>]`），覆盖到基础配置上
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// 只输出警告和错误
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// 输出更详细的日志（-v 调试，-vv 追踪）
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// 不显示进度条（输出不是终端时自动禁用）
+    #[arg(long, global = true)]
+    no_progress: bool,
+
+    /// 并行处理的线程数（0 表示使用所有 CPU，覆盖配置中 `[performance]` 的 threads），
+    /// 可写在子命令之前或之后
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -44,6 +78,42 @@ enum Commands {
         /// 使用 vigrid/workspace.yml 中的工作区域
         #[arg(long)]
         workspace: bool,
+        /// 区块数据格式（json 或 snbt）
+        #[arg(long)]
+        format: Option<SliceFormat>,
+        /// 单个切片文件的最大大小（MB，如 8、0.5，也可写为 512K、8M）
+        #[arg(long, visible_alias = "slice-size", value_parser = parse_slice_size)]
+        max_slice_size: Option<f64>,
+        /// 输出文件布局（sliced 或 per-chunk）
+        #[arg(long)]
+        layout: Option<ExportLayout>,
+        /// 只导出指定范围内的区块（区块坐标：minX,minZ,maxX,maxZ）
+        #[arg(long, allow_hyphen_values = true)]
+        bounds: Option<ChunkBounds>,
+        /// 增量导出：跳过源文件未变化的 region（允许输出目录已存在）
+        #[arg(long)]
+        incremental: bool,
+        /// 导出所有区块，不论生成状态（Status）
+        #[arg(long)]
+        all_statuses: bool,
+        /// 只导出含有该方块的区块（如 minecraft:chest），可多次指定，满足任一即可
+        #[arg(long = "include-block", value_name = "BLOCK_ID")]
+        include_blocks: Vec<String>,
+        /// 试运行：统计将要写入的文件和大小，不写入任何文件
+        #[arg(long, conflicts_with = "incremental")]
+        dry_run: bool,
+        /// 将导出结果汇总以 JSON 写入指定文件
+        #[arg(long)]
+        report: Option<PathBuf>,
+        /// 不写入导出清单 mcj-manifest.json
+        #[arg(long)]
+        no_manifest: bool,
+        /// 任一 region 失败时立即停止
+        #[arg(long, conflicts_with = "keep_going")]
+        fail_fast: bool,
+        /// 任一 region 失败时继续处理其余 region（默认）
+        #[arg(long)]
+        keep_going: bool,
     },
     /// 从 JSON 还原世界
     Restore {
@@ -55,6 +125,45 @@ enum Commands {
         /// 不恢复默认值
         #[arg(long)]
         no_restore_defaults: bool,
+        /// 区块数据格式（json 或 snbt）
+        #[arg(long)]
+        format: Option<SliceFormat>,
+        /// 只校验区块文件（解析、转换、坐标检查），不写入任何文件
+        #[arg(long)]
+        dry_run: bool,
+        /// 覆盖已存在的输出目录（只删除 region/ 和 level.dat，保留其它文件）
+        #[arg(long, visible_alias = "override", conflicts_with = "merge")]
+        overwrite: bool,
+        /// 合并到输出目录中已有的世界：覆盖导出的区块，保留其余区块
+        #[arg(long)]
+        merge: bool,
+        /// 只还原指定范围内的区块（区块坐标：minX,minZ,maxX,maxZ）
+        #[arg(long, visible_alias = "area", allow_hyphen_values = true)]
+        bounds: Option<ChunkBounds>,
+        /// 只还原指定的 region（rx,rz，可重复或用 ; 分隔）
+        #[arg(long, value_parser = parse_region, value_delimiter = ';', allow_hyphen_values = true)]
+        regions: Vec<[i32; 2]>,
+        /// 将坐标不属于所在 region 的区块移动到正确的 region（否则视为错误）
+        #[arg(long)]
+        relocate_chunks: bool,
+        /// 切片中出现坐标重复的区块时的处理方式（error 或 last）
+        #[arg(long)]
+        dedupe: Option<DuplicateChunkPolicy>,
+        /// 跳过无法读取的文件和无法还原的区块，尽可能还原其余区块
+        #[arg(long)]
+        best_effort: bool,
+        /// 为缺少高度图的区块根据方块数据计算 MOTION_BLOCKING 和 WORLD_SURFACE
+        #[arg(long)]
+        compute_heightmaps: bool,
+        /// 将还原结果汇总以 JSON 写入指定文件
+        #[arg(long)]
+        report: Option<PathBuf>,
+        /// 任一 region 失败时立即停止
+        #[arg(long, conflicts_with = "keep_going")]
+        fail_fast: bool,
+        /// 任一 region 失败时继续处理其余 region（默认）
+        #[arg(long)]
+        keep_going: bool,
     },
     /// 克隆世界（经过去噪处理）
     Clone {
@@ -74,36 +183,662 @@ enum Commands {
         /// 使用 vigrid/workspace.yml 中的工作区域
         #[arg(long)]
         workspace: bool,
+        /// 单个切片文件的最大大小（MB，如 8、0.5，也可写为 512K、8M）
+        #[arg(long, visible_alias = "slice-size", value_parser = parse_slice_size)]
+        max_slice_size: Option<f64>,
+        /// 不经过 JSON，直接复制区块的压缩数据（不去噪，需要同时指定 --no-denoise 或在配置中关闭去噪；速度快，可复制无法解析的区块）
+        #[arg(long, conflicts_with_all = ["json_dir", "workspace", "max_slice_size", "no_aggressive"])]
+        raw: bool,
+        /// 任一 region 失败时立即停止
+        #[arg(long, conflicts_with = "keep_going")]
+        fail_fast: bool,
+        /// 任一 region 失败时继续处理其余 region（默认）
+        #[arg(long)]
+        keep_going: bool,
+    },
+    /// 校验往返是否无损：导出并还原到临时目录，逐区块比较原世界和还原结果
+    Verify {
+        /// 世界文件夹路径
+        world: PathBuf,
+        /// 禁用去噪声处理
+        #[arg(long)]
+        no_denoise: bool,
+        /// 禁用激进去噪（默认启用）
+        #[arg(long)]
+        no_aggressive: bool,
+    },
+    /// 监视世界目录，自动重新导出游戏保存过的 region
+    Watch {
+        /// 世界文件夹路径
+        world: PathBuf,
+        /// 输出文件夹路径
+        output: PathBuf,
+        /// 检查间隔（秒）
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// 禁用去噪声处理
+        #[arg(long)]
+        no_denoise: bool,
+        /// 禁用激进去噪（默认启用）
+        #[arg(long)]
+        no_aggressive: bool,
+    },
+    /// 比较两个导出目录，列出新增、删除和修改的区块
+    Diff {
+        /// 旧导出目录
+        a: PathBuf,
+        /// 新导出目录
+        b: PathBuf,
+        /// 区块数据格式（json 或 snbt）
+        #[arg(long)]
+        format: Option<SliceFormat>,
+        /// 以 JSON 输出差异
+        #[arg(long)]
+        json: bool,
+        /// 比较前不去噪（默认先对两边去噪，忽略噪声字段的差异）
+        #[arg(long)]
+        no_denoise: bool,
+        /// 禁用激进去噪（默认启用）
+        #[arg(long)]
+        no_aggressive: bool,
+    },
+    /// 统计导出目录或世界中的区块、方块、方块实体和实体
+    Stats {
+        /// JSON 文件夹或世界文件夹路径（含有 level.dat 时按世界读取 MCA 文件）
+        path: PathBuf,
+        /// 区块数据格式（json 或 snbt，只用于导出目录）
+        #[arg(long)]
+        format: Option<SliceFormat>,
+        /// 列出数量最多的前 N 种方块
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// 预览去噪会移除哪些字段（只读，不修改任何文件）
+    DenoisePreview {
+        /// 世界文件夹路径
+        world: PathBuf,
+        /// 禁用激进去噪（默认启用）
+        #[arg(long)]
+        no_aggressive: bool,
     },
     /// 生成默认配置文件
     Config {
-        /// 输出路径（默认: mcj.toml）
-        #[arg(short, long, default_value = "mcj.toml")]
-        output: PathBuf,
+        /// 输出路径（默认: mcj.<格式扩展名>）
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// 配置文件格式（toml、json 或 yaml，默认按输出路径扩展名判断）
+        #[arg(long)]
+        format: Option<ConfigFormat>,
+        /// 生成配置文件的 JSON Schema（默认输出到 mcj-schema.json）
+        #[arg(long, conflicts_with = "format")]
+        schema: bool,
         /// 覆盖已存在的文件
         #[arg(long)]
         force: bool,
+        /// 检查配置文件中的错误和可能的问题，不生成文件（有错误时以非零状态退出）
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["output", "format", "schema", "force"])]
+        validate: Option<PathBuf>,
+    },
+    /// 生成 shell 补全脚本（输出到 stdout）
+    Completions {
+        /// shell 类型（bash、zsh、fish、powershell 或 elvish）
+        shell: Shell,
     },
 }
 
-fn load_config(config_path: Option<PathBuf>) -> Config {
-    if let Some(path) = config_path {
-        match Config::load_from_file(&path) {
+/// 命令行日志输出：警告和错误写入 stderr，其余写入 stdout
+struct CliLogger {
+    level: LevelFilter,
+}
+
+impl Log for CliLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level && metadata.target().starts_with("mcj")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let print = || match record.level() {
+            Level::Error => eprintln!("{}", record.args()),
+            Level::Warn => eprintln!("警告: {}", record.args()),
+            _ => println!("{}", record.args()),
+        };
+        // 进度条显示期间先隐藏进度条再输出，避免日志与进度条交错
+        let bar = ACTIVE_BAR.lock().unwrap().clone();
+        match bar {
+            Some(bar) => bar.suspend(print),
+            None => print(),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// 当前显示的进度条
+static ACTIVE_BAR: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// 命令行进度条：每个维度显示一个 region 进度条
+struct CliProgress;
+
+impl ProgressHandler for CliProgress {
+    fn start(&self, label: &str, total: usize) {
+        let bar = ProgressBar::new(total as u64);
+        bar.set_style(
+            ProgressStyle::with_template(
+                "{prefix} [{bar:40}] {pos}/{len} region ({elapsed}, ETA {eta})",
+            )
+            .unwrap()
+            .progress_chars("#>-"),
+        );
+        bar.set_prefix(label.to_string());
+        *ACTIVE_BAR.lock().unwrap() = Some(bar);
+    }
+
+    fn inc(&self) {
+        if let Some(bar) = ACTIVE_BAR.lock().unwrap().as_ref() {
+            bar.inc(1);
+        }
+    }
+
+    fn finish(&self) {
+        if let Some(bar) = ACTIVE_BAR.lock().unwrap().take() {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+fn init_logger(quiet: bool, verbose: u8) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Warn,
+        // 未指定 -q/-v 时使用 RUST_LOG（如 RUST_LOG=mcj=debug）
+        (false, 0) => env_log_level().unwrap_or(LevelFilter::Info),
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    let logger = Box::new(CliLogger { level });
+    if log::set_boxed_logger(logger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// 从 RUST_LOG 读取 mcj 的日志级别
+///
+/// 支持 `debug`、`mcj=debug` 和 `info,mcj=trace` 等写法，`mcj=` 指令优先于全局级别
+fn env_log_level() -> Option<LevelFilter> {
+    let spec = std::env::var("RUST_LOG").ok()?;
+    let mut global = None;
+    for directive in spec.split(',').map(str::trim) {
+        match directive.split_once('=') {
+            Some(("mcj", level)) => return level.parse().ok(),
+            Some(_) => {}
+            None => global = directive.parse().ok().or(global),
+        }
+    }
+    global
+}
+
+/// 解析切片大小参数（MB），也接受 K/M/G 后缀（按 1024 进制）
+fn parse_slice_size(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    let (digits, mb) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&s[..s.len() - 1], 1.0 / 1024.0),
+        Some('M') => (&s[..s.len() - 1], 1.0),
+        Some('G') => (&s[..s.len() - 1], 1024.0),
+        _ => (s, 1.0),
+    };
+    let value: f64 = digits
+        .trim()
+        .parse()
+        .map_err(|_| format!("无效的大小: {}", s))?;
+    Some(value * mb)
+        .filter(|size| size.is_finite() && *size > 0.0)
+        .ok_or_else(|| format!("无效的大小: {}", s))
+}
+
+/// 解析 region 坐标 `rx,rz`
+fn parse_region(s: &str) -> Result<[i32; 2], String> {
+    let invalid = || format!("无效的 region 坐标: {}（格式: rx,rz）", s);
+    let (rx, rz) = s.split_once(',').ok_or_else(invalid)?;
+    Ok([
+        rx.trim().parse().map_err(|_| invalid())?,
+        rz.trim().parse().map_err(|_| invalid())?,
+    ])
+}
+
+/// 输出区块差异，输出到终端时着色
+fn print_diff(diff: &ExportDiff) -> io::Result<()> {
+    let choice = if io::stdout().is_terminal() {
+        ColorChoice::Auto
+    } else {
+        ColorChoice::Never
+    };
+    let mut out = StandardStream::stdout(choice);
+    let pos = |p: &ChunkPos| match p.dimension.as_str() {
+        "" => format!("({}, {})", p.x, p.z),
+        dim => format!("{} ({}, {})", dim, p.x, p.z),
+    };
+
+    for (sign, color, chunks) in [
+        ("+", Color::Green, &diff.added),
+        ("-", Color::Red, &diff.removed),
+    ] {
+        out.set_color(ColorSpec::new().set_fg(Some(color)))?;
+        for chunk in chunks {
+            writeln!(out, "{} {}", sign, pos(chunk))?;
+        }
+    }
+    for chunk in &diff.modified {
+        out.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+        write!(out, "~ {}", pos(&chunk.pos))?;
+        out.reset()?;
+        writeln!(out, ": {}", chunk.changed_keys.join(", "))?;
+    }
+    out.reset()?;
+
+    if diff.is_empty() {
+        writeln!(out, "两个导出的区块完全相同")?;
+    } else {
+        writeln!(
+            out,
+            "\n新增 {} 个区块，删除 {} 个，修改 {} 个",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.modified.len()
+        )?;
+    }
+    Ok(())
+}
+
+/// 输出按区域文件名和 level.dat 检测的世界范围
+fn print_world_bounds(bounds: &WorldBounds) {
+    println!("== 世界范围");
+    if bounds.region_count > 0 {
+        println!(
+            "region 文件: {} 个，约 {} 个区块",
+            bounds.region_count, bounds.approximate_chunk_count
+        );
+        println!(
+            "区块范围: ({}, {}) ~ ({}, {})，{} x {} 区块（按 region 对齐）",
+            bounds.min_cx,
+            bounds.min_cz,
+            bounds.max_cx,
+            bounds.max_cz,
+            bounds.max_cx - bounds.min_cx + 1,
+            bounds.max_cz - bounds.min_cz + 1
+        );
+    } else {
+        println!("region 文件: 0 个");
+    }
+    if let (Some(x), Some(z)) = (bounds.spawn_x, bounds.spawn_z) {
+        println!(
+            "出生点: ({}, {})，区块 ({}, {})",
+            x,
+            z,
+            x.div_euclid(16),
+            z.div_euclid(16)
+        );
+    }
+    println!();
+}
+
+/// 以表格输出各维度的统计结果
+fn print_stats(stats: &[DimensionStats], top: usize) {
+    if stats.is_empty() {
+        println!("没有找到区块文件");
+        return;
+    }
+
+    for dim in stats {
+        println!("== {}", dim.name);
+        println!(
+            "区块: {} 个，section: {} 个，方块实体: {} 个，实体: {} 个",
+            dim.chunks,
+            dim.section_count(),
+            dim.block_entity_count(),
+            dim.entity_count()
+        );
+        if let Some(b) = dim.bounds {
+            println!(
+                "范围: ({}, {}) ~ ({}, {})，{} x {} 区块",
+                b.min_x,
+                b.min_z,
+                b.max_x,
+                b.max_z,
+                b.max_x - b.min_x + 1,
+                b.max_z - b.min_z + 1
+            );
+        }
+
+        let top_blocks: Vec<_> = dim
+            .top_blocks(top)
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count.to_string()))
+            .collect();
+        print_table((&format!("方块（前 {}）", top), "数量"), &top_blocks);
+
+        let block_entities: Vec<_> = dim
+            .block_entities
+            .iter()
+            .map(|(id, count)| (id.clone(), count.to_string()))
+            .collect();
+        print_table(("方块实体", "数量"), &block_entities);
+
+        let entities: Vec<_> = dim
+            .entities
+            .iter()
+            .map(|(id, count)| (id.clone(), count.to_string()))
+            .collect();
+        print_table(("实体", "数量"), &entities);
+
+        let histogram: Vec<_> = dim
+            .sections_histogram
+            .iter()
+            .map(|(sections, chunks)| (sections.to_string(), chunks.to_string()))
+            .collect();
+        print_table(("section 数", "区块数"), &histogram);
+        println!();
+    }
+}
+
+/// 输出两列表格，第二列右对齐
+fn print_table(header: (&str, &str), rows: &[(String, String)]) {
+    if rows.is_empty() {
+        return;
+    }
+    // 按显示宽度对齐（中文字符占两列）
+    let width = |s: &str| {
+        s.chars()
+            .map(|c| if c.is_ascii() { 1 } else { 2 })
+            .sum::<usize>()
+    };
+    let left = rows
+        .iter()
+        .map(|(l, _)| width(l))
+        .chain([width(header.0)])
+        .max()
+        .unwrap_or(0);
+    let right = rows
+        .iter()
+        .map(|(_, r)| width(r))
+        .chain([width(header.1)])
+        .max()
+        .unwrap_or(0);
+
+    println!();
+    let row = |l: &str, r: &str| {
+        println!(
+            "  {}{}  {}{}",
+            l,
+            " ".repeat(left - width(l)),
+            " ".repeat(right - width(r)),
+            r
+        )
+    };
+    row(header.0, header.1);
+    println!("  {}", "-".repeat(left + 2 + right));
+    for (l, r) in rows {
+        row(l, r);
+    }
+}
+
+/// 输出补全脚本，并在 stderr 提示安装方式
+fn print_completions(shell: Shell) {
+    let mut cmd = Cli::command();
+    let name = cmd.get_name().to_string();
+    generate(shell, &mut cmd, name, &mut io::stdout());
+
+    let hint = match shell {
+        Shell::Bash => "mcj completions bash > ~/.local/share/bash-completion/completions/mcj",
+        Shell::Zsh => "mcj completions zsh > ~/.zfunc/_mcj（并确保 ~/.zfunc 在 fpath 中）",
+        Shell::Fish => "mcj completions fish > ~/.config/fish/completions/mcj.fish",
+        Shell::PowerShell => "mcj completions powershell >> $PROFILE",
+        Shell::Elvish => "mcj completions elvish >> ~/.config/elvish/rc.elv",
+        _ => return,
+    };
+    eprintln!("安装方式: {}", hint);
+}
+
+/// 输出导出结果汇总
+fn print_export_report(report: &ExportReport) {
+    info!("");
+    if report.regions_unchanged > 0 {
+        info!(
+            "region: {} 个（失败 {} 个，未变化 {} 个）",
+            report.regions_total, report.regions_failed, report.regions_unchanged
+        );
+    } else {
+        info!(
+            "region: {} 个（失败 {} 个）",
+            report.regions_total, report.regions_failed
+        );
+    }
+    for region in report.failed_regions() {
+        info!(
+            "  失败 {:?}: {}",
+            region.path.file_name().unwrap_or_default(),
+            region.error.as_deref().unwrap_or_default()
+        );
+    }
+    info!(
+        "区块: 导出 {} 个，跳过空区块 {} 个，跳过未完成区块 {} 个",
+        report.chunks_exported, report.chunks_skipped_empty, report.chunks_skipped_not_full
+    );
+    if report.chunks_skipped_filtered > 0 {
+        info!("  被过滤器排除的区块 {} 个", report.chunks_skipped_filtered);
+    }
+    if report.chunks_failed > 0 {
+        info!("  无法读取的损坏区块 {} 个", report.chunks_failed);
+    }
+    info!(
+        "写入: {:.2} MB",
+        report.bytes_written as f64 / 1024.0 / 1024.0
+    );
+
+    let denoise = &report.denoise;
+    if !denoise.fields_removed.is_empty()
+        || denoise.sections_removed > 0
+        || denoise.chunks_skipped > 0
+    {
+        info!("去噪统计:");
+        for (field, count) in denoise.sorted_fields() {
+            info!("  {}: {}", field, count);
+        }
+        info!("  空 section: {}", denoise.sections_removed);
+        info!("  跳过区块: {}", denoise.chunks_skipped);
+    }
+}
+
+/// 将导出报告写为 JSON
+fn write_export_report(path: &Path, report: &ExportReport) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(report)? + "\n")?;
+    info!("已写入导出报告: {:?}", path);
+    Ok(())
+}
+
+/// 试运行时列出将要写入的文件
+fn print_dry_run_files(report: &ExportReport, output_path: &Path) {
+    let mut files: Vec<_> = report.per_region.iter().flat_map(|r| &r.files).collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    info!("");
+    info!("试运行，将写入以下文件:");
+    for file in &files {
+        let path = file.path.strip_prefix(output_path).unwrap_or(&file.path);
+        info!("  {} ({} 字节)", path.display(), file.bytes);
+    }
+    info!(
+        "共 {} 个文件，{} 字节（不含 level.dat）",
+        files.len(),
+        report.bytes_written
+    );
+}
+
+/// 输出直接复制区块（`clone --raw`）的结果汇总
+fn print_clone_report(report: &CloneReport) {
+    info!("");
+    info!(
+        "region: {} 个（失败 {} 个）",
+        report.regions_total,
+        report.failures.len()
+    );
+    for failure in &report.failures {
+        info!(
+            "  失败 {:?}: {}",
+            failure.path.file_name().unwrap_or_default(),
+            failure.error
+        );
+    }
+    info!(
+        "区块: 复制 {} 个，失败 {} 个",
+        report.chunks_copied, report.chunks_failed
+    );
+    info!("\n克隆完成! 总耗时: {:.2}s", report.elapsed_secs);
+}
+
+/// 输出还原结果汇总
+fn print_restore_report(report: &RestoreReport) {
+    for message in &report.errors {
+        warn!("已跳过: {}", message);
+    }
+    info!("");
+    let level_dat = match report.level_dat {
+        LevelDatStatus::Restored => "已还原",
+        LevelDatStatus::Kept => "保留目标世界已有的文件",
+        LevelDatStatus::Missing => "导出目录中没有",
+        LevelDatStatus::Skipped => "出错，已跳过",
+    };
+    info!("level.dat: {}", level_dat);
+    info!(
+        "region: {} 个（还原 {} 个，失败 {} 个）",
+        report.regions_total,
+        report.regions_restored,
+        report.failures.len()
+    );
+    for failure in &report.failures {
+        info!(
+            "  失败 {:?}: {}",
+            failure.path.file_name().unwrap_or_default(),
+            failure.error
+        );
+    }
+    info!(
+        "区块: 写入 {} 个，跳过 {} 个",
+        report.chunks_written, report.chunks_skipped
+    );
+    if !report.defaults_injected.is_empty() {
+        info!("补全默认值:");
+        for (field, count) in &report.defaults_injected {
+            info!("  {}: {} 个区块", field, count);
+        }
+    }
+}
+
+fn print_validation_report(report: &ValidationReport) {
+    for problem in &report.problems {
+        match problem.chunk {
+            Some((x, z)) => error!(
+                "{}: 区块 ({}, {}): {}",
+                problem.file.display(),
+                x,
+                z,
+                problem.message
+            ),
+            None => error!("{}: {}", problem.file.display(), problem.message),
+        }
+    }
+    info!(
+        "校验 {} 个文件、{} 个区块，发现 {} 个问题",
+        report.files_checked,
+        report.chunks_checked,
+        report.problems.len()
+    );
+}
+
+/// 输出往返校验结果：每个 region 一行，不一致的区块列在其下
+fn print_verify_report(report: &VerifyReport) {
+    info!("");
+    for region in &report.regions {
+        if region.passed() {
+            info!("  通过 {}（{} 个区块）", region.file, region.chunks_checked);
+            continue;
+        }
+        error!(
+            "  失败 {}（{} 个区块，{} 个不一致）",
+            region.file,
+            region.chunks_checked,
+            region.mismatches.len()
+        );
+        for mismatch in &region.mismatches {
+            error!(
+                "    区块 ({}, {}): {}",
+                mismatch.x, mismatch.z, mismatch.message
+            );
+        }
+    }
+    info!(
+        "region: {} 个（失败 {} 个）",
+        report.regions.len(),
+        report.failed_regions().count()
+    );
+}
+
+/// 根据 `--fail-fast`/`--keep-going` 得到失败处理方式，未指定时使用配置值
+fn resolve_fail_fast(fail_fast: bool, keep_going: bool, config_value: bool) -> bool {
+    if fail_fast {
+        true
+    } else if keep_going {
+        false
+    } else {
+        config_value
+    }
+}
+
+fn load_config(config_path: Option<PathBuf>, profile: Option<&str>) -> Result<Config> {
+    let mut config = config_path
+        .and_then(|path| match Config::load_from_file(&path) {
             Ok(config) => {
-                eprintln!("已加载配置: {}", path.display());
-                return config;
+                info!("已加载配置: {}", path.display());
+                Some(config)
             }
             Err(e) => {
-                eprintln!("警告: 无法加载配置 {}: {}", path.display(), e);
+                warn!("无法加载配置 {}: {:#}", path.display(), e);
+                None
             }
-        }
+        })
+        .unwrap_or_else(Config::load_from_default_paths);
+    if let Some(name) = profile {
+        config = config.with_profile(name)?;
+        info!("使用配置方案: {}", name);
+    }
+    // 环境变量优先于配置文件（包括配置方案），命令行参数在之后覆盖
+    config.apply_env_overrides();
+
+    for warning in config.errors().into_iter().chain(config.validate()) {
+        warn!("{}", warning);
     }
-    Config::load()
+    Ok(config)
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let config = load_config(cli.config);
+
+    // 补全脚本直接输出到 stdout，不加载配置，避免日志混入脚本
+    if let Commands::Completions { shell } = cli.command {
+        print_completions(shell);
+        return Ok(());
+    }
+
+    init_logger(cli.quiet, cli.verbose);
+    // 进度条输出到 stderr，不是终端时 indicatif 会自动隐藏
+    if !cli.no_progress && !cli.quiet {
+        set_progress_handler(Box::new(CliProgress));
+    }
+    let mut config = load_config(cli.config, cli.profile.as_deref())?;
+    if let Some(threads) = cli.threads {
+        config.performance.threads = threads;
+    }
 
     match cli.command {
         Commands::Export {
@@ -113,7 +848,44 @@ fn main() -> Result<()> {
             no_denoise,
             no_aggressive,
             workspace,
+            format,
+            max_slice_size,
+            layout,
+            bounds,
+            incremental,
+            all_statuses,
+            include_blocks,
+            dry_run,
+            report: report_path,
+            no_manifest,
+            fail_fast,
+            keep_going,
         } => {
+            if let Some(format) = format {
+                config.export.format = format;
+            }
+            config.export.fail_fast =
+                resolve_fail_fast(fail_fast, keep_going, config.export.fail_fast);
+            if let Some(max_slice_size) = max_slice_size {
+                config.export.max_slice_size_mb = max_slice_size;
+            }
+            if let Some(layout) = layout {
+                config.export.layout = layout;
+            }
+            if bounds.is_some() {
+                config.export.bounds = bounds;
+            }
+            if all_statuses {
+                config.export.included_statuses = vec!["*".to_string()];
+            }
+            if !include_blocks.is_empty() {
+                config.export.include_blocks = include_blocks;
+            }
+            config.export.dry_run = dry_run;
+            if no_manifest {
+                config.export.manifest = false;
+            }
+
             let output_path = output.unwrap_or_else(|| {
                 let mut p = world.clone();
                 p.set_file_name(format!(
@@ -123,19 +895,12 @@ fn main() -> Result<()> {
                 p
             });
 
-            // 检查输出目录
-            if output_path.exists() {
+            // 检查输出目录（试运行不会修改输出目录）
+            if output_path.exists() && !dry_run {
                 if overwrite {
                     // 只清理导出会生成的内容，保留 .git 等
-                    let level_json = output_path.join("level.json");
-                    if level_json.exists() {
-                        fs::remove_file(&level_json)?;
-                    }
-                    let region_dir = output_path.join("region");
-                    if region_dir.exists() {
-                        fs::remove_dir_all(&region_dir)?;
-                    }
-                } else {
+                    clean_export_output(&output_path)?;
+                } else if !incremental {
                     anyhow::bail!("输出目录已存在: {:?}\n使用 --overwrite 覆盖", output_path);
                 }
             }
@@ -146,15 +911,15 @@ fn main() -> Result<()> {
             } else {
                 config.export.denoise
             };
-            let do_aggressive = if no_aggressive { false } else { true }; // 默认启用激进模式
+            let do_aggressive = !no_aggressive; // 默认启用激进模式
 
-            println!("导出世界: {:?}", world);
-            println!("输出目录: {:?}", output_path);
-            println!("去噪声: {}", if do_denoise { "是" } else { "否" });
+            info!("导出世界: {:?}", world);
+            info!("输出目录: {:?}", output_path);
+            info!("去噪声: {}", if do_denoise { "是" } else { "否" });
             if do_denoise {
-                println!("激进模式: {}", if do_aggressive { "是" } else { "否" });
+                info!("激进模式: {}", if do_aggressive { "是" } else { "否" });
             }
-            println!();
+            info!("");
 
             // 加载工作区配置
             let area = if workspace {
@@ -164,7 +929,16 @@ fn main() -> Result<()> {
             };
 
             let start = Instant::now();
-            if area.is_some() {
+            let result = if incremental {
+                export_world_incremental(
+                    &world,
+                    &output_path,
+                    do_denoise,
+                    do_aggressive,
+                    &config,
+                    area.as_ref(),
+                )
+            } else if area.is_some() {
                 export_world_with_area(
                     &world,
                     &output_path,
@@ -172,18 +946,93 @@ fn main() -> Result<()> {
                     do_aggressive,
                     &config,
                     area.as_ref(),
-                )?;
+                )
             } else {
-                export_world_with_config(&world, &output_path, do_denoise, do_aggressive, &config)?;
+                export_world_with_config(&world, &output_path, do_denoise, do_aggressive, &config)
+            };
+            // 导出中止时也写入已完成部分的报告，便于自动化流程定位问题
+            let report = match result {
+                Ok(report) => report,
+                Err(e) => {
+                    if let (Some(report_path), Some(failed)) =
+                        (&report_path, e.downcast_ref::<ExportError>())
+                    {
+                        write_export_report(report_path, &failed.report)?;
+                    }
+                    return Err(e);
+                }
+            };
+            if dry_run {
+                print_dry_run_files(&report, &output_path);
+            }
+            print_export_report(&report);
+            info!("\n耗时: {:.2}s", start.elapsed().as_secs_f64());
+            // region 导出失败时也写入报告，便于自动化流程定位问题
+            if let Some(report_path) = report_path {
+                write_export_report(&report_path, &report)?;
+            }
+            if report.regions_failed > 0 {
+                anyhow::bail!("{} 个 region 导出失败", report.regions_failed);
             }
-            println!("\n耗时: {:.2}s", start.elapsed().as_secs_f64());
         }
 
         Commands::Restore {
             json_dir,
             output,
             no_restore_defaults,
+            format,
+            dry_run,
+            overwrite,
+            merge,
+            bounds,
+            regions,
+            relocate_chunks,
+            dedupe,
+            best_effort,
+            compute_heightmaps,
+            report: report_path,
+            fail_fast,
+            keep_going,
         } => {
+            if let Some(format) = format {
+                config.restore.format = format;
+            }
+            if relocate_chunks {
+                config.restore.relocate_chunks = true;
+            }
+            if let Some(dedupe) = dedupe {
+                config.restore.dedupe = dedupe;
+            }
+            if best_effort {
+                config.restore.best_effort = true;
+            }
+            if compute_heightmaps {
+                config.restore.compute_heightmaps = true;
+            }
+            config.restore.existing_output = if merge {
+                ExistingOutputPolicy::Merge
+            } else if overwrite {
+                ExistingOutputPolicy::Clean
+            } else {
+                ExistingOutputPolicy::Fail
+            };
+            if bounds.is_some() {
+                config.restore.bounds = bounds;
+            }
+            if !regions.is_empty() {
+                config.restore.regions = regions;
+            }
+            if dry_run {
+                let report = restore_world_validate_with_config(&json_dir, &config)?;
+                print_validation_report(&report);
+                if !report.is_ok() {
+                    anyhow::bail!("发现 {} 个问题", report.problems.len());
+                }
+                return Ok(());
+            }
+            config.restore.fail_fast =
+                resolve_fail_fast(fail_fast, keep_going, config.restore.fail_fast);
+
             let output_path = output.unwrap_or_else(|| {
                 let mut p = json_dir.clone();
                 p.set_file_name(format!(
@@ -200,17 +1049,30 @@ fn main() -> Result<()> {
                 config.restore.restore_defaults
             };
 
-            println!("还原 JSON: {:?}", json_dir);
-            println!("输出目录: {:?}", output_path);
-            println!(
+            info!("还原 JSON: {:?}", json_dir);
+            info!("输出目录: {:?}", output_path);
+            info!(
                 "恢复默认值: {}",
                 if do_restore_defaults { "是" } else { "否" }
             );
-            println!();
+            if merge {
+                info!("合并到已有世界: 是");
+            }
+            info!("");
 
             let start = Instant::now();
-            restore_world_with_config(&json_dir, &output_path, do_restore_defaults, &config)?;
-            println!("\n耗时: {:.2}s", start.elapsed().as_secs_f64());
+            let report =
+                restore_world_with_config(&json_dir, &output_path, do_restore_defaults, &config)?;
+            print_restore_report(&report);
+            info!("\n耗时: {:.2}s", start.elapsed().as_secs_f64());
+
+            if let Some(report_path) = report_path {
+                fs::write(&report_path, serde_json::to_string_pretty(&report)? + "\n")?;
+                info!("已写入还原报告: {:?}", report_path);
+            }
+            if !report.failures.is_empty() {
+                anyhow::bail!("{} 个 region 还原失败", report.failures.len());
+            }
         }
 
         Commands::Clone {
@@ -220,10 +1082,37 @@ fn main() -> Result<()> {
             no_denoise,
             no_aggressive,
             workspace,
+            max_slice_size,
+            raw,
+            fail_fast,
+            keep_going,
         } => {
             if dest.exists() {
                 anyhow::bail!("目标路径已存在: {:?}", dest);
             }
+            config.export.fail_fast =
+                resolve_fail_fast(fail_fast, keep_going, config.export.fail_fast);
+            if raw {
+                if config.export.denoise && !no_denoise {
+                    anyhow::bail!(
+                        "--raw 直接复制区块，不会去噪；请同时指定 --no-denoise 或在配置中关闭 export.denoise"
+                    );
+                }
+                info!("克隆世界（直接复制区块）: {:?}", source);
+                info!("目标位置: {:?}", dest);
+                info!("");
+                let report = clone_world_raw(&source, &dest, &config)?;
+                print_clone_report(&report);
+                if !report.failures.is_empty() {
+                    anyhow::bail!("{} 个 region 复制失败", report.failures.len());
+                }
+                return Ok(());
+            }
+            config.restore.fail_fast =
+                resolve_fail_fast(fail_fast, keep_going, config.restore.fail_fast);
+            if let Some(max_slice_size) = max_slice_size {
+                config.export.max_slice_size_mb = max_slice_size;
+            }
 
             // 使用配置默认值，命令行参数优先
             let do_denoise = if no_denoise {
@@ -231,15 +1120,15 @@ fn main() -> Result<()> {
             } else {
                 config.export.denoise
             };
-            let do_aggressive = if no_aggressive { false } else { true }; // 默认启用激进模式
+            let do_aggressive = !no_aggressive; // 默认启用激进模式
 
-            println!("克隆世界: {:?}", source);
-            println!("目标位置: {:?}", dest);
-            println!("去噪声: {}", if do_denoise { "是" } else { "否" });
+            info!("克隆世界: {:?}", source);
+            info!("目标位置: {:?}", dest);
+            info!("去噪声: {}", if do_denoise { "是" } else { "否" });
             if do_denoise {
-                println!("激进模式: {}", if do_aggressive { "是" } else { "否" });
+                info!("激进模式: {}", if do_aggressive { "是" } else { "否" });
             }
-            println!();
+            info!("");
 
             let start = Instant::now();
 
@@ -255,10 +1144,10 @@ fn main() -> Result<()> {
             });
             let use_temp = json_dir.is_none();
 
-            println!("========================================");
-            println!("步骤 1/2: 导出为 JSON");
-            println!("========================================");
-            if area.is_some() {
+            info!("========================================");
+            info!("步骤 1/2: 导出为 JSON");
+            info!("========================================");
+            let report = if area.is_some() {
                 export_world_with_area(
                     &source,
                     &temp_dir,
@@ -266,34 +1155,184 @@ fn main() -> Result<()> {
                     do_aggressive,
                     &config,
                     area.as_ref(),
-                )?;
+                )?
             } else {
-                export_world_with_config(&source, &temp_dir, do_denoise, do_aggressive, &config)?;
-            }
+                export_world_with_config(&source, &temp_dir, do_denoise, do_aggressive, &config)?
+            };
+            print_export_report(&report);
+            let export_failed = report.regions_failed;
 
-            println!();
-            println!("========================================");
-            println!("步骤 2/2: 还原为世界");
-            println!("========================================");
-            restore_world_with_config(&temp_dir, &dest, config.restore.restore_defaults, &config)?;
+            info!("");
+            info!("========================================");
+            info!("步骤 2/2: 还原为世界");
+            info!("========================================");
+            let restore_report = restore_world_with_config(
+                &temp_dir,
+                &dest,
+                config.restore.restore_defaults,
+                &config,
+            )?;
+            print_restore_report(&restore_report);
 
             if use_temp {
                 let _ = fs::remove_dir_all(&temp_dir);
             }
 
-            println!("\n克隆完成! 总耗时: {:.2}s", start.elapsed().as_secs_f64());
+            info!("\n克隆完成! 总耗时: {:.2}s", start.elapsed().as_secs_f64());
             if json_dir.is_some() {
-                println!("JSON 已保留在: {:?}", temp_dir);
+                info!("JSON 已保留在: {:?}", temp_dir);
+            }
+            if export_failed > 0 || !restore_report.failures.is_empty() {
+                anyhow::bail!(
+                    "{} 个 region 导出失败，{} 个 region 还原失败",
+                    export_failed,
+                    restore_report.failures.len()
+                );
             }
         }
 
-        Commands::Config { output, force } => {
+        Commands::Verify {
+            world,
+            no_denoise,
+            no_aggressive,
+        } => {
+            let do_denoise = !no_denoise && config.export.denoise;
+            info!("校验世界: {:?}", world);
+            info!("");
+
+            let start = Instant::now();
+            let report = verify_world(&world, do_denoise, !no_aggressive, &config)?;
+            print_verify_report(&report);
+            info!("\n耗时: {:.2}s", start.elapsed().as_secs_f64());
+            let failed = report.failed_regions().count();
+            if failed > 0 {
+                anyhow::bail!("{} 个 region 往返后与原世界不一致", failed);
+            }
+        }
+
+        Commands::Watch {
+            world,
+            output,
+            interval,
+            no_denoise,
+            no_aggressive,
+        } => {
+            let do_denoise = !no_denoise && config.export.denoise;
+            let stop = Arc::new(AtomicBool::new(false));
+            let handler_stop = stop.clone();
+            ctrlc::set_handler(move || handler_stop.store(true, Ordering::Relaxed))?;
+            watch_world(
+                &world,
+                &output,
+                do_denoise,
+                !no_aggressive,
+                &config,
+                Duration::from_secs(interval),
+                &stop,
+            )?;
+        }
+
+        Commands::Diff {
+            a,
+            b,
+            format,
+            json,
+            no_denoise,
+            no_aggressive,
+        } => {
+            if let Some(format) = format {
+                config.restore.format = format;
+            }
+            let do_denoise = !no_denoise && config.export.denoise;
+            let diff = diff_exports(&a, &b, do_denoise, !no_aggressive, &config)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_diff(&diff)?;
+            }
+        }
+
+        Commands::Stats { path, format, top } => {
+            if let Some(format) = format {
+                config.restore.format = format;
+            }
+            if path.join("level.dat").is_file() {
+                print_world_bounds(&detect_world_bounds(&path)?);
+                print_stats(&world_stats(&path, &config)?, top);
+            } else {
+                print_stats(&export_stats(&path, &config)?, top);
+            }
+        }
+
+        Commands::DenoisePreview {
+            world,
+            no_aggressive,
+        } => {
+            let previews = preview_world_denoise(&world, !no_aggressive, &config)?;
+            for (label, diff) in previews {
+                println!("--- {}", label);
+                println!("+++ {} (去噪后)", label);
+                if diff.is_empty() {
+                    println!("  (无变化)");
+                }
+                for field in &diff.removed {
+                    println!("-{}: {}", field.path, field.preview);
+                }
+                for field in &diff.changed {
+                    println!("-{}: {}", field.path, field.before);
+                    println!("+{}: {}", field.path, field.after);
+                }
+                println!();
+            }
+        }
+
+        Commands::Config {
+            output,
+            format,
+            schema,
+            force,
+            validate,
+        } => {
+            if let Some(path) = validate {
+                let check = Config::check_file(&path);
+                println!("检查配置: {:?}", path);
+                for error in &check.errors {
+                    println!("  错误: {}", error);
+                }
+                for warning in &check.warnings {
+                    println!("  警告: {}", warning);
+                }
+                println!(
+                    "{} 个错误，{} 个警告",
+                    check.errors.len(),
+                    check.warnings.len()
+                );
+                if !check.is_ok() {
+                    anyhow::bail!("配置文件有 {} 个错误", check.errors.len());
+                }
+                return Ok(());
+            }
+            if schema {
+                let output = output.unwrap_or_else(|| PathBuf::from("mcj-schema.json"));
+                if output.exists() && !force {
+                    anyhow::bail!("文件已存在: {:?}\n使用 --force 覆盖", output);
+                }
+                fs::write(&output, Config::json_schema() + "\n")?;
+                println!("已生成配置 Schema: {:?}", output);
+                return Ok(());
+            }
+
+            let format = format
+                .or_else(|| output.as_deref().map(ConfigFormat::from_path))
+                .unwrap_or_default();
+            let output =
+                output.unwrap_or_else(|| PathBuf::from(format!("mcj.{}", format.extension())));
             if output.exists() && !force {
                 anyhow::bail!("文件已存在: {:?}\n使用 --force 覆盖", output);
             }
 
             let default_config = Config::default();
-            default_config.save_to_file(&output)?;
+            default_config.save_to_file_with_format(&output, format)?;
             println!("已生成配置文件: {:?}", output);
             println!("\n配置项说明:");
             println!("  [export]");
@@ -310,9 +1349,14 @@ fn main() -> Result<()> {
                 "    restore_defaults = {}  # 默认恢复默认值",
                 default_config.restore.restore_defaults
             );
+            println!("  [restore.defaults]");
+            println!("    LastUpdate = \"0L\"      # 恢复默认值时补全的字段及默认值");
             println!("  [denoise.chunk]");
             println!("    fields = [...]         # 区块去噪字段");
             println!("    aggressive_fields = [...]  # 激进去噪字段");
+            println!("  [denoise.entity]");
+            println!("    fields = [...]         # 实体去噪字段");
+            println!("    aggressive_fields = [...]  # 激进模式下移除的实体字段（UUID）");
             println!("  [denoise.level]");
             println!("    fields = [...]         # 存档去噪字段");
             println!(
@@ -320,6 +1364,8 @@ fn main() -> Result<()> {
                 default_config.denoise.level.reset_weather
             );
         }
+
+        Commands::Completions { .. } => unreachable!("补全脚本已在加载配置前生成"),
     }
 
     Ok(())
@@ -0,0 +1,192 @@
+//! 导出清单 - 记录导出的 region、切片数量和区块数量，还原前检查切片文件是否齐全
+
+use crate::config::SliceFormat;
+use crate::export::RegionReport;
+use crate::mca::parse_region_filename;
+use crate::restore::{collect_region_files, is_block_entities_dir_file};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 清单文件名（位于导出目录根部）
+pub const EXPORT_MANIFEST_FILE: &str = "mcj-manifest.json";
+
+/// 清单格式版本
+const EXPORT_MANIFEST_VERSION: u32 = 1;
+
+/// 导出清单
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportManifest {
+    /// 清单格式版本
+    pub version: u32,
+    /// 世界名称（世界目录名）
+    pub world: String,
+    /// 导出时间（UTC，ISO 8601）
+    pub exported_at: String,
+    /// level.dat 中的 Minecraft 数据版本（`DataVersion`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_version: Option<i32>,
+    /// 导出的 region（按维度和坐标排序）
+    pub regions: Vec<ManifestRegion>,
+}
+
+/// 清单中的 region
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestRegion {
+    /// 维度目录（主世界为空，如 `DIM-1`）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub dimension: String,
+    /// 维度中的目录（`region`、`entities` 或 `poi`）
+    #[serde(default = "default_folder", skip_serializing_if = "is_default_folder")]
+    pub folder: String,
+    pub rx: i32,
+    pub rz: i32,
+    /// 切片文件数量（包括分层切片，不包括 `be/` 中拆分出的方块实体文件；
+    /// 每区块一个文件的布局下为区块文件数量）
+    pub slices: usize,
+    /// 导出的区块数量
+    pub chunks: usize,
+}
+
+impl ExportManifest {
+    /// 根据各 region 的导出结果生成清单
+    ///
+    /// 失败和没有写入任何文件的 region 不列出；增量导出中未变化的 region 沿用 `previous` 中的记录
+    pub fn new(
+        world_path: &Path,
+        data_version: Option<i32>,
+        regions: &[RegionReport],
+        previous: Option<&ExportManifest>,
+    ) -> Self {
+        let previous: BTreeMap<(&str, &str, i32, i32), &ManifestRegion> = previous
+            .map(|m| {
+                m.regions
+                    .iter()
+                    .map(|r| ((r.dimension.as_str(), r.folder.as_str(), r.rx, r.rz), r))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut entries = Vec::new();
+        for region in regions.iter().filter(|r| r.error.is_none()) {
+            let Some((dimension, folder, rx, rz)) = region_location(world_path, &region.path)
+            else {
+                continue;
+            };
+            if region.unchanged {
+                if let Some(entry) = previous.get(&(dimension.as_str(), folder.as_str(), rx, rz)) {
+                    entries.push((*entry).clone());
+                }
+            } else if region.slices_written > 0 {
+                entries.push(ManifestRegion {
+                    dimension,
+                    folder,
+                    rx,
+                    rz,
+                    slices: region
+                        .files
+                        .iter()
+                        .filter(|f| !is_block_entities_dir_file(&f.path))
+                        .count(),
+                    chunks: region.chunks_exported,
+                });
+            }
+        }
+        entries.sort_by(|a, b| {
+            (&a.dimension, &a.folder, a.rx, a.rz).cmp(&(&b.dimension, &b.folder, b.rx, b.rz))
+        });
+
+        Self {
+            version: EXPORT_MANIFEST_VERSION,
+            world: world_path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            exported_at: crate::util::iso8601_now(),
+            data_version,
+            regions: entries,
+        }
+    }
+
+    /// 读取导出目录中的清单，不存在时返回 `None`
+    pub fn load(output_path: &Path) -> Result<Option<Self>> {
+        let path = output_path.join(EXPORT_MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("读取 {} 失败", path.display()))?;
+        let manifest: Self = serde_json::from_str(&content)
+            .with_context(|| format!("解析 {} 失败", path.display()))?;
+        if manifest.version != EXPORT_MANIFEST_VERSION {
+            anyhow::bail!("{} 的版本 {} 不受支持", path.display(), manifest.version);
+        }
+        Ok(Some(manifest))
+    }
+
+    /// 检查导出目录中各 region 的切片文件是否齐全，返回缺少文件的 region 的说明
+    pub fn missing_files(&self, json_path: &Path, format: SliceFormat) -> Vec<String> {
+        let mut dimensions = BTreeMap::new();
+        let mut missing = Vec::new();
+        for region in &self.regions {
+            let files = dimensions
+                .entry((region.dimension.as_str(), region.folder.as_str()))
+                .or_insert_with(|| {
+                    let dir = json_path.join(&region.dimension).join(&region.folder);
+                    collect_region_files(&dir, format).unwrap_or_default()
+                });
+            let found = files.get(&(region.rx, region.rz)).map_or(0, |files| {
+                files
+                    .iter()
+                    .filter(|f| !is_block_entities_dir_file(f))
+                    .count()
+            });
+            if found < region.slices {
+                let prefix = if region.dimension.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}/", region.dimension)
+                };
+                missing.push(format!(
+                    "{}{}/r.{}.{}: 清单中有 {} 个切片文件，只找到 {} 个",
+                    prefix, region.folder, region.rx, region.rz, region.slices, found
+                ));
+            }
+        }
+        missing
+    }
+
+    /// 序列化为写入导出目录的内容
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+}
+
+/// 源 MCA 文件所在的维度目录、维度中的目录名和 region 坐标
+fn region_location(world_path: &Path, mca_path: &Path) -> Option<(String, String, i32, i32)> {
+    let (rx, rz, _) = parse_region_filename(mca_path.file_name()?.to_str()?)?;
+    let region_dir = mca_path.parent()?;
+    let folder = region_dir.file_name()?.to_str()?.to_string();
+    let dimension: PathBuf = region_dir
+        .parent()?
+        .strip_prefix(world_path)
+        .ok()?
+        .to_path_buf();
+    Some((
+        dimension.to_string_lossy().replace('\\', "/"),
+        folder,
+        rx,
+        rz,
+    ))
+}
+
+/// 旧版清单没有 `folder` 字段，记录的都是 `region/`
+fn default_folder() -> String {
+    "region".to_string()
+}
+
+fn is_default_folder(folder: &str) -> bool {
+    folder == "region"
+}
@@ -1,122 +1,683 @@
 //! MCA 区域文件解析与写入
 
+use crate::config::{CompressionCodec, CompressionConfig};
+use crate::util::write_atomically_synced;
 use anyhow::Result;
 use fastnbt::Value;
+use log::warn;
 use regex::Regex;
+use std::borrow::Cow;
 use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 
 /// 扇区大小（字节）
 pub const SECTOR_SIZE: usize = 4096;
 
+/// 压缩类型字节的最高位表示区块数据存储在外部的 `c.{x}.{z}.mcc` 文件中
+const EXTERNAL_CHUNK_FLAG: u8 = 0x80;
+
+/// LZ4 区块使用 lz4-java 的 `LZ4BlockOutputStream` 格式：若干个块，每块以 21 字节的块头开始
+/// （魔数、方式和级别、压缩后长度、原始长度、校验和，整数为小端序），以长度为 0 的块结束
+const LZ4_BLOCK_MAGIC: &[u8; 8] = b"LZ4Block";
+const LZ4_BLOCK_HEADER_LEN: usize = 21;
+/// 块内数据未压缩
+const LZ4_METHOD_RAW: u8 = 0x10;
+/// 块内数据为 LZ4 压缩
+const LZ4_METHOD_LZ4: u8 = 0x20;
+/// 写入时的块大小（lz4-java 默认的 64KB），块头低 4 位为 `log2(块大小) - 10`
+const LZ4_BLOCK_SIZE: usize = 64 * 1024;
+const LZ4_BLOCK_SIZE_LEVEL: u8 = 6;
+/// 块校验和：原始数据的 XXHash32（使用该种子）的低 28 位
+const LZ4_CHECKSUM_SEED: u32 = 0x9747_b28c;
+
 /// 区块数据
+#[derive(Debug, Clone)]
 pub struct ChunkData {
     pub x: i32,
     pub z: i32,
+    /// 最后保存时间（Unix 秒，来自 region 文件的时间戳表，0 表示未知）
+    pub timestamp: u32,
     pub data: Value,
 }
 
-/// 读取 MCA 文件中的所有区块
-pub fn read_mca(path: &Path) -> Result<Vec<ChunkData>> {
-    let mut file = File::open(path)?;
-    let mut data = Vec::new();
-    file.read_to_end(&mut data)?;
+impl ChunkData {
+    /// 区块的数据版本（根标签中的 `DataVersion`），1.9 之前保存的区块没有该字段
+    pub fn data_version(&self) -> Option<i32> {
+        match &self.data {
+            Value::Compound(root) => match root.get("DataVersion") {
+                Some(Value::Int(version)) => Some(*version),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// 未解压的区块，压缩类型和压缩后的数据原样保留（见 [`read_mca_raw`]）
+#[derive(Debug, Clone)]
+pub struct RawChunk {
+    /// region 内坐标
+    pub x: i32,
+    pub z: i32,
+    /// 最后保存时间（Unix 秒）
+    pub timestamp: u32,
+    /// 压缩类型字节（gzip 1、zlib 2、不压缩 3、LZ4 4，最高位表示数据在外部 `.mcc` 文件中）
+    pub compression: u8,
+    /// 压缩后的区块数据
+    pub payload: Vec<u8>,
+}
+
+/// 1.13 的数据版本，之后方块使用命名空间 ID 和 section 调色板（扁平化）
+pub const DATA_VERSION_1_13: i32 = 1519;
+
+/// 1.18 的数据版本，之后区块数据不再位于 `Level` 中，section 为 `sections` 列表，世界高度为 -64–319
+pub const DATA_VERSION_1_18: i32 = 2860;
 
-    if data.len() < SECTOR_SIZE * 2 {
-        return Ok(vec![]);
+/// 区块格式，按数据版本划分，同一格式内的区块结构相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChunkFormat {
+    /// 1.13 之前：数字方块 ID（`Blocks`/`Data` 数组），没有 `DataVersion` 的区块也归为此类
+    PreFlattening,
+    /// 1.13–1.17：数据位于 `Level` 中，section 带调色板
+    Level,
+    /// 1.18 及之后
+    Modern,
+}
+
+impl ChunkFormat {
+    /// 按数据版本判断区块格式
+    pub fn from_data_version(data_version: Option<i32>) -> Self {
+        match data_version {
+            Some(version) if version >= DATA_VERSION_1_18 => ChunkFormat::Modern,
+            Some(version) if version >= DATA_VERSION_1_13 => ChunkFormat::Level,
+            _ => ChunkFormat::PreFlattening,
+        }
     }
 
-    let mut chunks = Vec::new();
+    /// 用于日志的格式说明
+    pub fn description(&self) -> &'static str {
+        match self {
+            ChunkFormat::PreFlattening => "1.13 之前",
+            ChunkFormat::Level => "1.13–1.17",
+            ChunkFormat::Modern => "1.18+",
+        }
+    }
+}
 
-    for i in 0..1024 {
-        let offset =
-            u32::from_be_bytes([0, data[i * 4], data[i * 4 + 1], data[i * 4 + 2]]) as usize;
-        let sector_count = data[i * 4 + 3] as usize;
+/// 每个 region 的区块数量
+pub const CHUNKS_PER_REGION: usize = 1024;
 
-        if offset == 0 || sector_count == 0 {
-            continue;
+/// 区域文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RegionFormat {
+    /// Anvil（`.mca`，Beta 1.3 之后）
+    #[default]
+    Anvil,
+    /// McRegion（`.mcr`，Beta 1.3 之前），文件结构与 Anvil 相同，区块总是 zlib 压缩
+    McRegion,
+}
+
+impl RegionFormat {
+    /// 按扩展名识别格式，`.mcr` 为 McRegion，其它为 Anvil
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("mcr") => RegionFormat::McRegion,
+            _ => RegionFormat::Anvil,
         }
+    }
+
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RegionFormat::Anvil => "mca",
+            RegionFormat::McRegion => "mcr",
+        }
+    }
+}
 
-        let x = (i % 32) as i32;
-        let z = (i / 32) as i32;
+/// 区域文件头中一个区块的位置信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChunkLocation {
+    /// 区块数据起始的扇区编号，0 表示区块不存在
+    pub offset: u32,
+    /// 区块占用的扇区数
+    pub sector_count: u8,
+    /// 最后保存时间（Unix 时间戳，秒）
+    pub timestamp: u32,
+}
+
+impl ChunkLocation {
+    /// 区块是否存在
+    pub fn is_present(&self) -> bool {
+        self.offset != 0 && self.sector_count != 0
+    }
+
+    /// 区块数据在文件中的字节偏移
+    pub fn byte_offset(&self) -> usize {
+        self.offset as usize * SECTOR_SIZE
+    }
+
+    /// 区块占用的字节数（按扇区对齐，包括长度和压缩类型前缀）
+    pub fn allocated_size(&self) -> usize {
+        self.sector_count as usize * SECTOR_SIZE
+    }
+}
+
+/// 区域文件头（位置表和时间戳表），不解压任何区块
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionHeader {
+    locations: Vec<ChunkLocation>,
+}
+
+impl Default for RegionHeader {
+    fn default() -> Self {
+        Self {
+            locations: vec![ChunkLocation::default(); CHUNKS_PER_REGION],
+        }
+    }
+}
 
-        let chunk_offset = offset * SECTOR_SIZE;
-        if chunk_offset + 5 > data.len() {
-            continue;
+impl RegionHeader {
+    /// 从文件开头的两个扇区解析文件头
+    pub fn from_bytes(header: &[u8]) -> Result<Self> {
+        if header.len() < SECTOR_SIZE * 2 {
+            anyhow::bail!(
+                "区域文件头不完整（{} 字节，需要 {} 字节）",
+                header.len(),
+                SECTOR_SIZE * 2
+            );
         }
+        let read_u32 = |i: usize| u32::from_be_bytes(header[i..i + 4].try_into().unwrap());
+        let locations = (0..CHUNKS_PER_REGION)
+            .map(|index| {
+                let i = index * 4;
+                ChunkLocation {
+                    offset: u32::from_be_bytes([0, header[i], header[i + 1], header[i + 2]]),
+                    sector_count: header[i + 3],
+                    timestamp: read_u32(SECTOR_SIZE + i),
+                }
+            })
+            .collect();
+        Ok(Self { locations })
+    }
+
+    /// 指定编号（`z * 32 + x`）的区块位置
+    pub fn location(&self, index: usize) -> ChunkLocation {
+        self.locations[index]
+    }
+
+    /// region 内坐标为 (x, z) 的区块位置，坐标按 32 取模
+    pub fn get(&self, x: i32, z: i32) -> ChunkLocation {
+        self.locations[chunk_index(x, z)]
+    }
+
+    /// region 内坐标为 (x, z) 的区块是否存在，坐标按 32 取模
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        self.get(x, z).is_present()
+    }
 
-        let length = u32::from_be_bytes([
-            data[chunk_offset],
-            data[chunk_offset + 1],
-            data[chunk_offset + 2],
-            data[chunk_offset + 3],
-        ]) as usize;
+    /// 所有存在的区块的 region 内坐标 (x, z)，按编号顺序
+    pub fn chunks(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.present()
+            .map(|(index, _)| ((index % 32) as i32, (index / 32) as i32))
+    }
+
+    /// 所有存在的区块的编号和位置
+    pub fn present(&self) -> impl Iterator<Item = (usize, ChunkLocation)> + '_ {
+        self.locations
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, location)| location.is_present())
+    }
 
-        let compression = data[chunk_offset + 4];
+    /// 存在的区块数量
+    pub fn chunk_count(&self) -> usize {
+        self.present().count()
+    }
+}
+
+/// region 内坐标 (x, z) 对应的区块编号，坐标按 32 取模
+fn chunk_index(x: i32, z: i32) -> usize {
+    (z.rem_euclid(32) * 32 + x.rem_euclid(32)) as usize
+}
 
-        if chunk_offset + 5 + length - 1 > data.len() {
-            continue;
+/// 只读取区域文件头，不解压区块
+///
+/// 文件不足两个扇区（如游戏创建的空 region 文件）时返回没有区块的文件头
+pub fn read_region_header(path: &Path) -> Result<RegionHeader> {
+    let mut file = File::open(path)?;
+    if (file.metadata()?.len() as usize) < SECTOR_SIZE * 2 {
+        return Ok(RegionHeader::default());
+    }
+    let mut header = vec![0u8; SECTOR_SIZE * 2];
+    file.read_exact(&mut header)?;
+    RegionHeader::from_bytes(&header)
+}
+
+/// 逐个读取 MCA 文件中的区块
+///
+/// 只在内存中保留文件头，每次读取时按位置表定位到对应区块，
+/// 适合边读边处理、不希望一次性解码整个 region 的场景
+pub struct McaReader {
+    source: Source,
+    file_len: usize,
+    header: RegionHeader,
+    format: RegionFormat,
+    failed_chunks: usize,
+}
+
+impl McaReader {
+    /// 打开 MCA 文件（按扩展名识别 McRegion），文件不足两个扇区（没有完整的头部）时返回 `None`
+    pub fn open(path: &Path) -> Result<Option<Self>> {
+        let mut file = File::open(path)?;
+        let file_len = file.metadata()?.len() as usize;
+        if file_len < SECTOR_SIZE * 2 {
+            return Ok(None);
+        }
+
+        let mut header = vec![0u8; SECTOR_SIZE * 2];
+        file.read_exact(&mut header)?;
+        Ok(Some(Self {
+            header: RegionHeader::from_bytes(&header)?,
+            source: Source::File(file),
+            file_len,
+            format: RegionFormat::from_path(path),
+            failed_chunks: 0,
+        }))
+    }
+
+    /// 以只读内存映射打开 MCA 文件，直接在映射的内存上解压区块，不复制压缩数据
+    ///
+    /// 映射失败（如 Windows 上文件被游戏锁定）时回退到 [`open`](Self::open)
+    pub fn open_mmap(path: &Path) -> Result<Option<Self>> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len() as usize;
+        if file_len < SECTOR_SIZE * 2 {
+            return Ok(None);
         }
 
-        let compressed = &data[chunk_offset + 5..chunk_offset + 4 + length];
+        // SAFETY: 映射为只读；读取期间文件被其它进程截断属于未定义行为，
+        // 因此只应在世界未被游戏打开时使用
+        let map = match unsafe { memmap2::Mmap::map(&file) } {
+            Ok(map) => map,
+            Err(e) => {
+                warn!("无法映射 {}（{}），改用普通读取", path.display(), e);
+                return Self::open(path);
+            }
+        };
+        let header = RegionHeader::from_bytes(&map[..SECTOR_SIZE * 2])?;
+        Ok(Some(Self {
+            header,
+            source: Source::Mmap(map),
+            file_len,
+            format: RegionFormat::from_path(path),
+            failed_chunks: 0,
+        }))
+    }
+
+    /// 读取指定编号（`z * 32 + x`）的区块，区块不存在或无法解析时返回 `None`
+    ///
+    /// 无法解析的区块会输出警告并计入 [`failed_chunks`](Self::failed_chunks)
+    pub fn read_chunk(&mut self, index: usize) -> Result<Option<ChunkData>> {
+        match self.load_chunk(index)? {
+            Ok(chunk) => Ok(chunk),
+            Err(invalid) => {
+                warn!("{}，跳过", invalid);
+                self.failed_chunks += 1;
+                Ok(None)
+            }
+        }
+    }
 
-        let nbt_data = match compression {
-            1 => {
-                // Gzip
-                let mut decoder = flate2::read::GzDecoder::new(compressed);
-                let mut decompressed = Vec::new();
-                decoder.read_to_end(&mut decompressed)?;
-                decompressed
+    /// 读取指定编号的区块但不解压，区块不存在或数据截断时返回 `None`
+    ///
+    /// 压缩类型原样保留（包括未知的压缩类型和外部 `.mcc` 标记），截断的区块会输出警告并计入
+    /// [`failed_chunks`](Self::failed_chunks)
+    pub fn read_raw_chunk(&mut self, index: usize) -> Result<Option<RawChunk>> {
+        let (x, z) = ((index % 32) as i32, (index / 32) as i32);
+        match self.load_payload(index)? {
+            Ok(Some((timestamp, compression, payload))) => Ok(Some(RawChunk {
+                x,
+                z,
+                timestamp,
+                compression,
+                payload: payload.into_owned(),
+            })),
+            Ok(None) => Ok(None),
+            Err(invalid) => {
+                warn!("{}，跳过", invalid);
+                self.failed_chunks += 1;
+                Ok(None)
             }
-            2 => {
-                // Zlib
-                let mut decoder = flate2::read::ZlibDecoder::new(compressed);
-                let mut decompressed = Vec::new();
-                decoder.read_to_end(&mut decompressed)?;
-                decompressed
+        }
+    }
+
+    /// 按编号顺序读取所有存在的区块的压缩数据（见 [`read_raw_chunk`](Self::read_raw_chunk)）
+    pub fn read_all_raw(&mut self) -> Result<Vec<RawChunk>> {
+        let indices: Vec<usize> = self.header.present().map(|(index, _)| index).collect();
+        let mut chunks = Vec::with_capacity(indices.len());
+        for index in indices {
+            if let Some(chunk) = self.read_raw_chunk(index)? {
+                chunks.push(chunk);
             }
-            3 => compressed.to_vec(), // 无压缩
-            _ => continue,
+        }
+        Ok(chunks)
+    }
+
+    /// 读取并解码区块，外层错误为 I/O 错误，内层错误为区块数据损坏、截断或无法识别
+    fn load_chunk(&mut self, index: usize) -> Result<Result<Option<ChunkData>, InvalidChunk>> {
+        let x = (index % 32) as i32;
+        let z = (index / 32) as i32;
+        let invalid = |reason: String| Ok(Err(InvalidChunk { x, z, reason }));
+
+        let format = self.format;
+        let (timestamp, compression, compressed) = match self.load_payload(index)? {
+            Ok(Some(payload)) => payload,
+            Ok(None) => return Ok(Ok(None)),
+            Err(e) => return Ok(Err(e)),
+        };
+
+        if format == RegionFormat::McRegion && compression != 2 {
+            return invalid(format!(
+                "McRegion 区块的压缩类型为 {}（应为 2）",
+                compression
+            ));
+        }
+
+        let decompressed = match compression {
+            1 => gzip_decompress(&compressed), // Gzip
+            2 => zlib_decompress(&compressed), // Zlib
+            3 => Ok(compressed.into_owned()),  // 无压缩
+            4 => lz4_decompress(&compressed),  // LZ4（1.20.5+ 的 region-file-compression=lz4）
+            id if id & EXTERNAL_CHUNK_FLAG != 0 => {
+                return invalid("存储在外部 .mcc 文件中（过大的区块），暂不支持".to_string());
+            }
+            id => return invalid(format!("使用未知的压缩类型 {}", id)),
+        };
+        let nbt_data = match decompressed {
+            Ok(data) => data,
+            Err(e) => return invalid(format!("无法解压: {:#}", e)),
         };
 
         match fastnbt::from_bytes::<Value>(&nbt_data) {
-            Ok(value) => chunks.push(ChunkData { x, z, data: value }),
-            Err(e) => eprintln!("警告: 无法解析区块 ({}, {}): {}", x, z, e),
+            Ok(value) => Ok(Ok(Some(ChunkData {
+                x,
+                z,
+                timestamp,
+                data: value,
+            }))),
+            Err(e) => invalid(format!("无法解析: {}", e)),
         }
     }
 
+    /// 读取区块的时间戳、压缩类型和压缩后的数据，外层错误为 I/O 错误，内层错误为数据截断
+    #[allow(clippy::type_complexity)]
+    fn load_payload(
+        &mut self,
+        index: usize,
+    ) -> Result<Result<Option<(u32, u8, Cow<'_, [u8]>)>, InvalidChunk>> {
+        let location = self.header.location(index);
+        if !location.is_present() {
+            return Ok(Ok(None));
+        }
+
+        let x = (index % 32) as i32;
+        let z = (index / 32) as i32;
+        let invalid = |reason: String| Ok(Err(InvalidChunk { x, z, reason }));
+
+        let chunk_offset = location.byte_offset();
+        if chunk_offset + 5 > self.file_len {
+            return invalid("区块位置超出文件末尾".to_string());
+        }
+
+        let prefix: [u8; 5] = match &mut self.source {
+            Source::File(file) => {
+                file.seek(SeekFrom::Start(chunk_offset as u64))?;
+                let mut prefix = [0u8; 5];
+                file.read_exact(&mut prefix)?;
+                prefix
+            }
+            Source::Mmap(map) => map[chunk_offset..chunk_offset + 5].try_into().unwrap(),
+        };
+        let length = u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+        let compression = prefix[4];
+
+        if length == 0 || chunk_offset + 4 + length > self.file_len {
+            return invalid(format!("区块长度 {} 无效或数据被截断", length));
+        }
+
+        let payload: Cow<[u8]> = match &mut self.source {
+            Source::File(file) => {
+                let mut payload = vec![0u8; length - 1];
+                file.read_exact(&mut payload)?;
+                Cow::Owned(payload)
+            }
+            Source::Mmap(map) => Cow::Borrowed(&map[chunk_offset + 5..chunk_offset + 4 + length]),
+        };
+        Ok(Ok(Some((location.timestamp, compression, payload))))
+    }
+
+    /// 按编号顺序逐个读取所有存在的区块（见 [`read_mca_iter`]）
+    pub fn into_chunks(self) -> ChunkIter {
+        let order = self.header.present().map(|(index, _)| index).collect();
+        self.into_chunks_in_order(order)
+    }
+
+    /// 按 `order` 中的编号顺序逐个读取区块，跳过不存在的区块
+    pub fn into_chunks_in_order(self, order: Vec<usize>) -> ChunkIter {
+        ChunkIter {
+            reader: Some(self),
+            order: order.into_iter(),
+        }
+    }
+
+    /// 区域文件头
+    pub fn header(&self) -> &RegionHeader {
+        &self.header
+    }
+
+    /// 区域文件格式
+    pub fn format(&self) -> RegionFormat {
+        self.format
+    }
+
+    /// 已读取但因数据损坏、截断或未知压缩方式而无法解析的区块数量（未知压缩方式会输出警告）
+    pub fn failed_chunks(&self) -> usize {
+        self.failed_chunks
+    }
+}
+
+/// 无法解析的区块（数据损坏、截断或使用不支持的压缩方式）
+///
+/// [`ChunkIter`] 产生的错误可通过 `downcast_ref::<InvalidChunk>()` 与 I/O 错误区分
+#[derive(Debug, Clone)]
+pub struct InvalidChunk {
+    /// region 内坐标
+    pub x: i32,
+    pub z: i32,
+    /// 无法解析的原因
+    pub reason: String,
+}
+
+impl std::fmt::Display for InvalidChunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "区块 ({}, {}) {}", self.x, self.z, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidChunk {}
+
+/// 逐个读取区块的迭代器，每次只解码一个区块
+///
+/// 不存在的区块被跳过；无法解析的区块产生 [`InvalidChunk`] 错误，之后继续读取下一个区块；
+/// I/O 错误之后迭代结束
+pub struct ChunkIter {
+    reader: Option<McaReader>,
+    order: std::vec::IntoIter<usize>,
+}
+
+impl ChunkIter {
+    /// 已读取的区域文件，文件不足两个扇区时为 `None`
+    pub fn reader(&self) -> Option<&McaReader> {
+        self.reader.as_ref()
+    }
+}
+
+impl Iterator for ChunkIter {
+    type Item = Result<ChunkData>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let reader = self.reader.as_mut()?;
+        for index in self.order.by_ref() {
+            match reader.load_chunk(index) {
+                Ok(Ok(Some(chunk))) => return Some(Ok(chunk)),
+                Ok(Ok(None)) => {}
+                Ok(Err(invalid)) => {
+                    reader.failed_chunks += 1;
+                    return Some(Err(invalid.into()));
+                }
+                Err(e) => {
+                    self.reader = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// 区块数据来源
+enum Source {
+    File(File),
+    Mmap(memmap2::Mmap),
+}
+
+/// 读取 MCA 文件中的所有区块
+///
+/// 无法解析的区块输出警告后跳过
+pub fn read_mca(path: &Path) -> Result<Vec<ChunkData>> {
+    read_all(McaReader::open(path)?)
+}
+
+/// 逐个读取 MCA 文件中的区块，只读取一次文件头，之后每个区块单独定位和解码
+///
+/// 与 [`read_mca`] 不同，处理完的区块可以立即释放；无法解析的区块作为错误项返回而不输出警告
+pub fn read_mca_iter(path: &Path) -> Result<ChunkIter> {
+    Ok(chunk_iter(McaReader::open(path)?))
+}
+
+/// 读取 MCA 文件中所有区块的压缩数据，不解压也不解析 NBT
+///
+/// 可用于原样复制区块（包括 fastnbt 无法解析的区块）；数据截断的区块输出警告后跳过
+pub fn read_mca_raw(path: &Path) -> Result<Vec<RawChunk>> {
+    match McaReader::open(path)? {
+        Some(mut reader) => reader.read_all_raw(),
+        None => Ok(vec![]),
+    }
+}
+
+fn chunk_iter(reader: Option<McaReader>) -> ChunkIter {
+    match reader {
+        Some(reader) => reader.into_chunks(),
+        None => ChunkIter {
+            reader: None,
+            order: Vec::new().into_iter(),
+        },
+    }
+}
+
+/// 按指定格式读取区域文件中的所有区块（不按扩展名识别）
+pub fn read_region_file(path: &Path, format: RegionFormat) -> Result<Vec<ChunkData>> {
+    let reader = McaReader::open(path)?.map(|reader| McaReader { format, ..reader });
+    read_all(reader)
+}
+
+/// 通过内存映射读取 MCA 文件中的所有区块（映射失败时回退到普通读取）
+///
+/// 压缩数据直接从映射的内存中解压，不需要先将整个文件读入内存
+pub fn read_mca_mmap(path: &Path) -> Result<Vec<ChunkData>> {
+    read_all(McaReader::open_mmap(path)?)
+}
+
+fn read_all(reader: Option<McaReader>) -> Result<Vec<ChunkData>> {
+    let mut chunks = Vec::new();
+    for chunk in chunk_iter(reader) {
+        match chunk {
+            Ok(chunk) => chunks.push(chunk),
+            Err(e) => match e.downcast::<InvalidChunk>() {
+                Ok(invalid) => warn!("{}，跳过", invalid),
+                Err(e) => return Err(e),
+            },
+        }
+    }
     Ok(chunks)
 }
 
-/// 将区块数据写入 MCA 文件
+/// 将区块数据写入 MCA 文件（zlib，默认压缩级别）
 pub fn write_mca(path: &Path, chunks: &[ChunkData]) -> Result<()> {
+    write_mca_with_compression(path, chunks, CompressionConfig::default())
+}
+
+/// 将区块数据写入 MCA 文件，使用指定的压缩方式和级别
+///
+/// 每个区块头部写入对应的压缩类型字节（gzip 1、zlib 2、不压缩 3、LZ4 4）
+pub fn write_mca_with_compression(
+    path: &Path,
+    chunks: &[ChunkData],
+    compression: CompressionConfig,
+) -> Result<()> {
+    compression.check()?;
+
+    if chunks.is_empty() {
+        return Ok(());
+    }
+
+    let raw_chunks = chunks
+        .iter()
+        .map(|chunk| {
+            let nbt_data = fastnbt::to_bytes(&chunk.data)?;
+            let payload = match compression.codec {
+                CompressionCodec::Gzip => gzip_compress(&nbt_data, compression.level)?,
+                CompressionCodec::Zlib => zlib_compress(&nbt_data, compression.level)?,
+                CompressionCodec::Lz4 => lz4_compress(&nbt_data),
+                CompressionCodec::None => nbt_data,
+            };
+            Ok(RawChunk {
+                x: chunk.x,
+                z: chunk.z,
+                timestamp: chunk.timestamp,
+                compression: compression.codec.id(),
+                payload,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    write_mca_raw(path, &raw_chunks)
+}
+
+/// 将未解压的区块写入 MCA 文件，区块按顺序紧密排列（不保留原文件中的空闲扇区）
+///
+/// 压缩类型和数据原样写入；`chunks` 为空时不写入文件
+pub fn write_mca_raw(path: &Path, chunks: &[RawChunk]) -> Result<()> {
     if chunks.is_empty() {
         return Ok(());
     }
 
     let mut locations = vec![0u8; SECTOR_SIZE];
-    let timestamps = vec![0u8; SECTOR_SIZE];
+    let mut timestamps = vec![0u8; SECTOR_SIZE];
     let mut chunk_sectors: Vec<Vec<u8>> = Vec::new();
     let mut current_sector = 2u32;
 
     for chunk in chunks {
-        let nbt_data = fastnbt::to_bytes(&chunk.data)?;
-
-        // Zlib 压缩
-        let mut encoder =
-            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
-        encoder.write_all(&nbt_data)?;
-        let compressed = encoder.finish()?;
-
-        let chunk_length = compressed.len() + 5;
-        let sector_count = (chunk_length + SECTOR_SIZE - 1) / SECTOR_SIZE;
+        let chunk_length = chunk.payload.len() + 5;
+        let sector_count = chunk_length.div_ceil(SECTOR_SIZE);
 
         // 构建 chunk 数据
         let mut chunk_data = Vec::with_capacity(sector_count * SECTOR_SIZE);
-        chunk_data.extend_from_slice(&((compressed.len() + 1) as u32).to_be_bytes());
-        chunk_data.push(2); // Zlib
-        chunk_data.extend_from_slice(&compressed);
+        chunk_data.extend_from_slice(&((chunk.payload.len() + 1) as u32).to_be_bytes());
+        chunk_data.push(chunk.compression);
+        chunk_data.extend_from_slice(&chunk.payload);
         chunk_data.resize(sector_count * SECTOR_SIZE, 0);
 
         // 写入位置表
@@ -127,6 +688,7 @@ pub fn write_mca(path: &Path, chunks: &[ChunkData]) -> Result<()> {
         locations[idx + 1] = offset_bytes[2];
         locations[idx + 2] = offset_bytes[3];
         locations[idx + 3] = sector_count as u8;
+        timestamps[idx..idx + 4].copy_from_slice(&chunk.timestamp.to_be_bytes());
 
         chunk_sectors.push(chunk_data);
         current_sector += sector_count as u32;
@@ -136,14 +698,197 @@ pub fn write_mca(path: &Path, chunks: &[ChunkData]) -> Result<()> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    let mut file = File::create(path)?;
-    file.write_all(&locations)?;
-    file.write_all(&timestamps)?;
+    let mut output = Vec::with_capacity(current_sector as usize * SECTOR_SIZE);
+    output.extend_from_slice(&locations);
+    output.extend_from_slice(&timestamps);
     for sector in chunk_sectors {
-        file.write_all(&sector)?;
+        output.extend_from_slice(&sector);
+    }
+    write_atomically_synced(path, output)
+}
+
+/// 解压 LZ4 区块数据（lz4-java 的块格式），校验每个块的长度和校验和
+fn lz4_decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut rest = data;
+    // 缺少结束块时按数据结束处理
+    while !rest.is_empty() {
+        if rest.len() < LZ4_BLOCK_HEADER_LEN || &rest[..8] != LZ4_BLOCK_MAGIC {
+            anyhow::bail!("LZ4 块头无效");
+        }
+        let int_at = |i: usize| u32::from_le_bytes(rest[i..i + 4].try_into().unwrap());
+        let method = rest[8] & 0xf0;
+        let compressed_len = int_at(9) as usize;
+        let original_len = int_at(13) as usize;
+        let checksum = int_at(17);
+        rest = &rest[LZ4_BLOCK_HEADER_LEN..];
+        if original_len == 0 {
+            break;
+        }
+        if rest.len() < compressed_len {
+            anyhow::bail!("LZ4 块数据被截断");
+        }
+        let (block, remaining) = rest.split_at(compressed_len);
+        let start = output.len();
+        match method {
+            LZ4_METHOD_RAW if compressed_len == original_len => output.extend_from_slice(block),
+            LZ4_METHOD_LZ4 => {
+                let decompressed = lz4_flex::block::decompress(block, original_len)?;
+                if decompressed.len() != original_len {
+                    anyhow::bail!("LZ4 块解压后长度不符");
+                }
+                output.extend_from_slice(&decompressed);
+            }
+            _ => anyhow::bail!("LZ4 块头无效"),
+        }
+        if lz4_checksum(&output[start..]) != checksum {
+            anyhow::bail!("LZ4 块校验和不匹配");
+        }
+        rest = remaining;
+    }
+    Ok(output)
+}
+
+/// 以 lz4-java 的块格式压缩区块数据（游戏读取时会校验块校验和）
+fn lz4_compress(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len() / 2 + LZ4_BLOCK_HEADER_LEN * 2);
+    let mut write_block = |method: u8, block: &[u8], original_len: usize, checksum: u32| {
+        output.extend_from_slice(LZ4_BLOCK_MAGIC);
+        output.push(method | LZ4_BLOCK_SIZE_LEVEL);
+        output.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        output.extend_from_slice(&(original_len as u32).to_le_bytes());
+        output.extend_from_slice(&checksum.to_le_bytes());
+        output.extend_from_slice(block);
+    };
+    for block in data.chunks(LZ4_BLOCK_SIZE) {
+        let checksum = lz4_checksum(block);
+        let compressed = lz4_flex::block::compress(block);
+        // 压缩后没有变小的块原样存储
+        if compressed.len() < block.len() {
+            write_block(LZ4_METHOD_LZ4, &compressed, block.len(), checksum);
+        } else {
+            write_block(LZ4_METHOD_RAW, block, block.len(), checksum);
+        }
     }
+    write_block(LZ4_METHOD_RAW, &[], 0, 0);
+    output
+}
+
+fn lz4_checksum(data: &[u8]) -> u32 {
+    xxhash_rust::xxh32::xxh32(data, LZ4_CHECKSUM_SEED) & 0x0fff_ffff
+}
+
+// ============== zlib/gzip ==============
+//
+// 启用 `libdeflate` feature 时使用 libdeflate 一次性处理整个缓冲区，否则使用 flate2 的纯 Rust 实现
 
-    Ok(())
+/// 解压 zlib 数据
+#[cfg(not(feature = "libdeflate"))]
+pub(crate) fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    flate2::read::ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// 解压 gzip 数据（只读取第一个成员）
+#[cfg(not(feature = "libdeflate"))]
+pub(crate) fn gzip_decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(data).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// 以 zlib 压缩数据，`level` 为 0–9
+#[cfg(not(feature = "libdeflate"))]
+pub(crate) fn zlib_compress(data: &[u8], level: u32) -> Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::new(level));
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// 以 gzip 压缩数据，`level` 为 0–9
+#[cfg(not(feature = "libdeflate"))]
+pub(crate) fn gzip_compress(data: &[u8], level: u32) -> Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level));
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// 解压 zlib 数据
+///
+/// 解压后的大小未知，输出缓冲区不足时加倍后重试
+#[cfg(feature = "libdeflate")]
+pub(crate) fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressor = libdeflater::Decompressor::new();
+    let mut output = vec![0u8; (data.len() * 4).max(SECTOR_SIZE)];
+    loop {
+        match decompressor.zlib_decompress(data, &mut output) {
+            Ok(len) => {
+                output.truncate(len);
+                return Ok(output);
+            }
+            Err(libdeflater::DecompressionError::InsufficientSpace) => {
+                output.resize(output.len() * 2, 0);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// 解压 gzip 数据（只读取第一个成员）
+///
+/// 按 gzip 尾部记录的原始大小（对 2^32 取模）分配输出缓冲区，不足时加倍后重试
+#[cfg(feature = "libdeflate")]
+pub(crate) fn gzip_decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let size = match data.len().checked_sub(4) {
+        Some(start) => u32::from_le_bytes(data[start..].try_into().unwrap()) as usize,
+        None => 0,
+    };
+    let mut decompressor = libdeflater::Decompressor::new();
+    let mut output = vec![0u8; size.max(SECTOR_SIZE)];
+    loop {
+        match decompressor.gzip_decompress(data, &mut output) {
+            Ok(len) => {
+                output.truncate(len);
+                return Ok(output);
+            }
+            Err(libdeflater::DecompressionError::InsufficientSpace) => {
+                output.resize(output.len() * 2, 0);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// 以 zlib 压缩数据，`level` 为 0–9
+#[cfg(feature = "libdeflate")]
+pub(crate) fn zlib_compress(data: &[u8], level: u32) -> Result<Vec<u8>> {
+    let mut compressor = libdeflate_compressor(level)?;
+    let mut output = vec![0u8; compressor.zlib_compress_bound(data.len())];
+    let len = compressor.zlib_compress(data, &mut output)?;
+    output.truncate(len);
+    Ok(output)
+}
+
+/// 以 gzip 压缩数据，`level` 为 0–9
+#[cfg(feature = "libdeflate")]
+pub(crate) fn gzip_compress(data: &[u8], level: u32) -> Result<Vec<u8>> {
+    let mut compressor = libdeflate_compressor(level)?;
+    let mut output = vec![0u8; compressor.gzip_compress_bound(data.len())];
+    let len = compressor.gzip_compress(data, &mut output)?;
+    output.truncate(len);
+    Ok(output)
+}
+
+#[cfg(feature = "libdeflate")]
+fn libdeflate_compressor(level: u32) -> Result<libdeflater::Compressor> {
+    let level = libdeflater::CompressionLvl::new(level as i32)
+        .map_err(|_| anyhow::anyhow!("压缩级别必须在 0–9 之间，当前为 {}", level))?;
+    Ok(libdeflater::Compressor::new(level))
 }
 
 /// 解析 MCA 文件名，返回 (rx, rz)
@@ -154,3 +899,62 @@ pub fn parse_mca_filename(filename: &str) -> Option<(i32, i32)> {
     let rz = caps.get(2)?.as_str().parse().ok()?;
     Some((rx, rz))
 }
+
+/// 解析区域文件名（`r.{rx}.{rz}.mca` 或 `r.{rx}.{rz}.mcr`），返回 (rx, rz, 格式)
+pub fn parse_region_filename(filename: &str) -> Option<(i32, i32, RegionFormat)> {
+    let re = Regex::new(r"^r\.(-?\d+)\.(-?\d+)\.(mca|mcr)$").ok()?;
+    let caps = re.captures(filename)?;
+    let rx = caps.get(1)?.as_str().parse().ok()?;
+    let rz = caps.get(2)?.as_str().parse().ok()?;
+    let format = match caps.get(3)?.as_str() {
+        "mcr" => RegionFormat::McRegion,
+        _ => RegionFormat::Anvil,
+    };
+    Some((rx, rz, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::sample_chunks;
+
+    #[test]
+    fn every_codec_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunks = sample_chunks(&[(0, 0), (3, 7), (31, 31)]);
+        let codecs = [
+            CompressionCodec::Gzip,
+            CompressionCodec::Zlib,
+            CompressionCodec::None,
+            CompressionCodec::Lz4,
+        ];
+        for codec in codecs {
+            for level in [0, 1, 9] {
+                let path = dir.path().join(format!("r.{:?}.{}.mca", codec, level));
+                let compression = CompressionConfig { codec, level };
+                write_mca_with_compression(&path, &chunks, compression).unwrap();
+
+                let raw = read_mca_raw(&path).unwrap();
+                assert!(raw.iter().all(|chunk| chunk.compression == codec.id()));
+                let read = read_mca(&path).unwrap();
+                assert_eq!(read.len(), chunks.len());
+                for (read, chunk) in read.iter().zip(&chunks) {
+                    assert_eq!((read.x, read.z), (chunk.x, chunk.z));
+                    assert_eq!(read.data, chunk.data, "{:?} level {}", codec, level);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn level_above_nine_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let compression = CompressionConfig {
+            codec: CompressionCodec::Zlib,
+            level: 10,
+        };
+        let path = dir.path().join("r.0.0.mca");
+        assert!(write_mca_with_compression(&path, &sample_chunks(&[(0, 0)]), compression).is_err());
+        assert!(!path.exists());
+    }
+}
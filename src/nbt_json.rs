@@ -1,42 +1,47 @@
 //! NBT 与 JSON 之间的转换
 
-use crate::config::FieldMappingConfig;
-use anyhow::Result;
+use crate::config::{ByteArrayEncoding, ExportConfig, FieldMappingConfig};
+use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use fastnbt::{ByteArray, IntArray, LongArray, Value};
 use once_cell::sync::Lazy;
 use serde_json::{json, Map, Value as JsonValue};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+/// 内置字段名别名：长名 -> 短名
+///
+/// 导出的 JSON 以这些短名提交到 Git，已有的别名不能修改或删除，只能新增
+pub const BUILTIN_FIELD_ALIASES: &[(&str, &str)] = &[
+    // 版本
+    ("DataVersion", "dv"),
+    // 区块核心字段
+    ("sections", "sec"),
+    ("block_entities", "be"),
+    ("block_states", "bs"),
+    ("block_ticks", "bt"),
+    ("fluid_ticks", "ft"),
+    ("PostProcessing", "pp"),
+    ("InhabitedTime", "it"),
+    ("LastUpdate", "lu"),
+    ("Heightmaps", "hm"),
+    ("CarvingMasks", "cm"),
+    ("blending_data", "bd"),
+    ("structures", "st"),
+    // section 字段
+    ("BlockLight", "bl"),
+    ("SkyLight", "skl"),
+    ("biomes", "bio"),
+    ("palette", "pal"),
+    // starlight mod 字段
+    ("starlight.blocklight_state", "sl.bls"),
+    ("starlight.skylight_state", "sl.sls"),
+    ("starlight.light_version", "sl.lv"),
+];
 
 /// 默认字段名映射：长名 -> 短名
-static DEFAULT_SHORTEN: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
-    HashMap::from([
-        // 版本
-        ("DataVersion", "dv"),
-        // 区块核心字段
-        ("sections", "sec"),
-        ("block_entities", "be"),
-        ("block_states", "bs"),
-        ("block_ticks", "bt"),
-        ("fluid_ticks", "ft"),
-        ("PostProcessing", "pp"),
-        ("InhabitedTime", "it"),
-        ("LastUpdate", "lu"),
-        ("Heightmaps", "hm"),
-        ("CarvingMasks", "cm"),
-        ("blending_data", "bd"),
-        ("structures", "st"),
-        // section 字段
-        ("BlockLight", "bl"),
-        ("SkyLight", "skl"),
-        ("biomes", "bio"),
-        ("palette", "pal"),
-        // starlight mod 字段
-        ("starlight.blocklight_state", "sl.bls"),
-        ("starlight.skylight_state", "sl.sls"),
-        ("starlight.light_version", "sl.lv"),
-    ])
-});
+static DEFAULT_SHORTEN: Lazy<HashMap<&'static str, &'static str>> =
+    Lazy::new(|| BUILTIN_FIELD_ALIASES.iter().copied().collect());
 
 /// 默认字段名映射：短名 -> 长名（反向查找）
 static DEFAULT_RESTORE: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
@@ -47,6 +52,9 @@ static DEFAULT_RESTORE: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(||
 });
 
 /// 字段名映射器
+///
+/// 导出时用 [`FieldMapper::shorten_json_keys`] 把字段名替换为短名，还原时用
+/// [`FieldMapper::restore_json_keys`] 换回原名；映射必须是一一对应的，否则还原结果不确定
 #[derive(Debug, Clone)]
 pub struct FieldMapper {
     enabled: bool,
@@ -56,6 +64,13 @@ pub struct FieldMapper {
 
 impl Default for FieldMapper {
     fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+impl FieldMapper {
+    /// 使用内置别名（[`BUILTIN_FIELD_ALIASES`]）创建映射器
+    pub fn with_builtins() -> Self {
         Self {
             enabled: true,
             shorten_map: DEFAULT_SHORTEN
@@ -68,26 +83,50 @@ impl Default for FieldMapper {
                 .collect(),
         }
     }
-}
 
-impl FieldMapper {
-    /// 从配置创建映射器
-    pub fn from_config(config: &FieldMappingConfig) -> Self {
-        let shorten_map: HashMap<String, String> = config
-            .mappings
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect();
-        let restore_map: HashMap<String, String> = config
-            .mappings
+    /// 不做任何映射的映射器：缩短和还原都原样保留字段名，内置别名的短名（如 `bs`）也不会被还原
+    pub fn identity() -> Self {
+        Self {
+            enabled: true,
+            shorten_map: HashMap::new(),
+            restore_map: HashMap::new(),
+        }
+    }
+
+    /// 使用自定义映射（长名 -> 短名）创建映射器，不包含内置别名（还原时只识别 `mappings` 中的短名）
+    ///
+    /// 两个字段映射为同一个短名，或短名与另一个被映射的字段名相同时返回错误
+    pub fn new(mappings: HashMap<String, String>) -> Result<Self> {
+        check_aliases(mappings.iter().map(|(k, v)| (k.as_str(), v.as_str())))?;
+        let restore_map = mappings
             .iter()
-            .map(|(k, v)| (v.clone(), k.clone()))
+            .map(|(long, short)| (short.clone(), long.clone()))
             .collect();
-        Self {
-            enabled: config.enabled,
-            shorten_map,
+        Ok(Self {
+            enabled: true,
+            shorten_map: mappings,
             restore_map,
+        })
+    }
+
+    /// 从配置创建映射器
+    ///
+    /// `use_builtins` 为 true 时以内置别名为基础，`mappings` 中的同名字段覆盖内置别名；
+    /// 不检查映射是否一一对应（由 [`FieldMappingConfig::check_aliases`] 在加载配置时检查）
+    pub fn from_config(config: &FieldMappingConfig) -> Self {
+        let mut mapper = if config.use_builtins {
+            Self::with_builtins()
+        } else {
+            Self::identity()
+        };
+        mapper.enabled = config.enabled;
+        for (long, short) in &config.mappings {
+            if let Some(old) = mapper.shorten_map.insert(long.clone(), short.clone()) {
+                mapper.restore_map.remove(&old);
+            }
+            mapper.restore_map.insert(short.clone(), long.clone());
         }
+        mapper
     }
 
     /// 缩短字段名
@@ -191,21 +230,89 @@ impl FieldMapper {
     }
 }
 
-/// 使用默认映射缩短字段名（向后兼容）
-#[allow(dead_code)]
-#[inline]
-fn shorten_field(name: &str) -> &str {
-    DEFAULT_SHORTEN.get(name).copied().unwrap_or(name)
+/// 检查字段映射（长名 -> 短名）是否一一对应
+///
+/// 两个字段映射为同一个短名，或某个短名本身也是被映射的字段名时，还原结果不确定
+pub(crate) fn check_aliases<'a>(
+    mappings: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Result<()> {
+    let shorten: BTreeMap<&str, &str> = mappings.into_iter().collect();
+    let mut restore: BTreeMap<&str, &str> = BTreeMap::new();
+    for (&long, &short) in &shorten {
+        if let Some(other) = restore.insert(short, long) {
+            anyhow::bail!("字段别名冲突: {} 和 {} 都映射为 {}", other, long, short);
+        }
+    }
+    for (&long, &short) in &shorten {
+        if short != long && shorten.contains_key(short) {
+            anyhow::bail!("字段别名冲突: {} 的短名 {} 也是被映射的字段名", long, short);
+        }
+    }
+    Ok(())
 }
 
-/// 使用默认映射还原字段名（向后兼容）
-#[inline]
-fn restore_field(name: &str) -> &str {
-    DEFAULT_RESTORE.get(name).copied().unwrap_or(name)
+/// `Array` 编码下写为 JSON 整数数组的默认最大元素数
+pub const DEFAULT_MAX_INLINE_ARRAY_LEN: usize = 64;
+
+/// JSON 整数数组形式的 NBT 数组标记键（`{"B;": [1, 2]}`）
+const ARRAY_MARKERS: [&str; 3] = ["B;", "I;", "L;"];
+
+/// NBT 数组的编码设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArrayEncoding {
+    /// 编码方式
+    pub encoding: ByteArrayEncoding,
+    /// `Array` 编码下写为 JSON 整数数组的最大元素数，更大的数组使用 base64
+    pub max_inline_len: usize,
+}
+
+impl Default for ArrayEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: ByteArrayEncoding::Base64,
+            max_inline_len: DEFAULT_MAX_INLINE_ARRAY_LEN,
+        }
+    }
+}
+
+impl ArrayEncoding {
+    /// 长度为 `len` 的数组是否写为 JSON 整数数组
+    ///
+    /// 空数组仍写为字符串，避免 `{"B;": []}` 被当作空值过滤掉
+    fn inline(&self, len: usize) -> bool {
+        self.encoding == ByteArrayEncoding::Array && len > 0 && len <= self.max_inline_len
+    }
+}
+
+/// NBT -> JSON 转换选项
+#[derive(Debug, Clone, Default)]
+pub struct NbtJsonOptions {
+    /// 短于该字节数的 ByteArray 写为十六进制 `"B;hex:<hex>"`，便于 diff（0 表示始终使用 base64）
+    pub hex_byte_array_threshold: usize,
+    /// ByteArray/IntArray/LongArray 的编码方式
+    pub array_encoding: ArrayEncoding,
+}
+
+impl NbtJsonOptions {
+    /// 从导出配置创建转换选项
+    pub fn from_config(config: &ExportConfig) -> Self {
+        Self {
+            hex_byte_array_threshold: config.hex_byte_array_threshold,
+            array_encoding: ArrayEncoding {
+                encoding: config.array_encoding,
+                ..ArrayEncoding::default()
+            },
+        }
+    }
 }
 
 /// 将 fastnbt Value 转换为紧凑 JSON 格式
 pub fn nbt_to_json(value: &Value) -> JsonValue {
+    nbt_to_json_with_options(value, &NbtJsonOptions::default())
+}
+
+/// 将 fastnbt Value 转换为紧凑 JSON 格式（使用转换选项）
+pub fn nbt_to_json_with_options(value: &Value, options: &NbtJsonOptions) -> JsonValue {
     match value {
         Value::Byte(v) => JsonValue::String(format!("{}b", v)),
         Value::Short(v) => JsonValue::String(format!("{}s", v)),
@@ -228,9 +335,24 @@ pub fn nbt_to_json(value: &Value) -> JsonValue {
                 JsonValue::String(s.clone())
             }
         }
+        Value::ByteArray(arr) if options.array_encoding.inline(arr.len()) => {
+            json!({ "B;": arr.iter().collect::<Vec<_>>() })
+        }
+        Value::IntArray(arr) if options.array_encoding.inline(arr.len()) => {
+            json!({ "I;": arr.iter().collect::<Vec<_>>() })
+        }
+        Value::LongArray(arr) if options.array_encoding.inline(arr.len()) => {
+            json!({ "L;": arr.iter().collect::<Vec<_>>() })
+        }
         Value::ByteArray(arr) => {
             let bytes: Vec<u8> = arr.iter().map(|&b| b as u8).collect();
-            JsonValue::String(format!("B;{}", BASE64.encode(&bytes)))
+            if options.array_encoding.encoding == ByteArrayEncoding::Hex
+                || bytes.len() < options.hex_byte_array_threshold
+            {
+                JsonValue::String(format!("B;hex:{}", encode_hex(&bytes)))
+            } else {
+                JsonValue::String(format!("B;{}", BASE64.encode(&bytes)))
+            }
         }
         Value::IntArray(arr) => {
             let mut bytes = Vec::with_capacity(arr.len() * 4);
@@ -250,13 +372,17 @@ pub fn nbt_to_json(value: &Value) -> JsonValue {
             if list.is_empty() {
                 json!({"[]": "End"})
             } else {
-                JsonValue::Array(list.iter().map(nbt_to_json).collect())
+                JsonValue::Array(
+                    list.iter()
+                        .map(|v| nbt_to_json_with_options(v, options))
+                        .collect(),
+                )
             }
         }
         Value::Compound(map) => {
             let obj: Map<String, JsonValue> = map
                 .iter()
-                .map(|(k, v)| (k.clone(), nbt_to_json(v)))
+                .map(|(k, v)| (k.clone(), nbt_to_json_with_options(v, options)))
                 .collect();
             JsonValue::Object(obj)
         }
@@ -278,8 +404,8 @@ fn is_type_like_string(s: &str) -> bool {
             }
         }
     }
-    // 检查 "B;", "I;", "L;" 前缀（都是 ASCII）
-    if s.len() > 2 && s.as_bytes().get(1) == Some(&b';') {
+    // 检查 "B;", "I;", "L;" 前缀（都是 ASCII，空数组只有前缀）
+    if s.as_bytes().get(1) == Some(&b';') {
         let first = s.as_bytes()[0];
         if matches!(first, b'B' | b'I' | b'L') {
             return true;
@@ -288,26 +414,142 @@ fn is_type_like_string(s: &str) -> bool {
     false
 }
 
+/// 将字节编码为小写十六进制字符串
+fn encode_hex(bytes: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        out.push(HEX[(b >> 4) as usize] as char);
+        out.push(HEX[(b & 0x0f) as usize] as char);
+    }
+    out
+}
+
+/// 解码十六进制字符串
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        anyhow::bail!("十六进制字符串长度必须为偶数: {}", s.len());
+    }
+    fn nibble(c: u8) -> Result<u8> {
+        match c {
+            b'0'..=b'9' => Ok(c - b'0'),
+            b'a'..=b'f' => Ok(c - b'a' + 10),
+            b'A'..=b'F' => Ok(c - b'A' + 10),
+            _ => anyhow::bail!("无效的十六进制字符: {:?}", c as char),
+        }
+    }
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| Ok((nibble(pair[0])? << 4) | nibble(pair[1])?))
+        .collect()
+}
+
 /// 将 JSON 转换回 fastnbt Value
+///
+/// 转换失败时错误信息包含出错值的 JSON 路径（如 `sections[3].block_states.data`）；
+/// 顶层的 `_root_name` 会被移除，需要根标签名时使用 [`json_to_nbt_with_root_name`]
 pub fn json_to_nbt(json: &JsonValue) -> Result<Value> {
+    Ok(json_to_nbt_with_root_name(json)?.0)
+}
+
+/// 将 JSON 转换回 fastnbt Value，同时返回顶层 `_root_name` 记录的根标签名（没有时为空）
+pub fn json_to_nbt_with_root_name(json: &JsonValue) -> Result<(Value, String)> {
+    let mut value = json_to_nbt_at(json, &mut String::new())?;
+    let root_name = take_root_name(&mut value);
+    Ok((value, root_name))
+}
+
+// ============== 根标签名 ==============
+
+/// 根复合标签的名称在 JSON 对象（或 SNBT 复合标签）顶层的键，只在名称非空时写入
+pub const ROOT_NAME_KEY: &str = "_root_name";
+
+/// 读取 NBT 二进制数据中根复合标签的名称，数据不以复合标签开头时返回 `None`
+pub fn nbt_root_name(data: &[u8]) -> Option<String> {
+    // 标签类型（1 字节，复合标签为 10）+ 名称长度（u16 大端）+ 名称
+    if data.first() != Some(&10) {
+        return None;
+    }
+    let len = u16::from_be_bytes([*data.get(1)?, *data.get(2)?]) as usize;
+    let name = data.get(3..3 + len)?;
+    Some(String::from_utf8_lossy(name).into_owned())
+}
+
+/// 根标签名非空时记录到复合标签顶层的 `_root_name` 中（导出时使用）
+pub fn insert_root_name(value: &mut Value, root_name: &str) {
+    if let Value::Compound(map) = value {
+        if !root_name.is_empty() {
+            map.insert(
+                ROOT_NAME_KEY.to_string(),
+                Value::String(root_name.to_string()),
+            );
+        }
+    }
+}
+
+/// 取出并移除复合标签顶层的 `_root_name`，没有时返回空字符串
+pub fn take_root_name(value: &mut Value) -> String {
+    match value {
+        Value::Compound(map) => match map.remove(ROOT_NAME_KEY) {
+            Some(Value::String(name)) => name,
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// 序列化为 NBT 二进制数据，根复合标签使用指定名称
+pub fn nbt_to_bytes(value: &Value, root_name: &str) -> Result<Vec<u8>> {
+    Ok(fastnbt::to_bytes_with_opts(
+        value,
+        fastnbt::SerOpts::new().root_name(root_name),
+    )?)
+}
+
+/// 转换 `path` 处的值，`path` 在返回时恢复原样
+fn json_to_nbt_at(json: &JsonValue, path: &mut String) -> Result<Value> {
     match json {
         JsonValue::Object(obj) => {
             // 检查空列表标记
             if obj.len() == 1 && obj.contains_key("[]") {
                 return Ok(Value::List(vec![]));
             }
+            // 检查 JSON 整数数组形式的 NBT 数组
+            if let Some((marker, JsonValue::Array(items))) = obj.iter().next() {
+                if obj.len() == 1 && ARRAY_MARKERS.contains(&marker.as_str()) {
+                    return if path.is_empty() {
+                        parse_inline_array(marker, items)
+                    } else {
+                        parse_inline_array(marker, items).with_context(|| format!("位于 {}", path))
+                    };
+                }
+            }
             let mut map = HashMap::new();
             for (k, v) in obj {
-                let key = restore_field(k).to_string();
-                map.insert(key, json_to_nbt(v)?);
+                let key = k.to_string();
+                let len = path.len();
+                if len > 0 {
+                    path.push('.');
+                }
+                path.push_str(&key);
+                let value = json_to_nbt_at(v, path)?;
+                path.truncate(len);
+                map.insert(key, value);
             }
             Ok(Value::Compound(map))
         }
         JsonValue::Array(arr) => {
-            let list: Result<Vec<Value>> = arr.iter().map(json_to_nbt).collect();
-            Ok(Value::List(list?))
+            let mut list = Vec::with_capacity(arr.len());
+            for (i, v) in arr.iter().enumerate() {
+                let len = path.len();
+                let _ = write!(path, "[{}]", i);
+                list.push(json_to_nbt_at(v, path)?);
+                path.truncate(len);
+            }
+            Ok(Value::List(list))
         }
-        JsonValue::String(s) => parse_string_value(s),
+        JsonValue::String(s) if path.is_empty() => parse_string_value(s),
+        JsonValue::String(s) => parse_string_value(s).with_context(|| format!("位于 {}", path)),
         JsonValue::Number(n) => {
             if let Some(i) = n.as_i64() {
                 if i >= i32::MIN as i64 && i <= i32::MAX as i64 {
@@ -326,40 +568,100 @@ pub fn json_to_nbt(json: &JsonValue) -> Result<Value> {
     }
 }
 
+/// 解析 JSON 整数数组形式的 NBT 数组（`marker` 为 `B;`、`I;` 或 `L;`）
+fn parse_inline_array(marker: &str, items: &[JsonValue]) -> Result<Value> {
+    let values = items
+        .iter()
+        .map(|item| {
+            item.as_i64()
+                .with_context(|| format!("{} 数组中的元素不是整数: {}", marker, item))
+        })
+        .collect::<Result<Vec<i64>>>()?;
+    Ok(match marker {
+        "B;" => Value::ByteArray(ByteArray::new(
+            values
+                .into_iter()
+                .map(|v| i8::try_from(v).with_context(|| format!("ByteArray 元素 {} 超出范围", v)))
+                .collect::<Result<_>>()?,
+        )),
+        "I;" => Value::IntArray(IntArray::new(
+            values
+                .into_iter()
+                .map(|v| i32::try_from(v).with_context(|| format!("IntArray 元素 {} 超出范围", v)))
+                .collect::<Result<_>>()?,
+        )),
+        _ => Value::LongArray(LongArray::new(values)),
+    })
+}
+
+/// 截断过长的字符串，用于错误信息
+fn truncate_for_error(s: &str) -> String {
+    const MAX_CHARS: usize = 32;
+    if s.chars().count() > MAX_CHARS {
+        format!("{}...", s.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        s.to_string()
+    }
+}
+
 /// 解析字符串值（可能包含类型标记）
 fn parse_string_value(s: &str) -> Result<Value> {
     // 转义字符串（\0 是 2 字节 ASCII）
-    if s.ends_with("\\0") {
-        let unescaped = &s[..s.len() - 2];
+    if let Some(unescaped) = s.strip_suffix("\\0") {
         return Ok(Value::String(unescaped.to_string()));
     }
 
-    // 数组类型（B;, I;, L; 都是 ASCII 前缀）
-    if s.len() > 2 && s.as_bytes().get(1) == Some(&b';') {
+    // 十六进制 ByteArray（`B;hex:` 中的冒号不属于 base64 字符集，不会与 base64 混淆）
+    if let Some(hex) = s.strip_prefix("B;hex:") {
+        let arr: Vec<i8> = decode_hex(hex)?.iter().map(|&b| b as i8).collect();
+        return Ok(Value::ByteArray(ByteArray::new(arr)));
+    }
+
+    // 数组类型（B;, I;, L; 都是 ASCII 前缀，空数组只有前缀）
+    if s.as_bytes().get(1) == Some(&b';') {
         let prefix = s.as_bytes()[0];
-        let b64 = &s[2..];
-        let bytes = BASE64.decode(b64)?;
+        if matches!(prefix, b'B' | b'I' | b'L') {
+            let b64 = &s[2..];
+            let bytes = BASE64
+                .decode(b64)
+                .with_context(|| format!("无效的 base64 数组: {}", truncate_for_error(s)))?;
 
-        match prefix {
-            b'B' => {
-                let arr: Vec<i8> = bytes.iter().map(|&b| b as i8).collect();
-                return Ok(Value::ByteArray(ByteArray::new(arr)));
-            }
-            b'I' => {
-                let arr: Vec<i32> = bytes
-                    .chunks(4)
-                    .map(|c| i32::from_be_bytes([c[0], c[1], c[2], c[3]]))
-                    .collect();
-                return Ok(Value::IntArray(IntArray::new(arr)));
-            }
-            b'L' => {
-                let arr: Vec<i64> = bytes
-                    .chunks(8)
-                    .map(|c| i64::from_be_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]))
-                    .collect();
-                return Ok(Value::LongArray(LongArray::new(arr)));
+            match prefix {
+                b'B' => {
+                    let arr: Vec<i8> = bytes.iter().map(|&b| b as i8).collect();
+                    return Ok(Value::ByteArray(ByteArray::new(arr)));
+                }
+                b'I' => {
+                    if !bytes.len().is_multiple_of(4) {
+                        anyhow::bail!(
+                            "IntArray 字节长度 {} 不是 4 的倍数（数据可能被截断）: {}",
+                            bytes.len(),
+                            truncate_for_error(s)
+                        );
+                    }
+                    let arr: Vec<i32> = bytes
+                        .chunks_exact(4)
+                        .map(|c| i32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                        .collect();
+                    return Ok(Value::IntArray(IntArray::new(arr)));
+                }
+                _ => {
+                    if !bytes.len().is_multiple_of(8) {
+                        anyhow::bail!(
+                            "LongArray 字节长度 {} 不是 8 的倍数（数据可能被截断）: {}",
+                            bytes.len(),
+                            truncate_for_error(s)
+                        );
+                    }
+                    let arr: Vec<i64> = bytes
+                        .chunks_exact(8)
+                        .map(|c| {
+                            i64::from_be_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]])
+                        })
+                        .collect();
+                    return Ok(Value::LongArray(LongArray::new(arr)));
+                }
             }
-            _ => {}
         }
     }
 
@@ -408,3 +710,490 @@ pub fn restore_json_keys(value: &mut JsonValue) {
     static MAPPER: Lazy<FieldMapper> = Lazy::new(FieldMapper::default);
     MAPPER.restore_json_keys(value);
 }
+
+// ============== SNBT ==============
+
+/// 将 fastnbt Value 转换为 SNBT（字符串化 NBT）
+///
+/// 复合标签的键按字典序输出，保证结果稳定
+pub fn nbt_to_snbt(value: &Value) -> String {
+    let mut out = String::new();
+    write_snbt(value, &mut out);
+    out
+}
+
+fn write_snbt(value: &Value, out: &mut String) {
+    match value {
+        Value::Byte(v) => out.push_str(&format!("{}b", v)),
+        Value::Short(v) => out.push_str(&format!("{}s", v)),
+        Value::Int(v) => out.push_str(&v.to_string()),
+        Value::Long(v) => out.push_str(&format!("{}L", v)),
+        Value::Float(v) => out.push_str(&format!("{}f", v)),
+        Value::Double(v) => out.push_str(&format!("{}d", v)),
+        Value::String(s) => write_snbt_string(s, out),
+        Value::ByteArray(arr) => write_snbt_array(out, 'B', arr.iter().map(|v| format!("{}b", v))),
+        Value::IntArray(arr) => write_snbt_array(out, 'I', arr.iter().map(|v| v.to_string())),
+        Value::LongArray(arr) => write_snbt_array(out, 'L', arr.iter().map(|v| format!("{}L", v))),
+        Value::List(list) => {
+            out.push('[');
+            for (i, v) in list.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_snbt(v, out);
+            }
+            out.push(']');
+        }
+        Value::Compound(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                if is_snbt_bare_key(key) {
+                    out.push_str(key);
+                } else {
+                    write_snbt_string(key, out);
+                }
+                out.push(':');
+                write_snbt(&map[key], out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_snbt_array(out: &mut String, prefix: char, items: impl Iterator<Item = String>) {
+    out.push('[');
+    out.push(prefix);
+    out.push(';');
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&item);
+    }
+    out.push(']');
+}
+
+fn write_snbt_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// 键是否可以不加引号输出
+fn is_snbt_bare_key(key: &str) -> bool {
+    !key.is_empty() && key.bytes().all(is_snbt_bare_char)
+}
+
+fn is_snbt_bare_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.' | b'+')
+}
+
+/// 解析 SNBT 字符串为 fastnbt Value
+pub fn snbt_to_nbt(s: &str) -> Result<Value> {
+    let mut parser = SnbtParser { src: s, pos: 0 };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos != s.len() {
+        anyhow::bail!("SNBT 解析错误: 位置 {} 处有多余内容", parser.pos);
+    }
+    Ok(value)
+}
+
+/// 简单的 SNBT 递归下降解析器
+struct SnbtParser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl SnbtParser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => anyhow::bail!(
+                "SNBT 解析错误: 位置 {} 处期望 '{}'，实际为 '{}'",
+                self.pos,
+                expected as char,
+                c as char
+            ),
+            None => anyhow::bail!("SNBT 解析错误: 期望 '{}'，但已到达末尾", expected as char),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.parse_compound(),
+            Some(b'[') => self.parse_list(),
+            Some(b'"') | Some(b'\'') => Ok(Value::String(self.parse_quoted()?)),
+            Some(_) => {
+                let token = self.parse_bare()?;
+                Ok(parse_snbt_scalar(token))
+            }
+            None => anyhow::bail!("SNBT 解析错误: 意外的末尾"),
+        }
+    }
+
+    fn parse_compound(&mut self) -> Result<Value> {
+        self.expect(b'{')?;
+        let mut map = HashMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Compound(map));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some(b'"') | Some(b'\'') => self.parse_quoted()?,
+                _ => self.parse_bare()?.to_string(),
+            };
+            self.expect(b':')?;
+            let value = self.parse_value()?;
+            map.insert(key, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                _ => break,
+            }
+        }
+        self.expect(b'}')?;
+        Ok(Value::Compound(map))
+    }
+
+    fn parse_list(&mut self) -> Result<Value> {
+        self.expect(b'[')?;
+        self.skip_whitespace();
+
+        // 类型数组 [B;...] [I;...] [L;...]
+        let bytes = self.src.as_bytes();
+        if self.pos + 1 < bytes.len()
+            && matches!(bytes[self.pos], b'B' | b'I' | b'L')
+            && bytes[self.pos + 1] == b';'
+        {
+            let kind = bytes[self.pos];
+            self.pos += 2;
+            let items = self.parse_items()?;
+            return match kind {
+                b'B' => Ok(Value::ByteArray(ByteArray::new(
+                    items
+                        .iter()
+                        .map(|v| snbt_array_item(v).map(|n| n as i8))
+                        .collect::<Result<_>>()?,
+                ))),
+                b'I' => Ok(Value::IntArray(IntArray::new(
+                    items
+                        .iter()
+                        .map(|v| snbt_array_item(v).map(|n| n as i32))
+                        .collect::<Result<_>>()?,
+                ))),
+                _ => Ok(Value::LongArray(LongArray::new(
+                    items.iter().map(snbt_array_item).collect::<Result<_>>()?,
+                ))),
+            };
+        }
+
+        Ok(Value::List(self.parse_items()?))
+    }
+
+    /// 解析列表元素直到 `]`
+    fn parse_items(&mut self) -> Result<Vec<Value>> {
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                _ => break,
+            }
+        }
+        self.expect(b']')?;
+        Ok(items)
+    }
+
+    fn parse_quoted(&mut self) -> Result<String> {
+        let quote = self.peek().context("SNBT 解析错误: 意外的末尾")?;
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.src[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    let (_, escaped) = chars
+                        .next()
+                        .context("SNBT 解析错误: 字符串在转义符后结束")?;
+                    out.push(match escaped {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        other => other,
+                    });
+                }
+                c if c as u32 == quote as u32 => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                c => out.push(c),
+            }
+        }
+        anyhow::bail!("SNBT 解析错误: 未闭合的字符串")
+    }
+
+    fn parse_bare(&mut self) -> Result<&str> {
+        let start = self.pos;
+        while self.peek().is_some_and(is_snbt_bare_char) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            anyhow::bail!("SNBT 解析错误: 位置 {} 处期望值", start);
+        }
+        Ok(&self.src[start..self.pos])
+    }
+}
+
+/// 解析不带引号的标量（数值、布尔值或字符串）
+fn parse_snbt_scalar(token: &str) -> Value {
+    match token {
+        "true" => return Value::Byte(1),
+        "false" => return Value::Byte(0),
+        _ => {}
+    }
+
+    let (body, suffix) = token.split_at(token.len() - 1);
+    let parsed = match suffix {
+        "b" | "B" => body.parse().ok().map(Value::Byte),
+        "s" | "S" => body.parse().ok().map(Value::Short),
+        "l" | "L" => body.parse().ok().map(Value::Long),
+        "f" | "F" => body.parse().ok().map(Value::Float),
+        "d" | "D" => body.parse().ok().map(Value::Double),
+        _ => None,
+    };
+    if let Some(value) = parsed {
+        return value;
+    }
+
+    if let Ok(v) = token.parse::<i32>() {
+        return Value::Int(v);
+    }
+    if token.contains(['.', 'e', 'E']) {
+        if let Ok(v) = token.parse::<f64>() {
+            return Value::Double(v);
+        }
+    }
+    Value::String(token.to_string())
+}
+
+/// 提取类型数组中的整数元素
+fn snbt_array_item(value: &Value) -> Result<i64> {
+    match value {
+        Value::Byte(v) => Ok(*v as i64),
+        Value::Short(v) => Ok(*v as i64),
+        Value::Int(v) => Ok(*v as i64),
+        Value::Long(v) => Ok(*v),
+        other => anyhow::bail!("SNBT 解析错误: 类型数组中包含非整数元素 {:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncated_int_array_is_an_error() {
+        // 6 字节不是 4 的倍数
+        let b64 = BASE64.encode([0u8; 6]);
+        let err = parse_string_value(&format!("I;{}", b64)).unwrap_err();
+        assert!(err.to_string().contains("4 的倍数"), "{}", err);
+    }
+
+    #[test]
+    fn truncated_long_array_is_an_error() {
+        let b64 = BASE64.encode([0u8; 12]);
+        let err = parse_string_value(&format!("L;{}", b64)).unwrap_err();
+        assert!(err.to_string().contains("8 的倍数"), "{}", err);
+    }
+
+    #[test]
+    fn invalid_base64_array_is_an_error() {
+        assert!(parse_string_value("I;AAA").is_err());
+    }
+
+    #[test]
+    fn whole_arrays_decode() {
+        let ints = BASE64.encode([0, 0, 0, 1, 0xff, 0xff, 0xff, 0xff]);
+        let Value::IntArray(arr) = parse_string_value(&format!("I;{}", ints)).unwrap() else {
+            panic!("不是 IntArray");
+        };
+        assert_eq!(arr.to_vec(), vec![1, -1]);
+
+        let longs = BASE64.encode([0, 0, 0, 0, 0, 0, 0, 2]);
+        let Value::LongArray(arr) = parse_string_value(&format!("L;{}", longs)).unwrap() else {
+            panic!("不是 LongArray");
+        };
+        assert_eq!(arr.to_vec(), vec![2]);
+    }
+
+    #[test]
+    fn empty_arrays_round_trip_in_every_encoding() {
+        let empty = [
+            Value::ByteArray(ByteArray::new(Vec::new())),
+            Value::IntArray(IntArray::new(Vec::new())),
+            Value::LongArray(LongArray::new(Vec::new())),
+        ];
+        for encoding in [
+            ByteArrayEncoding::Base64,
+            ByteArrayEncoding::Hex,
+            ByteArrayEncoding::Array,
+        ] {
+            let options = NbtJsonOptions {
+                array_encoding: ArrayEncoding {
+                    encoding,
+                    ..ArrayEncoding::default()
+                },
+                ..NbtJsonOptions::default()
+            };
+            for value in &empty {
+                let json = nbt_to_json_with_options(value, &options);
+                assert!(json.is_string(), "{:?}: {}", encoding, json);
+                assert_eq!(&json_to_nbt(&json).unwrap(), value, "{:?}", encoding);
+            }
+        }
+    }
+
+    #[test]
+    fn short_byte_arrays_use_the_single_hex_spelling() {
+        let value = Value::ByteArray(ByteArray::new(vec![0x48, 0x69]));
+        let options = NbtJsonOptions {
+            hex_byte_array_threshold: 16,
+            ..NbtJsonOptions::default()
+        };
+        let json = nbt_to_json_with_options(&value, &options);
+        assert_eq!(json, JsonValue::String("B;hex:4869".to_string()));
+        assert_eq!(json_to_nbt(&json).unwrap(), value);
+        // "Bx;" 不是数组编码，按普通字符串读取
+        assert_eq!(
+            parse_string_value("Bx;4869").unwrap(),
+            Value::String("Bx;4869".to_string())
+        );
+    }
+
+    #[test]
+    fn short_names_are_restored_only_by_the_mapper() {
+        // 数据中本来就叫 "bs" 的字段不会被 json_to_nbt 改名
+        let json = json!({"bs": 1, "Status": "minecraft:full"});
+        let Value::Compound(map) = json_to_nbt(&json).unwrap() else {
+            panic!("不是 Compound");
+        };
+        assert!(map.contains_key("bs"));
+
+        let config = FieldMappingConfig {
+            enabled: true,
+            use_builtins: false,
+            mappings: Vec::new(),
+        };
+        let mut restored = json.clone();
+        FieldMapper::from_config(&config).restore_json_keys(&mut restored);
+        assert_eq!(restored, json);
+
+        let config = FieldMappingConfig {
+            use_builtins: true,
+            ..config
+        };
+        FieldMapper::from_config(&config).restore_json_keys(&mut restored);
+        assert!(restored.get("block_states").is_some());
+        assert!(restored.get("bs").is_none());
+    }
+
+    #[test]
+    fn mapped_keys_restore_to_the_original() {
+        let original = json!({
+            "block_states": {"palette": [{"Name": "minecraft:stone"}]},
+            "entities": [{"custom_name": 1}],
+            "bs": 2,
+        });
+        let mapper = FieldMapper::new(HashMap::from([
+            ("custom_name".to_string(), "cn".to_string()),
+            ("entities".to_string(), "en".to_string()),
+        ]))
+        .unwrap();
+        let mut json = original.clone();
+        mapper.shorten_json_keys(&mut json);
+        assert_eq!(json["en"][0]["cn"], 1);
+        mapper.restore_json_keys(&mut json);
+        assert_eq!(json, original);
+
+        let mut json = json!({"block_states": {"palette": []}, "Status": "full"});
+        let expected = json.clone();
+        let builtins = FieldMapper::with_builtins();
+        builtins.shorten_json_keys(&mut json);
+        assert!(json["bs"].get("pal").is_some());
+        builtins.restore_json_keys(&mut json);
+        assert_eq!(json, expected);
+
+        let identity = FieldMapper::identity();
+        assert_eq!(identity.shorten("block_states"), "block_states");
+        assert_eq!(identity.restore("bs"), "bs");
+    }
+
+    #[test]
+    fn non_bijective_mappings_are_rejected() {
+        let same_alias = HashMap::from([
+            ("block_states".to_string(), "b".to_string()),
+            ("biomes".to_string(), "b".to_string()),
+        ]);
+        let err = FieldMapper::new(same_alias).unwrap_err();
+        assert!(err.to_string().contains("都映射为 b"), "{}", err);
+
+        // 短名是另一个被映射的字段名
+        let chained = HashMap::from([
+            ("block_states".to_string(), "biomes".to_string()),
+            ("biomes".to_string(), "bio".to_string()),
+        ]);
+        assert!(FieldMapper::new(chained).is_err());
+
+        // 与内置别名冲突
+        let config = FieldMappingConfig {
+            enabled: true,
+            use_builtins: true,
+            mappings: vec![("entities".to_string(), "bs".to_string())],
+        };
+        assert!(config.check_aliases().is_err());
+        let config = FieldMappingConfig {
+            use_builtins: false,
+            ..config
+        };
+        assert!(config.check_aliases().is_ok());
+    }
+}
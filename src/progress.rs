@@ -0,0 +1,40 @@
+//! 进度通知 - 由调用方安装进度处理器（如命令行进度条）
+//!
+//! 与 `log` 门面类似，库只负责发出进度事件，未安装处理器时不做任何事
+
+use once_cell::sync::OnceCell;
+
+/// 进度处理器
+pub trait ProgressHandler: Send + Sync {
+    /// 开始一个阶段，`total` 为需要处理的 region 数量
+    fn start(&self, label: &str, total: usize);
+    /// 完成一个 region（无论成功或失败）
+    fn inc(&self);
+    /// 当前阶段结束
+    fn finish(&self);
+}
+
+static HANDLER: OnceCell<Box<dyn ProgressHandler>> = OnceCell::new();
+
+/// 安装全局进度处理器，只能安装一次，重复安装时返回 false
+pub fn set_progress_handler(handler: Box<dyn ProgressHandler>) -> bool {
+    HANDLER.set(handler).is_ok()
+}
+
+pub(crate) fn start(label: &str, total: usize) {
+    if let Some(handler) = HANDLER.get() {
+        handler.start(label, total);
+    }
+}
+
+pub(crate) fn inc() {
+    if let Some(handler) = HANDLER.get() {
+        handler.inc();
+    }
+}
+
+pub(crate) fn finish() {
+    if let Some(handler) = HANDLER.get() {
+        handler.finish();
+    }
+}
@@ -1,176 +1,1270 @@
 //! 从 JSON 还原世界
 
-use crate::config::Config;
-use crate::denoise::restore_defaults;
-use crate::mca::{write_mca, ChunkData};
-use crate::nbt_json::{json_to_nbt, restore_json_keys, FieldMapper};
+use crate::block_states::{compute_heightmaps, pack_biomes, pack_block_states, pack_heightmaps};
+use crate::config::{
+    CompressionConfig, Config, DenoiseMode, DuplicateChunkPolicy, ExistingOutputPolicy,
+    RestoreConfig, SliceFormat,
+};
+use crate::denoise::{
+    chunk_body, default_restore_values, restore_defaults_with_values, whitelist_restore_values,
+    LEGACY_LEVEL_KEY,
+};
+use crate::export::{build_thread_pool, dimensions, ChunkLayer, RegionKind, BLOCK_ENTITIES_DIR};
+use crate::manifest::ExportManifest;
+use crate::mca::{gzip_compress, read_mca, write_mca_with_compression, ChunkData};
+use crate::nbt_json::{
+    json_to_nbt, json_to_nbt_with_root_name, nbt_to_bytes, restore_json_keys, snbt_to_nbt,
+    take_root_name, FieldMapper,
+};
+use crate::progress;
+use crate::util::{unique_temp_dir, write_atomically_synced};
 use anyhow::{Context, Result};
+use fastnbt::Value;
+use log::{debug, error, info, warn};
 use rayon::prelude::*;
 use regex::Regex;
-use serde_json::Value as JsonValue;
-use std::fs::{self, File};
-use std::io::Write;
-use std::path::Path;
-use std::sync::Arc;
-
-/// 维度定义
-const DIMENSIONS: &[(&str, &str)] = &[
-    ("", "主世界"),    // 主世界 region/
-    ("DIM-1", "地狱"), // 地狱 DIM-1/region/
-    ("DIM1", "末地"),  // 末地 DIM1/region/
-];
+use serde::de::{DeserializeSeed, Error as _, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Serialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 方块实体文件的后缀
+const ENTITIES_SUFFIX: &str = "entities";
+
+/// 无损去噪记录被移除的值的文件的后缀
+const NOISE_SUFFIX: &str = "noise";
+
+/// 还原结果汇总（可序列化为 JSON 供自动化使用）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestoreReport {
+    /// 处理的 region 数量
+    pub regions_total: usize,
+    /// 成功还原的 region 数量
+    pub regions_restored: usize,
+    /// 还原失败的 region（按路径排序）
+    pub failures: Vec<RegionFailure>,
+    /// 写入 region 文件的区块数量（可与导出清单中的区块数量对照）
+    pub chunks_written: usize,
+    /// 跳过的区块数量（尽力还原时无法还原的区块，以及位置与所在切片不符的区块）
+    pub chunks_skipped: usize,
+    /// level.dat 的还原情况
+    pub level_dat: LevelDatStatus,
+    /// 恢复默认值时补全的字段及补全的区块数量
+    pub defaults_injected: BTreeMap<String, usize>,
+    /// 跳过的文件和区块的错误信息
+    pub errors: Vec<String>,
+}
+
+impl RestoreReport {
+    /// 写入 region 文件的区块数量（旧名称）
+    #[deprecated(note = "使用 `chunks_written` 字段")]
+    pub fn restored_chunks(&self) -> usize {
+        self.chunks_written
+    }
+
+    /// 跳过的区块数量（旧名称）
+    #[deprecated(note = "使用 `chunks_skipped` 字段")]
+    pub fn skipped_chunks(&self) -> usize {
+        self.chunks_skipped
+    }
+}
+
+/// level.dat 的还原情况
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LevelDatStatus {
+    /// 导出目录中没有 level 文件
+    #[default]
+    Missing,
+    /// 已还原
+    Restored,
+    /// 合并还原时保留了目标世界已有的 level.dat
+    Kept,
+    /// 尽力还原时因出错而跳过
+    Skipped,
+}
+
+/// 还原失败的 region
+#[derive(Debug, Clone, Serialize)]
+pub struct RegionFailure {
+    /// 目标 MCA 文件路径
+    pub path: PathBuf,
+    /// 失败原因
+    pub error: String,
+}
+
+/// 校验结果汇总
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    /// 检查的区块文件数量
+    pub files_checked: usize,
+    /// 通过检查的区块数量
+    pub chunks_checked: usize,
+    /// 发现的问题（按文件和区块坐标排序）
+    pub problems: Vec<ValidationProblem>,
+}
+
+impl ValidationReport {
+    /// 是否没有发现问题
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// 校验发现的问题
+#[derive(Debug, Clone)]
+pub struct ValidationProblem {
+    /// 出现问题的文件
+    pub file: PathBuf,
+    /// 区块坐标（文件中记录的 `x`/`z`），无法确定区块时为 `None`
+    pub chunk: Option<(i32, i32)>,
+    /// 问题描述
+    pub message: String,
+}
+
+/// 记录单个 region 的还原结果并输出进度
+///
+/// `fail_fast` 为 true 时直接返回失败的错误，用于中止整个还原
+fn record_region(
+    report: &Mutex<RestoreReport>,
+    mca_path: PathBuf,
+    result: Result<RestoreReport>,
+    fail_fast: bool,
+) -> Result<()> {
+    progress::inc();
+    let name = mca_path.file_name().unwrap().to_string_lossy().into_owned();
+    let mut report_guard = report.lock().unwrap();
+    report_guard.regions_total += 1;
+    match result {
+        Ok(region) => {
+            debug!("  完成 {}", name);
+            report_guard.regions_restored += 1;
+            report_guard.chunks_written += region.chunks_written;
+            report_guard.chunks_skipped += region.chunks_skipped;
+            for (field, count) in region.defaults_injected {
+                *report_guard.defaults_injected.entry(field).or_insert(0) += count;
+            }
+            report_guard.errors.extend(region.errors);
+        }
+        Err(e) => {
+            error!("  失败 {}: {:#}", name, e);
+            if fail_fast {
+                return Err(e.context(format!("还原 {} 失败", mca_path.display())));
+            }
+            report_guard.failures.push(RegionFailure {
+                path: mca_path,
+                error: format!("{:#}", e),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 取出汇总结果，并按路径排序失败列表
+fn finish_report(report: Mutex<RestoreReport>) -> RestoreReport {
+    let mut report = report.into_inner().unwrap();
+    report.failures.sort_by(|a, b| a.path.cmp(&b.path));
+    report.errors.sort();
+    report
+}
+
+/// 尽力还原时记录无法读取的文件或无法还原的内容并继续，否则返回错误
+fn skip_error(result: Result<()>, best_effort: bool, errors: &mut Vec<String>) -> Result<()> {
+    match result {
+        Err(e) if best_effort => {
+            errors.push(format!("{:#}", e));
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// 收集维度 region 目录中的区块文件，按 (rx, rz) 分组
+///
+/// 自动识别两种布局：切片文件 `r.{rx}.{rz}.{id}.{ext}`
+/// 和每区块一个文件的 `r.{rx}.{rz}/c.{cx}.{cz}.{ext}`，组内文件按路径排序；
+/// `be/` 中拆分出的方块实体切片（布局与 region 目录相同；旧版本导出为 `r.{rx}.{rz}.entities.{ext}`，
+/// 也可能位于 region 目录本身）、无损去噪的 `r.{rx}.{rz}.noise.{ext}` 和分层切片也归入对应分组
+pub(crate) fn collect_region_files(
+    region_json_path: &Path,
+    format: SliceFormat,
+) -> Result<BTreeMap<(i32, i32), Vec<PathBuf>>> {
+    let ext = format.extension();
+    // 分层导出的图层后缀（可选），如 `.blocks`
+    let layers = ChunkLayer::ALL.map(ChunkLayer::name).join("|");
+    let slice_re = Regex::new(&format!(
+        r"^r\.(-?\d+)\.(-?\d+)\.(\d+)(\.({}))?\.{}$",
+        layers, ext
+    ))?;
+    let dir_re = Regex::new(r"^r\.(-?\d+)\.(-?\d+)$")?;
+    let chunk_re = Regex::new(&format!(r"^c\.(-?\d+)\.(-?\d+)(\.({}))?\.{}$", layers, ext))?;
+    let entities_re = Regex::new(&format!(
+        r"^r\.(-?\d+)\.(-?\d+)\.({}|{})\.{}$",
+        ENTITIES_SUFFIX, NOISE_SUFFIX, ext
+    ))?;
+
+    let mut region_files: BTreeMap<(i32, i32), Vec<PathBuf>> = BTreeMap::new();
+    for entry in fs::read_dir(region_json_path)? {
+        let path = entry?.path();
+        let Some(filename) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+
+        if path.is_file() {
+            if let Some(caps) = slice_re
+                .captures(filename)
+                .or_else(|| entities_re.captures(filename))
+            {
+                let rx: i32 = caps[1].parse()?;
+                let rz: i32 = caps[2].parse()?;
+                region_files.entry((rx, rz)).or_default().push(path);
+            }
+        } else if filename == BLOCK_ENTITIES_DIR {
+            // `be/` 与 region 目录的布局相同
+            for (region, files) in collect_region_files(&path, format)? {
+                region_files.entry(region).or_default().extend(files);
+            }
+        } else if let Some(caps) = dir_re.captures(filename) {
+            let rx: i32 = caps[1].parse()?;
+            let rz: i32 = caps[2].parse()?;
+            for chunk_entry in fs::read_dir(&path)? {
+                let chunk_path = chunk_entry?.path();
+                let is_chunk_file = chunk_path.is_file()
+                    && chunk_path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| chunk_re.is_match(n));
+                if is_chunk_file {
+                    region_files.entry((rx, rz)).or_default().push(chunk_path);
+                }
+            }
+        }
+    }
+
+    for files in region_files.values_mut() {
+        files.sort();
+    }
+    Ok(region_files)
+}
+
+/// 是否为 `be/` 目录中拆分出的方块实体文件（可以不提交到 Git，清单不要求存在）
+///
+/// 每区块一个文件的布局下位于 `be/r.{rx}.{rz}/` 中
+pub(crate) fn is_block_entities_dir_file(path: &Path) -> bool {
+    let is_be_dir = |dir: Option<&Path>| {
+        dir.and_then(Path::file_name)
+            .is_some_and(|name| name == BLOCK_ENTITIES_DIR)
+    };
+    let parent = path.parent();
+    let in_chunk_dir = parent
+        .and_then(Path::file_name)
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("r."));
+    is_be_dir(parent) || (in_chunk_dir && is_be_dir(parent.and_then(Path::parent)))
+}
+
+/// 是否为拆分出的文件：`be/` 中的方块实体文件、旧版本的方块实体文件 `r.{rx}.{rz}.entities.{ext}`、
+/// 无损去噪的 `r.{rx}.{rz}.noise.{ext}` 或分层切片 `r.{rx}.{rz}.{N}.{layer}.{ext}` / `c.{cx}.{cz}.{layer}.{ext}`
+fn is_layer_file(path: &Path) -> bool {
+    is_block_entities_dir_file(path)
+        || file_suffix(path).is_some_and(|suffix| {
+            suffix == ENTITIES_SUFFIX
+                || suffix == NOISE_SUFFIX
+                || ChunkLayer::ALL.iter().any(|layer| layer.name() == suffix)
+        })
+}
+
+/// 文件名中扩展名之前的后缀，如 `r.0.0.noise.json` 的 `noise`
+fn file_suffix(path: &Path) -> Option<&str> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.rsplit_once('.'))
+        .map(|(_, suffix)| suffix)
+}
+
+/// 是否为无损去噪的 noise 文件（其中的区块按记录的原值还原，不再恢复默认值）
+fn is_noise_file(path: &Path) -> bool {
+    file_suffix(path) == Some(NOISE_SUFFIX)
+}
+
+/// 逐个解析 JSON 切片文件 `chunks` 数组中的区块，交给 `f` 处理（参数为序号和区块）
+///
+/// 不构建整个切片的 JSON 树，每个区块处理完即可释放，降低大切片并行还原时的内存峰值；
+/// `f` 返回错误时立即停止解析并返回该错误
+fn for_each_slice_chunk(
+    path: &Path,
+    mut f: impl FnMut(usize, JsonValue) -> Result<()>,
+) -> Result<()> {
+    let content = fs::read(path).with_context(|| format!("读取 {} 失败", path.display()))?;
+    let mut failure = None;
+    let mut deserializer = serde_json::Deserializer::from_slice(&content);
+    let visitor = SliceVisitor {
+        f: &mut f,
+        failure: &mut failure,
+    };
+    let parsed = serde::Deserializer::deserialize_map(&mut deserializer, visitor)
+        .and_then(|found| deserializer.end().map(|()| found));
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    let found = parsed.with_context(|| format!("解析 {} 失败", path.display()))?;
+    if !found {
+        anyhow::bail!("{} 缺少 chunks 数组", path.display());
+    }
+    Ok(())
+}
+
+/// 切片文件顶层对象的访问器，只解析 `chunks` 数组，返回是否存在该数组
+struct SliceVisitor<'a, F> {
+    f: &'a mut F,
+    failure: &'a mut Option<anyhow::Error>,
+}
+
+impl<'de, F: FnMut(usize, JsonValue) -> Result<()>> Visitor<'de> for SliceVisitor<'_, F> {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("包含 chunks 数组的切片对象")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<bool, A::Error> {
+        let mut found = false;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "chunks" {
+                map.next_value_seed(ChunkSeq {
+                    f: &mut *self.f,
+                    failure: &mut *self.failure,
+                })?;
+                found = true;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// `chunks` 数组的访问器，每解析出一个区块就交给回调处理
+struct ChunkSeq<'a, F> {
+    f: &'a mut F,
+    failure: &'a mut Option<anyhow::Error>,
+}
+
+impl<'de, F: FnMut(usize, JsonValue) -> Result<()>> DeserializeSeed<'de> for ChunkSeq<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F: FnMut(usize, JsonValue) -> Result<()>> Visitor<'de> for ChunkSeq<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("区块数组")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        let mut index = 0;
+        while let Some(chunk) = seq.next_element::<JsonValue>()? {
+            if let Err(e) = (self.f)(index, chunk) {
+                *self.failure = Some(e);
+                return Err(A::Error::custom("已中止"));
+            }
+            index += 1;
+        }
+        Ok(())
+    }
+}
+
+/// 读取拆分出的区块片段（方块实体或分层切片），按区块坐标合并
+///
+/// 同一区块的多个片段合并为一个对象，不含 `x`/`z`；noise 文件中出现的区块坐标加入 `noise_chunks`
+///
+/// 尽力还原时跳过无法读取的文件（已读取的片段仍然保留），错误信息加入 `file_errors`
+fn load_chunk_layers(
+    files: &[&PathBuf],
+    restore_keys: impl Fn(&mut JsonValue),
+    options: ChunkReadOptions,
+    noise_chunks: &mut BTreeSet<(i32, i32)>,
+    file_errors: &mut Vec<String>,
+) -> Result<BTreeMap<(i32, i32), JsonMap<String, JsonValue>>> {
+    let mut layers: BTreeMap<_, JsonMap<String, JsonValue>> = BTreeMap::new();
+    for file_path in files {
+        debug!("  读取 {}", file_path.display());
+        let noise = is_noise_file(file_path);
+        let result = for_each_slice_chunk(file_path, |_, mut entry| {
+            restore_keys(&mut entry);
+            let JsonValue::Object(mut fragment) = entry else {
+                anyhow::bail!("{} 中的区块片段不是对象", file_path.display());
+            };
+            let cx = fragment
+                .remove("x")
+                .and_then(|v| v.as_i64())
+                .with_context(|| format!("{} 中的区块片段缺少 x 坐标", file_path.display()))?;
+            let cz = fragment
+                .remove("z")
+                .and_then(|v| v.as_i64())
+                .with_context(|| format!("{} 中的区块片段缺少 z 坐标", file_path.display()))?;
+            let coords = (cx as i32, cz as i32);
+            if noise {
+                noise_chunks.insert(coords);
+            }
+            merge_json_layer(layers.entry(coords).or_default(), fragment);
+            Ok(())
+        });
+        skip_error(result, options.best_effort, file_errors)?;
+    }
+    Ok(layers)
+}
+
+/// 将片段合并到区块中，已有的字段按 [`merge_json_value`] 合并，其余字段直接写入
+fn merge_json_layer(chunk: &mut JsonMap<String, JsonValue>, fragment: JsonMap<String, JsonValue>) {
+    for (key, value) in fragment {
+        match chunk.get_mut(&key) {
+            Some(existing) => merge_json_value(existing, value),
+            None => {
+                chunk.insert(key, value);
+            }
+        }
+    }
+}
+
+/// 将片段中的值合并到已有的值中
+///
+/// 对象逐个字段合并；元素都带有 `Y` 的数组（sections）按 `Y` 合并，没有对应元素时按 `Y` 的顺序插入；
+/// 其余数组和值直接替换（noise 文件中记录的是整个原数组）
+fn merge_json_value(target: &mut JsonValue, value: JsonValue) {
+    match (target, value) {
+        (JsonValue::Object(target), JsonValue::Object(fragment)) => {
+            merge_json_layer(target, fragment)
+        }
+        (JsonValue::Array(existing), JsonValue::Array(elements))
+            if elements.iter().all(|e| e.get("Y").is_some()) =>
+        {
+            let y = |v: &JsonValue| {
+                v.get("Y")
+                    .and_then(|y| json_to_nbt(y).ok())
+                    .and_then(nbt_int)
+            };
+            for element in elements {
+                match existing.iter_mut().find(|s| s.get("Y") == element.get("Y")) {
+                    Some(target) => merge_json_value(target, element),
+                    None => {
+                        let at = existing
+                            .iter()
+                            .position(|s| y(s) > y(&element))
+                            .unwrap_or(existing.len());
+                        existing.insert(at, element);
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+/// 整数类型标签的值（用于比较 section 的 `Y`）
+fn nbt_int(value: Value) -> Option<i64> {
+    match value {
+        Value::Byte(v) => Some(v.into()),
+        Value::Short(v) => Some(v.into()),
+        Value::Int(v) => Some(v.into()),
+        Value::Long(v) => Some(v),
+        _ => None,
+    }
+}
+
+/// 读取 SNBT 格式的区块片段（每行一个），按区块坐标合并
+///
+/// 尽力还原时跳过无法读取的文件和无法解析的行，错误信息加入 `file_errors`；
+/// noise 文件中出现的区块坐标加入 `noise_chunks`
+fn load_chunk_layers_snbt(
+    files: &[&PathBuf],
+    options: ChunkReadOptions,
+    noise_chunks: &mut BTreeSet<(i32, i32)>,
+    file_errors: &mut Vec<String>,
+) -> Result<BTreeMap<(i32, i32), HashMap<String, Value>>> {
+    let mut layers: BTreeMap<_, HashMap<String, Value>> = BTreeMap::new();
+    for file_path in files {
+        debug!("  读取 {}", file_path.display());
+        let noise = is_noise_file(file_path);
+        let content = match fs::read_to_string(file_path)
+            .with_context(|| format!("读取 {} 失败", file_path.display()))
+        {
+            Ok(content) => content,
+            Err(e) => {
+                skip_error(Err(e), options.best_effort, file_errors)?;
+                continue;
+            }
+        };
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let location = || format!("{} 第 {} 行", file_path.display(), i + 1);
+            let result = (|| {
+                let Value::Compound(mut fragment) =
+                    snbt_to_nbt(line).with_context(|| format!("解析 {} 失败", location()))?
+                else {
+                    anyhow::bail!("{}: 区块片段不是复合标签", location());
+                };
+                let (Some(Value::Int(cx)), Some(Value::Int(cz))) =
+                    (fragment.remove("x"), fragment.remove("z"))
+                else {
+                    anyhow::bail!("{}: 区块片段缺少坐标", location());
+                };
+                if noise {
+                    noise_chunks.insert((cx, cz));
+                }
+                merge_nbt_layer(layers.entry((cx, cz)).or_default(), fragment);
+                Ok(())
+            })();
+            skip_error(result, options.best_effort, file_errors)?;
+        }
+    }
+    Ok(layers)
+}
+
+/// 将 NBT 片段合并到区块中，规则与 [`merge_json_layer`] 相同
+fn merge_nbt_layer(chunk: &mut HashMap<String, Value>, fragment: HashMap<String, Value>) {
+    for (key, value) in fragment {
+        match chunk.get_mut(&key) {
+            Some(existing) => merge_nbt_value(existing, value),
+            None => {
+                chunk.insert(key, value);
+            }
+        }
+    }
+}
+
+/// 将片段中的 NBT 值合并到已有的值中，规则与 [`merge_json_value`] 相同
+fn merge_nbt_value(target: &mut Value, value: Value) {
+    let y = |v: &Value| match v {
+        Value::Compound(map) => map.get("Y").cloned(),
+        _ => None,
+    };
+    match (target, value) {
+        (Value::Compound(target), Value::Compound(fragment)) => merge_nbt_layer(target, fragment),
+        (Value::List(existing), Value::List(elements))
+            if elements.iter().all(|e| y(e).is_some()) =>
+        {
+            for element in elements {
+                match existing.iter_mut().find(|s| y(s) == y(&element)) {
+                    Some(target) => merge_nbt_value(target, element),
+                    None => {
+                        let order = |v: &Value| y(v).and_then(nbt_int);
+                        let at = existing
+                            .iter()
+                            .position(|s| order(s) > order(&element))
+                            .unwrap_or(existing.len());
+                        existing.insert(at, element);
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+/// 补全只存在于拆分文件中的区块（主切片中没有该区块）所缺少的基本字段
+fn fill_layer_only_chunk(
+    chunk: &mut HashMap<String, Value>,
+    (rx, rz): (i32, i32),
+    cx: i32,
+    cz: i32,
+) {
+    chunk
+        .entry("xPos".to_string())
+        .or_insert(Value::Int(rx * 32 + cx));
+    chunk
+        .entry("zPos".to_string())
+        .or_insert(Value::Int(rz * 32 + cz));
+    chunk
+        .entry("Status".to_string())
+        .or_insert_with(|| Value::String("minecraft:full".to_string()));
+}
 
 /// 还原整个世界
+///
+/// 输出目录已存在时报错（与默认的 `restore.existing_output` 相同），需要覆盖或合并时使用
+/// [`restore_world_with_config`]
 pub fn restore_world(
     json_path: &Path,
     output_path: &Path,
     restore_default_values: bool,
-) -> Result<()> {
+) -> Result<RestoreReport> {
+    prepare_output_dir(output_path, ExistingOutputPolicy::default())?;
     fs::create_dir_all(output_path)?;
+    let report = Mutex::new(RestoreReport::default());
 
     // 还原 level.dat
     let level_json = json_path.join("level.json");
     if level_json.exists() {
-        println!("还原 level.dat");
+        info!("还原 level.dat");
         restore_level_dat(&level_json, &output_path.join("level.dat"))?;
     }
 
-    // 还原所有维度
-    for (dim_folder, dim_name) in DIMENSIONS {
-        let (region_json_path, region_output) = if dim_folder.is_empty() {
-            (json_path.join("region"), output_path.join("region"))
-        } else {
-            (
-                json_path.join(dim_folder).join("region"),
-                output_path.join(dim_folder).join("region"),
-            )
-        };
+    // 还原所有维度的 region/、entities/ 和 poi/
+    let folders = dimensions(json_path)
+        .into_iter()
+        .flat_map(|dim| RegionKind::ALL.map(|kind| (dim.clone(), kind)));
+    for ((dim_folder, dim_name), kind) in folders {
+        let region_json_path = json_path.join(&dim_folder).join(kind.folder());
+        let region_output = output_path.join(&dim_folder).join(kind.folder());
 
         if !region_json_path.exists() {
             continue;
         }
 
         // 收集所有 region JSON 文件，按 (rx, rz) 分组
-        // 支持切片格式: r.{rx}.{rz}.{id}.json
-        let region_re = Regex::new(r"r\.(-?\d+)\.(-?\d+)\.(\d+)\.json")?;
-        let mut region_files: std::collections::HashMap<(i32, i32), Vec<std::path::PathBuf>> =
-            std::collections::HashMap::new();
-
-        for entry in fs::read_dir(&region_json_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if !path.is_file() {
-                continue;
-            }
-            let filename = path.file_name().unwrap().to_str().unwrap();
-            if let Some(caps) = region_re.captures(filename) {
-                let rx: i32 = caps.get(1).unwrap().as_str().parse()?;
-                let rz: i32 = caps.get(2).unwrap().as_str().parse()?;
-                region_files.entry((rx, rz)).or_default().push(path);
-            }
-        }
+        let region_files = collect_region_files(&region_json_path, SliceFormat::Json)?;
 
         if region_files.is_empty() {
             continue;
         }
 
         fs::create_dir_all(&region_output)?;
-        println!("还原 {} ({} 个 region)", dim_name, region_files.len());
+        info!(
+            "还原 {} {}/ ({} 个 region)",
+            dim_name,
+            kind.folder(),
+            region_files.len()
+        );
+        progress::start(&dim_name, region_files.len());
 
+        // 默认值只属于方块区块
+        let restore_default_values = restore_default_values && kind == RegionKind::Chunks;
         let region_list: Vec<_> = region_files.into_iter().collect();
-        region_list.par_iter().for_each(|((rx, rz), files)| {
-            if let Err(e) =
-                restore_region_slices(*rx, *rz, files, &region_output, restore_default_values)
-            {
-                eprintln!("  失败 r.{}.{}: {}", rx, rz, e);
-            } else {
-                println!("  完成 r.{}.{}", rx, rz);
-            }
+        let result = region_list.par_iter().try_for_each(|((rx, rz), files)| {
+            let result = restore_region_slices(
+                *rx,
+                *rz,
+                files,
+                &region_output,
+                restore_default_values,
+                false,
+            );
+            let mca_path = region_output.join(format!("r.{}.{}.mca", rx, rz));
+            record_region(&report, mca_path, result, false)
         });
+        progress::finish();
+        result?;
     }
 
-    println!("还原完成");
-    Ok(())
+    info!("还原完成");
+    Ok(finish_report(report))
 }
 
 /// 还原整个世界（使用配置）
+///
+/// 在 `[performance] threads` 指定大小的线程池中并行还原 region；
+/// 输出目录已存在时按 `restore.existing_output` 报错、清理或合并
 pub fn restore_world_with_config(
     json_path: &Path,
     output_path: &Path,
     restore_default_values: bool,
     config: &Config,
-) -> Result<()> {
+) -> Result<RestoreReport> {
+    let pool = build_thread_pool(config.performance.threads)?;
+    pool.install(|| restore_world_in_pool(json_path, output_path, restore_default_values, config))
+}
+
+/// 从内存中的导出文件还原世界（使用配置）
+///
+/// `files` 的键为以 `/` 分隔的相对路径，与 [`export_world_to_memory`](crate::export::export_world_to_memory)
+/// 的返回值相同。文件先写入临时目录再按 [`restore_world_with_config`] 还原，临时目录在还原结束后删除
+pub fn restore_world_from_memory(
+    files: &HashMap<String, String>,
+    output_path: &Path,
+    restore_default_values: bool,
+    config: &Config,
+) -> Result<RestoreReport> {
+    let temp_dir = unique_temp_dir("mcj_restore");
+    let result = write_memory_files(files, &temp_dir).and_then(|()| {
+        restore_world_with_config(&temp_dir, output_path, restore_default_values, config)
+    });
+    let _ = fs::remove_dir_all(&temp_dir);
+    result
+}
+
+/// 将内存中的导出文件写入目录，拒绝绝对路径和包含 `..` 的路径
+fn write_memory_files(files: &HashMap<String, String>, dir: &Path) -> Result<()> {
+    for (key, content) in files {
+        let relative = Path::new(key);
+        if !relative
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+        {
+            anyhow::bail!("无效的导出文件路径: {}", key);
+        }
+        let path = dir.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content).with_context(|| format!("写入 {} 失败", path.display()))?;
+    }
+    Ok(())
+}
+
+/// 将导出目录中的区块合并还原到已有世界（使用配置）
+///
+/// 每个 region 读取目标目录中已有的 `.mca` 文件，按坐标用导出的区块覆盖，
+/// 导出中没有的区块保持不变；目标世界已有 level.dat 时不覆盖。
+/// 适合将区块范围导出（`bounds`）的结果写回原世界
+pub fn restore_world_merge(
+    json_path: &Path,
+    output_path: &Path,
+    restore_default_values: bool,
+    config: &Config,
+) -> Result<RestoreReport> {
+    let mut config = config.clone();
+    config.restore.existing_output = ExistingOutputPolicy::Merge;
+    restore_world_with_config(json_path, output_path, restore_default_values, &config)
+}
+
+/// 按 `policy` 处理已存在的输出目录
+///
+/// `Clean` 只删除各维度的 `region/`、`entities/`、`poi/` 和 `level.dat`，保留 `.git`、`playerdata/` 等其它文件
+fn prepare_output_dir(output_path: &Path, policy: ExistingOutputPolicy) -> Result<()> {
+    if !output_path.exists() {
+        return Ok(());
+    }
+    match policy {
+        ExistingOutputPolicy::Fail => {
+            anyhow::bail!(
+                "输出目录已存在: {}\n使用 --overwrite 覆盖或 --merge 合并到已有世界",
+                output_path.display()
+            )
+        }
+        ExistingOutputPolicy::Clean => {
+            let level_dat = output_path.join("level.dat");
+            if level_dat.exists() {
+                fs::remove_file(&level_dat)
+                    .with_context(|| format!("删除 {} 失败", level_dat.display()))?;
+            }
+            for (dim_folder, _) in &dimensions(output_path) {
+                for kind in RegionKind::ALL {
+                    let region_dir = output_path.join(dim_folder).join(kind.folder());
+                    if region_dir.exists() {
+                        fs::remove_dir_all(&region_dir)
+                            .with_context(|| format!("删除 {} 失败", region_dir.display()))?;
+                    }
+                }
+            }
+            Ok(())
+        }
+        ExistingOutputPolicy::Merge => Ok(()),
+    }
+}
+
+fn restore_world_in_pool(
+    json_path: &Path,
+    output_path: &Path,
+    restore_default_values: bool,
+    config: &Config,
+) -> Result<RestoreReport> {
+    config.restore.compression.check()?;
+    prepare_output_dir(output_path, config.restore.existing_output)?;
     fs::create_dir_all(output_path)?;
 
+    let mut report = RestoreReport::default();
     let field_mapper = Arc::new(FieldMapper::from_config(&config.field_mapping));
+    let format = config.restore.format;
+    let compression = config.restore.compression;
+    let best_effort = config.restore.best_effort;
+    let default_values = if restore_default_values {
+        config.restore.default_values()?
+    } else {
+        Vec::new()
+    };
 
-    // 还原 level.dat
-    let level_json = json_path.join("level.json");
-    if level_json.exists() {
-        println!("还原 level.dat");
-        restore_level_dat_with_config(&level_json, &output_path.join("level.dat"), &field_mapper)?;
+    // 还原 level.dat（合并时保留目标世界已有的 level.dat）
+    let level_file = json_path.join(format!("level.{}", format.extension()));
+    let merge = config.restore.existing_output == ExistingOutputPolicy::Merge;
+    let keep_level = merge && output_path.join("level.dat").exists();
+    if keep_level {
+        report.level_dat = LevelDatStatus::Kept;
+    } else if level_file.exists() {
+        info!("还原 level.dat");
+        let result = match format {
+            SliceFormat::Json => restore_level_dat_with_config(
+                &level_file,
+                &output_path.join("level.dat"),
+                &field_mapper,
+            ),
+            SliceFormat::Snbt => {
+                restore_level_dat_snbt(&level_file, &output_path.join("level.dat"))
+            }
+        };
+        let result = result.with_context(|| format!("还原 {} 失败", level_file.display()));
+        report.level_dat = match result {
+            Ok(()) => LevelDatStatus::Restored,
+            Err(e) => {
+                skip_error(Err(e), best_effort, &mut report.errors)?;
+                LevelDatStatus::Skipped
+            }
+        };
+    }
+
+    // 还原 data/ 目录（地图、记分板、袭击等）
+    for (dim_folder, _) in &dimensions(json_path) {
+        let data_dir = json_path.join(dim_folder).join("data");
+        if data_dir.is_dir() {
+            restore_data_dir(
+                &data_dir,
+                &output_path.join(dim_folder).join("data"),
+                format,
+                &field_mapper,
+                best_effort,
+                &mut report.errors,
+            )?;
+        }
+    }
+
+    // 还原 playerdata/
+    let playerdata_dir = json_path.join("playerdata");
+    if playerdata_dir.is_dir() {
+        restore_data_dir(
+            &playerdata_dir,
+            &output_path.join("playerdata"),
+            format,
+            &field_mapper,
+            best_effort,
+            &mut report.errors,
+        )?;
+    }
+
+    // 导出清单中记录的切片文件必须齐全（尽力还原时只记录缺少的文件和无法读取的清单）
+    let manifest = match ExportManifest::load(json_path) {
+        Ok(manifest) => manifest,
+        Err(e) if best_effort => {
+            warn!("{:#}，跳过切片文件检查", e);
+            report.errors.push(format!("{:#}", e));
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    if let Some(manifest) = manifest {
+        let missing = manifest.missing_files(json_path, format);
+        if !missing.is_empty() {
+            if !best_effort {
+                anyhow::bail!(
+                    "导出目录不完整，{} 个 region 缺少切片文件:\n    {}",
+                    missing.len(),
+                    missing.join("\n    ")
+                );
+            }
+            report.errors.extend(missing);
+        }
     }
+    let report = Mutex::new(report);
+
+    // 还原所有维度的 region/、entities/ 和 poi/
+    let folders = dimensions(json_path)
+        .into_iter()
+        .flat_map(|dim| RegionKind::ALL.map(|kind| (dim.clone(), kind)));
+    for ((dim_folder, dim_name), kind) in folders {
+        let region_json_path = json_path.join(&dim_folder).join(kind.folder());
+        let region_output = output_path.join(&dim_folder).join(kind.folder());
 
-    // 还原所有维度
-    for (dim_folder, dim_name) in DIMENSIONS {
-        let (region_json_path, region_output) = if dim_folder.is_empty() {
-            (json_path.join("region"), output_path.join("region"))
+        if !region_json_path.exists() {
+            continue;
+        }
+
+        let terrain = kind == RegionKind::Chunks;
+        let mut region_files = collect_region_files(&region_json_path, format)?;
+        let options = ChunkReadOptions::from_config(&config.restore);
+        let dimension = (!dim_folder.is_empty()).then_some(dim_folder.as_str());
+        // 默认值和高度图只属于方块区块
+        let dimension_defaults = if restore_default_values && terrain {
+            dimension_default_values(&default_values, config, dimension)
         } else {
-            (
-                json_path.join(dim_folder).join("region"),
-                output_path.join(dim_folder).join("region"),
-            )
+            Vec::new()
+        };
+        let relocated = if options.relocate {
+            collect_misplaced_chunks(&region_files, format, &field_mapper, options)?
+        } else {
+            BTreeMap::new()
         };
+        // 只收到移入区块的 region 没有自己的文件
+        for key in relocated.keys() {
+            region_files.entry(*key).or_default();
+        }
+        let relocated = Mutex::new(relocated);
 
-        if !region_json_path.exists() {
+        // 跳过不在 regions 列表中或完全位于区块范围之外的 region
+        let region_list: Vec<_> = region_files
+            .into_iter()
+            .filter(|((rx, rz), _)| {
+                let restore = &config.restore;
+                (restore.regions.is_empty() || restore.regions.contains(&[*rx, *rz]))
+                    && restore
+                        .bounds
+                        .is_none_or(|b| b.may_contain_region(*rx, *rz))
+            })
+            .collect();
+
+        if region_list.is_empty() {
             continue;
         }
 
-        let region_re = Regex::new(r"r\.(-?\d+)\.(-?\d+)\.(\d+)\.json")?;
-        let mut region_files: std::collections::HashMap<(i32, i32), Vec<std::path::PathBuf>> =
-            std::collections::HashMap::new();
+        fs::create_dir_all(&region_output)?;
+        info!(
+            "还原 {} {}/ ({} 个 region)",
+            dim_name,
+            kind.folder(),
+            region_list.len()
+        );
+        progress::start(&dim_name, region_list.len());
+
+        let mapper = field_mapper.clone();
+        let result = region_list.par_iter().try_for_each(|((rx, rz), files)| {
+            let result = read_region_chunks((*rx, *rz), files, format, &mapper, options).and_then(
+                |mut region| {
+                    let chunks = &mut region.chunks;
+                    if options.relocate {
+                        // 坐标不属于该 region 的区块已在第一遍中取出
+                        chunks.retain(|c| in_region(c.x, c.z));
+                        let moved = relocated.lock().unwrap().remove(&(*rx, *rz));
+                        add_relocated_chunks(chunks, moved.unwrap_or_default(), *rx, *rz)?;
+                    }
+                    // 区块范围之外的区块不写入（而不是写为空区块）
+                    if let Some(bounds) = config.restore.bounds {
+                        chunks.retain(|c| bounds.contains(rx * 32 + c.x, rz * 32 + c.z));
+                    }
+                    if terrain && config.restore.compute_heightmaps {
+                        for chunk in chunks.iter_mut() {
+                            compute_heightmaps(&mut chunk.data);
+                        }
+                    }
+                    region.into_report(&dimension_defaults, |chunks| {
+                        if merge {
+                            merge_region(*rx, *rz, chunks, &region_output, compression)
+                        } else {
+                            write_region(*rx, *rz, chunks, &region_output, compression)
+                        }
+                    })
+                },
+            );
+            let mca_path = region_output.join(format!("r.{}.{}.mca", rx, rz));
+            record_region(&report, mca_path, result, config.restore.fail_fast)
+        });
+        progress::finish();
+        result?;
+    }
 
-        for entry in fs::read_dir(&region_json_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if !path.is_file() {
+    info!("还原完成");
+    Ok(finish_report(report))
+}
+
+/// 读取所有 region，取出坐标不属于所在 region 的区块，按正确的 region 分组（坐标转换为该 region 内的坐标）
+///
+/// 任一 region 读取失败时返回错误（无法确定该 region 中是否有需要移出的区块）
+fn collect_misplaced_chunks(
+    region_files: &BTreeMap<(i32, i32), Vec<PathBuf>>,
+    format: SliceFormat,
+    field_mapper: &FieldMapper,
+    options: ChunkReadOptions,
+) -> Result<BTreeMap<(i32, i32), Vec<ChunkData>>> {
+    let regions: Vec<((i32, i32), Vec<ChunkData>)> = region_files
+        .par_iter()
+        .map(|((rx, rz), files)| {
+            let region = read_region_chunks((*rx, *rz), files, format, field_mapper, options)
+                .with_context(|| format!("读取 r.{}.{} 失败，无法确定需要移动的区块", rx, rz))?;
+            Ok(((*rx, *rz), region.chunks))
+        })
+        .collect::<Result<_>>()?;
+
+    let mut relocated: BTreeMap<(i32, i32), Vec<ChunkData>> = BTreeMap::new();
+    for ((rx, rz), chunks) in regions {
+        for mut chunk in chunks.into_iter().filter(|c| !in_region(c.x, c.z)) {
+            let (x, z) = (rx * 32 + chunk.x, rz * 32 + chunk.z);
+            let target = (x.div_euclid(32), z.div_euclid(32));
+            warn!(
+                "区块 ({}, {}) 位于 r.{}.{} 的文件中，移动到 r.{}.{}",
+                x, z, rx, rz, target.0, target.1
+            );
+            chunk.x = x.rem_euclid(32);
+            chunk.z = z.rem_euclid(32);
+            relocated.entry(target).or_default().push(chunk);
+        }
+    }
+    Ok(relocated)
+}
+
+/// 将移入的区块加入目标 region，与该 region 已有的区块坐标相同时报错
+fn add_relocated_chunks(
+    chunks: &mut Vec<ChunkData>,
+    moved: Vec<ChunkData>,
+    rx: i32,
+    rz: i32,
+) -> Result<()> {
+    for chunk in moved {
+        if chunks.iter().any(|c| c.x == chunk.x && c.z == chunk.z) {
+            anyhow::bail!(
+                "移入的区块 ({}, {}) 与 r.{}.{} 中已有的区块坐标重复",
+                rx * 32 + chunk.x,
+                rz * 32 + chunk.z,
+                rx,
+                rz
+            );
+        }
+        chunks.push(chunk);
+    }
+    Ok(())
+}
+
+/// 将导出的 data/、playerdata/ 等目录还原为 `.dat` 文件
+///
+/// 尽力还原时跳过无法还原的文件，错误信息加入 `errors`
+fn restore_data_dir(
+    data_dir: &Path,
+    output_dir: &Path,
+    format: SliceFormat,
+    field_mapper: &FieldMapper,
+    best_effort: bool,
+    errors: &mut Vec<String>,
+) -> Result<()> {
+    let mut files: Vec<_> = fs::read_dir(data_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == format.extension()))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    info!("还原 {} ({} 个文件)", data_dir.display(), files.len());
+    for file in files {
+        let stem = file.file_stem().unwrap().to_string_lossy();
+        let output = output_dir.join(format!("{}.dat", stem));
+        let result = match format {
+            SliceFormat::Json => restore_level_dat_with_config(&file, &output, field_mapper),
+            SliceFormat::Snbt => restore_level_dat_snbt(&file, &output),
+        };
+        let result = result.with_context(|| format!("还原 {} 失败", file.display()));
+        skip_error(result, best_effort, errors)?;
+    }
+    Ok(())
+}
+
+/// 校验导出目录，只读取和转换区块文件，不写入任何文件（使用默认配置）
+pub fn restore_world_validate(json_path: &Path) -> Result<ValidationReport> {
+    restore_world_validate_with_config(json_path, &Config::default())
+}
+
+/// 校验导出目录（使用配置）
+///
+/// 对每个区块文件执行与还原相同的解析和转换（JSON/SNBT → NBT、base64 解码、
+/// 展开数据的重新打包），检查区块坐标是否存在、属于所在 region 且不重复，
+/// 收集所有问题而不在第一个错误处停止
+pub fn restore_world_validate_with_config(
+    json_path: &Path,
+    config: &Config,
+) -> Result<ValidationReport> {
+    let field_mapper = FieldMapper::from_config(&config.field_mapping);
+    let format = config.restore.format;
+    let pool = build_thread_pool(config.performance.threads)?;
+    let mut report = ValidationReport::default();
+
+    for (dim_folder, dim_name) in &dimensions(json_path) {
+        let region_path = json_path.join(dim_folder).join("region");
+        if !region_path.exists() {
+            continue;
+        }
+        let region_files: Vec<_> = collect_region_files(&region_path, format)?
+            .into_iter()
+            .collect();
+        if region_files.is_empty() {
+            continue;
+        }
+
+        info!("校验 {} ({} 个 region)", dim_name, region_files.len());
+        let region_reports: Vec<ValidationReport> = pool.install(|| {
+            region_files
+                .par_iter()
+                .map(|(region, files)| {
+                    validate_region(
+                        *region,
+                        files,
+                        format,
+                        &field_mapper,
+                        ChunkReadOptions::from_config(&config.restore),
+                    )
+                })
+                .collect()
+        });
+        for region in region_reports {
+            report.files_checked += region.files_checked;
+            report.chunks_checked += region.chunks_checked;
+            report.problems.extend(region.problems);
+        }
+    }
+
+    report
+        .problems
+        .sort_by(|a, b| (&a.file, a.chunk).cmp(&(&b.file, b.chunk)));
+    Ok(report)
+}
+
+/// 校验单个 region 的所有区块文件
+fn validate_region(
+    region: (i32, i32),
+    files: &[PathBuf],
+    format: SliceFormat,
+    field_mapper: &FieldMapper,
+    options: ChunkReadOptions,
+) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    // (图层后缀, x, z) -> 第一次出现的文件，主切片的后缀为空
+    let mut seen: HashMap<(String, i32, i32), PathBuf> = HashMap::new();
+
+    for file in files {
+        report.files_checked += 1;
+        let layer = if is_layer_file(file) {
+            file.file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.rsplit_once('.'))
+                .map(|(_, suffix)| suffix.to_string())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        let mut problem = |chunk: Option<(i32, i32)>, message: String| {
+            report.problems.push(ValidationProblem {
+                file: file.clone(),
+                chunk,
+                message,
+            })
+        };
+
+        let chunks = match format {
+            SliceFormat::Json => validate_json_file(file, field_mapper, &mut problem),
+            SliceFormat::Snbt => validate_snbt_file(file, &mut problem),
+        };
+        for ((cx, cz), data) in chunks {
+            let (x, z) = match &data {
+                Value::Compound(root) => actual_position(root, region, cx, cz),
+                _ => (cx, cz),
+            };
+            if !options.relocate && ((x, z) != (cx, cz) || !in_region(x, z)) {
+                problem(Some((cx, cz)), misplaced_message(region, (cx, cz), (x, z)));
                 continue;
             }
-            let filename = path.file_name().unwrap().to_str().unwrap();
-            if let Some(caps) = region_re.captures(filename) {
-                let rx: i32 = caps.get(1).unwrap().as_str().parse()?;
-                let rz: i32 = caps.get(2).unwrap().as_str().parse()?;
-                region_files.entry((rx, rz)).or_default().push(path);
+            match seen.get(&(layer.clone(), cx, cz)) {
+                Some(first) if options.dedupe == DuplicateChunkPolicy::Error => problem(
+                    Some((cx, cz)),
+                    format!(
+                        "区块坐标重复（已出现在 {}），可使用 --dedupe last 保留最后出现的区块",
+                        first.display()
+                    ),
+                ),
+                Some(_) => {}
+                None => {
+                    seen.insert((layer.clone(), cx, cz), file.clone());
+                }
             }
         }
+    }
+    report.chunks_checked = seen.len();
+    report
+}
 
-        if region_files.is_empty() {
-            continue;
+/// 校验 JSON 区块文件，返回成功转换的区块坐标和数据
+fn validate_json_file(
+    file: &Path,
+    field_mapper: &FieldMapper,
+    problem: &mut impl FnMut(Option<(i32, i32)>, String),
+) -> Vec<((i32, i32), Value)> {
+    let json: JsonValue = match fs::read_to_string(file)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Ok(serde_json::from_str(&content)?))
+    {
+        Ok(json) => json,
+        Err(e) => {
+            problem(None, format!("无法解析: {}", e));
+            return Vec::new();
         }
+    };
+    let Some(entries) = json.get("chunks").and_then(|v| v.as_array()) else {
+        problem(None, "缺少 chunks 数组".to_string());
+        return Vec::new();
+    };
 
-        fs::create_dir_all(&region_output)?;
-        println!("还原 {} ({} 个 region)", dim_name, region_files.len());
+    let mut coords = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let mut entry = entry.clone();
+        field_mapper.restore_json_keys(&mut entry);
+        let JsonValue::Object(mut obj) = entry else {
+            problem(None, format!("第 {} 个区块不是对象", i + 1));
+            continue;
+        };
+        let coord = |key: &str| obj.get(key).and_then(|v| v.as_i64()).map(|v| v as i32);
+        let (Some(cx), Some(cz)) = (coord("x"), coord("z")) else {
+            problem(None, format!("第 {} 个区块缺少 x/z 坐标", i + 1));
+            continue;
+        };
+        obj.remove("x");
+        obj.remove("z");
+        obj.remove("_ts");
+        match json_chunk_to_nbt(&JsonValue::Object(obj)) {
+            Ok(data) => coords.push(((cx, cz), data)),
+            Err(e) => problem(Some((cx, cz)), format!("{:#}", e)),
+        }
+    }
+    coords
+}
 
-        let region_list: Vec<_> = region_files.into_iter().collect();
-        let mapper = field_mapper.clone();
-        region_list.par_iter().for_each(|((rx, rz), files)| {
-            if let Err(e) = restore_region_slices_with_config(
-                *rx,
-                *rz,
-                files,
-                &region_output,
-                restore_default_values,
-                &mapper,
-            ) {
-                eprintln!("  失败 r.{}.{}: {}", rx, rz, e);
-            } else {
-                println!("  完成 r.{}.{}", rx, rz);
+/// 校验 SNBT 区块文件（每行一个区块），返回成功转换的区块坐标和数据
+fn validate_snbt_file(
+    file: &Path,
+    problem: &mut impl FnMut(Option<(i32, i32)>, String),
+) -> Vec<((i32, i32), Value)> {
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(e) => {
+            problem(None, format!("无法读取: {}", e));
+            return Vec::new();
+        }
+    };
+
+    let mut coords = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut value = match snbt_to_nbt(line) {
+            Ok(value) => value,
+            Err(e) => {
+                problem(None, format!("第 {} 行无法解析: {:#}", i + 1, e));
+                continue;
             }
-        });
+        };
+        let Value::Compound(map) = &mut value else {
+            problem(None, format!("第 {} 行不是复合标签", i + 1));
+            continue;
+        };
+        let (Some(Value::Int(cx)), Some(Value::Int(cz))) = (map.remove("x"), map.remove("z"))
+        else {
+            problem(None, format!("第 {} 行缺少 x/z 坐标", i + 1));
+            continue;
+        };
+        match pack_chunk(&mut value) {
+            Ok(()) => coords.push(((cx, cz), value)),
+            Err(e) => problem(Some((cx, cz)), format!("{:#}", e)),
+        }
     }
-
-    println!("还原完成");
-    Ok(())
+    coords
 }
 
 /// 还原 level.dat 文件
@@ -183,20 +1277,8 @@ pub fn restore_level_dat(json_path: &Path, output_path: &Path) -> Result<()> {
     // 使用默认映射器还原字段名
     restore_json_keys(&mut data);
 
-    let value = json_to_nbt(&data)?;
-
-    let nbt_data = fastnbt::to_bytes(&value)?;
-
-    if let Some(parent) = output_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
-    let file = File::create(output_path)?;
-    let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
-    encoder.write_all(&nbt_data)?;
-    encoder.finish()?;
-
-    Ok(())
+    let (value, root_name) = json_to_nbt_with_root_name(&data)?;
+    write_gzip_nbt(output_path, &value, &root_name)
 }
 
 /// 还原 level.dat 文件（使用配置）
@@ -213,141 +1295,1225 @@ pub fn restore_level_dat_with_config(
     // 使用配置的映射器还原字段名
     field_mapper.restore_json_keys(&mut data);
 
-    let value = json_to_nbt(&data)?;
+    let (value, root_name) = json_to_nbt_with_root_name(&data)?;
+    write_gzip_nbt(output_path, &value, &root_name)
+}
+
+/// 从 SNBT 还原 level.dat 文件
+pub fn restore_level_dat_snbt(snbt_path: &Path, output_path: &Path) -> Result<()> {
+    let content = fs::read_to_string(snbt_path)?;
+    let mut value = snbt_to_nbt(content.trim())?;
+    let root_name = take_root_name(&mut value);
+    write_gzip_nbt(output_path, &value, &root_name)
+}
+
+/// level.dat 的 gzip 压缩级别
+const LEVEL_DAT_COMPRESSION_LEVEL: u32 = 6;
 
-    let nbt_data = fastnbt::to_bytes(&value)?;
+/// 以 gzip 压缩写出 NBT 文件（level.dat 格式），根复合标签使用 `root_name`
+fn write_gzip_nbt(output_path: &Path, value: &Value, root_name: &str) -> Result<()> {
+    let nbt_data = nbt_to_bytes(value, root_name)?;
 
     if let Some(parent) = output_path.parent() {
         fs::create_dir_all(parent)?;
     }
 
-    let file = File::create(output_path)?;
-    let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
-    encoder.write_all(&nbt_data)?;
-    encoder.finish()?;
+    write_atomically_synced(
+        output_path,
+        gzip_compress(&nbt_data, LEVEL_DAT_COMPRESSION_LEVEL)?,
+    )?;
 
     Ok(())
 }
 
 /// 从多个切片文件还原单个 region
+///
+/// `best_effort` 为 true 时跳过无法读取的文件和无法还原的区块，错误信息记录在返回的结果中，
+/// 否则任一区块出错时整个 region 失败
 pub fn restore_region_slices(
     rx: i32,
     rz: i32,
     files: &[std::path::PathBuf],
     output_dir: &Path,
     restore_default_values: bool,
+    best_effort: bool,
+) -> Result<RestoreReport> {
+    let options = ChunkReadOptions {
+        best_effort,
+        ..Default::default()
+    };
+    let region = read_json_chunks((rx, rz), files, restore_json_keys, options)?;
+    let defaults = if restore_default_values {
+        default_restore_values()
+    } else {
+        Vec::new()
+    };
+    region.into_report(&defaults, |chunks| {
+        write_region(rx, rz, chunks, output_dir, CompressionConfig::default())
+    })
+}
+
+/// 从多个切片文件还原单个 region（使用配置）
+///
+/// 压缩方式、尽力还原和补全的默认值（`[restore.defaults]`）来自 `config`
+pub fn restore_region_slices_with_config(
+    rx: i32,
+    rz: i32,
+    files: &[std::path::PathBuf],
+    output_dir: &Path,
+    restore_default_values: bool,
+    field_mapper: &FieldMapper,
+    config: &RestoreConfig,
+) -> Result<RestoreReport> {
+    let options = ChunkReadOptions {
+        best_effort: config.best_effort,
+        ..Default::default()
+    };
+    let region = read_json_chunks(
+        (rx, rz),
+        files,
+        |json| field_mapper.restore_json_keys(json),
+        options,
+    )?;
+    let defaults = if restore_default_values {
+        config.default_values()?
+    } else {
+        Vec::new()
+    };
+    region.into_report(&defaults, |chunks| {
+        write_region(rx, rz, chunks, output_dir, config.compression)
+    })
+}
+
+/// 维度还原时补全的默认值
+///
+/// 维度使用白名单去噪时，额外将被移除的、区块加载需要的字段补全为空值（不覆盖配置中的同名字段）
+pub(crate) fn dimension_default_values(
+    defaults: &[(String, Value)],
+    config: &Config,
+    dimension: Option<&str>,
+) -> Vec<(String, Value)> {
+    let mut values = defaults.to_vec();
+    if config.denoise.chunk_config(dimension).mode == DenoiseMode::Whitelist {
+        for (field, value) in whitelist_restore_values() {
+            if !values.iter().any(|(f, _)| *f == field) {
+                values.push((field, value));
+            }
+        }
+    }
+    values
+}
+
+/// 从多个 SNBT 切片文件还原单个 region（每行一个区块）
+pub fn restore_region_slices_snbt(
+    rx: i32,
+    rz: i32,
+    files: &[std::path::PathBuf],
+    output_dir: &Path,
+    restore_default_values: bool,
+    compression: CompressionConfig,
+    best_effort: bool,
+) -> Result<RestoreReport> {
+    let options = ChunkReadOptions {
+        best_effort,
+        ..Default::default()
+    };
+    let region = read_snbt_chunks((rx, rz), files, options)?;
+    let defaults = if restore_default_values {
+        default_restore_values()
+    } else {
+        Vec::new()
+    };
+    region.into_report(&defaults, |chunks| {
+        write_region(rx, rz, chunks, output_dir, compression)
+    })
+}
+
+/// 将还原的区块合并到目标目录中已有的 region 文件
+///
+/// 按坐标覆盖已有区块，JSON 中没有的区块保持不变；目标文件不存在时与普通还原相同
+fn merge_region(
+    rx: i32,
+    rz: i32,
+    chunks: Vec<ChunkData>,
+    output_dir: &Path,
+    compression: CompressionConfig,
 ) -> Result<()> {
-    let mut chunks = Vec::new();
+    let output_file = output_dir.join(format!("r.{}.{}.mca", rx, rz));
+    let mut merged: BTreeMap<(i32, i32), ChunkData> = BTreeMap::new();
+    if output_file.exists() {
+        for chunk in read_mca(&output_file)? {
+            merged.insert((chunk.z, chunk.x), chunk);
+        }
+    }
+    for chunk in chunks {
+        merged.insert((chunk.z, chunk.x), chunk);
+    }
+    let chunks: Vec<ChunkData> = merged.into_values().collect();
+    write_region(rx, rz, chunks, output_dir, compression)
+}
 
-    for file_path in files {
-        let content = fs::read_to_string(file_path)?;
-        let json: JsonValue = serde_json::from_str(&content)?;
+/// 写出 region 文件，没有区块时不生成文件
+fn write_region(
+    rx: i32,
+    rz: i32,
+    chunks: Vec<ChunkData>,
+    output_dir: &Path,
+    compression: CompressionConfig,
+) -> Result<()> {
+    if chunks.is_empty() {
+        return Ok(());
+    }
+    let output_file = output_dir.join(format!("r.{}.{}.mca", rx, rz));
+    write_mca_with_compression(&output_file, &chunks, compression)
+}
+
+/// 读取单个 region 的所有区块文件（切片、拆分出的片段），转换为区块数据
+///
+/// 区块坐标为 region 内坐标，不恢复默认值。区块数据中的 `xPos`/`zPos` 与所在 region 或记录的 x/z 不符时，
+/// 不允许移动则视为错误，否则返回相对于该 region 的实际坐标（可能超出 0–31，由调用方移动到正确的 region）
+pub(crate) fn read_region_chunks(
+    region: (i32, i32),
+    files: &[PathBuf],
+    format: SliceFormat,
+    field_mapper: &FieldMapper,
+    options: ChunkReadOptions,
+) -> Result<RegionChunks> {
+    match format {
+        SliceFormat::Json => read_json_chunks(
+            region,
+            files,
+            |json| field_mapper.restore_json_keys(json),
+            options,
+        ),
+        SliceFormat::Snbt => read_snbt_chunks(region, files, options),
+    }
+}
 
-        let chunks_array = json
-            .get("chunks")
-            .and_then(|v| v.as_array())
-            .context("缺少 chunks 数组")?;
+/// 读取 JSON 切片中的区块，`restore_keys` 用于还原缩短的字段名
+fn read_json_chunks(
+    region: (i32, i32),
+    files: &[PathBuf],
+    restore_keys: impl Fn(&mut JsonValue),
+    options: ChunkReadOptions,
+) -> Result<RegionChunks> {
+    let (layer_files, files): (Vec<&PathBuf>, Vec<&PathBuf>) =
+        files.iter().partition(|path| is_layer_file(path));
+    let mut file_errors = Vec::new();
+    let mut noise_chunks = BTreeSet::new();
+    let layers = load_chunk_layers(
+        &layer_files,
+        &restore_keys,
+        options,
+        &mut noise_chunks,
+        &mut file_errors,
+    )?;
+    let mut merged = BTreeSet::new();
+    let mut pending = BTreeMap::new();
+    let mut errors = Vec::new();
+    let mut misplaced = Vec::new();
 
-        for chunk_json in chunks_array {
+    for file_path in files {
+        debug!("  读取 {}", file_path.display());
+        let source = file_path.display().to_string();
+        // 逐个区块解析并立即转换为 NBT，不保留整个切片的 JSON
+        let result = for_each_slice_chunk(file_path, |i, mut chunk_json| {
             // 还原缩短的字段名
-            let mut chunk_json = chunk_json.clone();
-            restore_json_keys(&mut chunk_json);
+            restore_keys(&mut chunk_json);
 
-            let cx = chunk_json
-                .get("x")
-                .and_then(|v| v.as_i64())
-                .context("区块缺少 x 坐标")? as i32;
-            let cz = chunk_json
-                .get("z")
-                .and_then(|v| v.as_i64())
-                .context("区块缺少 z 坐标")? as i32;
+            let coord = |key: &str| chunk_json.get(key).and_then(|v| v.as_i64());
+            let (Some(cx), Some(cz)) = (coord("x"), coord("z")) else {
+                errors.push(format!("{} 第 {} 个区块: 缺少 x/z 坐标", source, i + 1));
+                return Ok(());
+            };
+            let (cx, cz) = (cx as i32, cz as i32);
 
-            // 移除 x, z 字段后转换为 NBT
+            // 合并拆分出的片段（noise 文件中可能记录了 _ts），移除 x, z, _ts 字段
+            let mut timestamp = 0;
             if let JsonValue::Object(ref mut obj) = chunk_json {
+                if let Some(fragment) = layers.get(&(cx, cz)) {
+                    merge_json_layer(obj, fragment.clone());
+                    merged.insert((cx, cz));
+                }
                 obj.remove("x");
                 obj.remove("z");
+                timestamp = obj.remove("_ts").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+            }
+            let position = json_actual_position(&chunk_json, region, cx, cz);
+            if let Err(message) = check_position(region, (cx, cz), position, options) {
+                misplaced.push(format!("{} 区块 ({}, {}): {}", source, cx, cz, message));
+                return Ok(());
             }
+            match json_chunk_to_nbt(&chunk_json) {
+                Ok(data) => {
+                    let chunk = PendingChunk {
+                        source: source.clone(),
+                        recorded: (cx, cz),
+                        data,
+                        timestamp,
+                    };
+                    add_pending_chunk(&mut pending, position, chunk, options.dedupe, &mut errors);
+                }
+                Err(e) => errors.push(format!("{} 区块 ({}, {}): {:#}", source, cx, cz, e)),
+            }
+            Ok(())
+        });
+        skip_error(result, options.best_effort, &mut file_errors)?;
+    }
 
-            let mut value = json_to_nbt(&chunk_json)?;
+    // noise 文件中的坐标为记录的坐标，转换为区块的实际位置
+    let mut noise = BTreeSet::new();
+    let mut chunks: Vec<ChunkData> = pending
+        .into_iter()
+        .map(|((x, z), chunk)| {
+            if noise_chunks.contains(&chunk.recorded) {
+                noise.insert((x, z));
+            }
+            ChunkData {
+                x,
+                z,
+                timestamp: chunk.timestamp,
+                data: chunk.data,
+            }
+        })
+        .collect();
 
-            if restore_default_values {
-                restore_defaults(&mut value);
+    // 主切片中缺失的区块由片段单独还原，缺少的部分保持默认
+    for ((cx, cz), mut fragment) in layers {
+        if merged.contains(&(cx, cz)) {
+            continue;
+        }
+        let timestamp = fragment.remove("_ts").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+        match json_chunk_to_nbt(&JsonValue::Object(fragment)) {
+            Ok(mut value) => {
+                if let Value::Compound(map) = &mut value {
+                    fill_layer_only_chunk(map, region, cx, cz);
+                }
+                if noise_chunks.contains(&(cx, cz)) {
+                    noise.insert((cx, cz));
+                }
+                chunks.push(ChunkData {
+                    x: cx,
+                    z: cz,
+                    timestamp,
+                    data: value,
+                });
             }
+            Err(e) => errors.push(format!("区块片段 ({}, {}): {:#}", cx, cz, e)),
+        }
+    }
 
-            chunks.push(ChunkData {
-                x: cx,
-                z: cz,
-                data: value,
-            });
+    Ok(RegionChunks {
+        noise,
+        ..finish_chunks(chunks, errors, misplaced, file_errors, options)?
+    })
+}
+
+/// 读取区块文件的选项
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ChunkReadOptions {
+    /// 位置不属于所在 region 的区块返回相对该 region 的实际坐标（由调用方移动到正确的 region），
+    /// 否则视为错误
+    pub relocate: bool,
+    /// 同一 region 中坐标重复的区块的处理方式
+    pub dedupe: DuplicateChunkPolicy,
+    /// 跳过无法读取的文件和无法还原的区块（记录错误信息），而不是让整个 region 失败
+    pub best_effort: bool,
+}
+
+impl ChunkReadOptions {
+    /// 还原时使用的选项
+    pub(crate) fn from_config(config: &RestoreConfig) -> Self {
+        Self {
+            relocate: config.relocate_chunks,
+            dedupe: config.dedupe,
+            best_effort: config.best_effort,
+        }
+    }
+
+    /// 只读取区块内容（比较、统计）时使用的选项：区块按实际位置返回，重复的区块保留最后一个
+    pub(crate) fn lenient() -> Self {
+        Self {
+            relocate: true,
+            dedupe: DuplicateChunkPolicy::Last,
+            best_effort: false,
         }
     }
+}
+
+/// 已读取但尚未转换的区块
+struct PendingChunk<T> {
+    /// 来源文件（SNBT 还包括行号），用于错误信息
+    source: String,
+    /// 切片中记录的坐标（拆分出的片段按该坐标合并）
+    recorded: (i32, i32),
+    data: T,
+    timestamp: u32,
+}
 
-    if !chunks.is_empty() {
-        let output_file = output_dir.join(format!("r.{}.{}.mca", rx, rz));
-        write_mca(&output_file, &chunks)?;
+/// 按区块的实际位置加入区块，位置重复时按 `dedupe` 报错或保留后出现的区块
+fn add_pending_chunk<T>(
+    pending: &mut BTreeMap<(i32, i32), PendingChunk<T>>,
+    position: (i32, i32),
+    chunk: PendingChunk<T>,
+    dedupe: DuplicateChunkPolicy,
+    errors: &mut Vec<String>,
+) {
+    let (cx, cz) = chunk.recorded;
+    match pending.get_mut(&position) {
+        None => {
+            pending.insert(position, chunk);
+        }
+        Some(existing) => match dedupe {
+            DuplicateChunkPolicy::Error => errors.push(format!(
+                "{} 区块 ({}, {}): 与 {} 中的区块坐标重复，可使用 --dedupe last 保留最后出现的区块",
+                chunk.source, cx, cz, existing.source
+            )),
+            DuplicateChunkPolicy::Last => {
+                warn!(
+                    "区块 ({}, {}) 同时出现在 {} 和 {} 中，保留后者",
+                    cx, cz, existing.source, chunk.source
+                );
+                *existing = chunk;
+            }
+        },
     }
+}
 
+/// 检查区块的实际位置（相对所在 region）与所在 region 和记录的坐标是否一致，不一致且不允许移动时返回错误信息
+fn check_position(
+    region: (i32, i32),
+    recorded: (i32, i32),
+    (x, z): (i32, i32),
+    options: ChunkReadOptions,
+) -> std::result::Result<(), String> {
+    if !options.relocate && ((x, z) != recorded || !in_region(x, z)) {
+        return Err(misplaced_message(region, recorded, (x, z)));
+    }
     Ok(())
 }
 
-/// 从多个切片文件还原单个 region（使用配置）
-pub fn restore_region_slices_with_config(
-    rx: i32,
-    rz: i32,
-    files: &[std::path::PathBuf],
-    output_dir: &Path,
-    restore_default_values: bool,
-    field_mapper: &FieldMapper,
-) -> Result<()> {
-    let mut chunks = Vec::new();
+/// 将区块 JSON 转换为 NBT，并将展开的 block_states、biomes 和高度图重新打包为 LongArray
+fn json_chunk_to_nbt(json: &JsonValue) -> Result<Value> {
+    let mut value = json_to_nbt(json)?;
+    pack_chunk(&mut value)?;
+    Ok(value)
+}
 
-    for file_path in files {
-        let content = fs::read_to_string(file_path)?;
-        let json: JsonValue = serde_json::from_str(&content)?;
+/// 区块坐标（region 内坐标）是否在 0–31 之间
+fn in_region(cx: i32, cz: i32) -> bool {
+    (0..32).contains(&cx) && (0..32).contains(&cz)
+}
 
-        let chunks_array = json
-            .get("chunks")
-            .and_then(|v| v.as_array())
-            .context("缺少 chunks 数组")?;
+/// 区块相对 `region` 的实际坐标：优先使用区块数据中的 `xPos`/`zPos`（世界区块坐标，
+/// 1.18 之前位于 `Level` 内），没有时为切片中记录的 x/z
+fn actual_position(
+    root: &HashMap<String, Value>,
+    (rx, rz): (i32, i32),
+    cx: i32,
+    cz: i32,
+) -> (i32, i32) {
+    let body = chunk_body(root).0;
+    match (body.get("xPos"), body.get("zPos")) {
+        (Some(Value::Int(x)), Some(Value::Int(z))) => (x - rx * 32, z - rz * 32),
+        _ => (cx, cz),
+    }
+}
 
-        for chunk_json in chunks_array {
-            // 还原缩短的字段名
-            let mut chunk_json = chunk_json.clone();
-            field_mapper.restore_json_keys(&mut chunk_json);
+/// 与 [`actual_position`] 相同，读取转换前的区块 JSON
+fn json_actual_position(json: &JsonValue, (rx, rz): (i32, i32), cx: i32, cz: i32) -> (i32, i32) {
+    let body = match json.get(LEGACY_LEVEL_KEY) {
+        Some(level) if level.is_object() => level,
+        _ => json,
+    };
+    let coord = |key: &str| body.get(key).and_then(|v| v.as_i64());
+    match (coord("xPos"), coord("zPos")) {
+        (Some(x), Some(z)) => (x as i32 - rx * 32, z as i32 - rz * 32),
+        _ => (cx, cz),
+    }
+}
 
-            let cx = chunk_json
-                .get("x")
-                .and_then(|v| v.as_i64())
-                .context("区块缺少 x 坐标")? as i32;
-            let cz = chunk_json
-                .get("z")
-                .and_then(|v| v.as_i64())
-                .context("区块缺少 z 坐标")? as i32;
+/// 不属于所在 region 或位置与切片中记录的 x/z 不符的区块的错误信息
+fn misplaced_message((rx, rz): (i32, i32), (cx, cz): (i32, i32), (x, z): (i32, i32)) -> String {
+    let (wx, wz) = (rx * 32 + x, rz * 32 + z);
+    let problem = if (x, z) == (cx, cz) {
+        format!("位置 ({}, {}) 不属于 r.{}.{}", wx, wz, rx, rz)
+    } else {
+        format!(
+            "区块数据中的位置 ({}, {}) 与切片记录的位置 ({}, {}) 不符",
+            wx,
+            wz,
+            rx * 32 + cx,
+            rz * 32 + cz
+        )
+    };
+    format!(
+        "{}，区块可能被移到了错误的切片文件，可使用 --relocate-chunks 移动到正确的 region",
+        problem
+    )
+}
 
-            // 移除 x, z 字段后转换为 NBT
-            if let JsonValue::Object(ref mut obj) = chunk_json {
-                obj.remove("x");
-                obj.remove("z");
+/// 单个 region 读取到的区块
+#[derive(Default)]
+pub(crate) struct RegionChunks {
+    pub chunks: Vec<ChunkData>,
+    /// 跳过的区块数量（尽力还原时无法还原的区块，以及位置不符的区块）
+    pub skipped_chunks: usize,
+    /// 跳过的文件和区块的错误信息
+    pub errors: Vec<String>,
+    /// noise 文件中有记录的区块（region 内坐标），已按原值还原，不再恢复默认值
+    pub noise: BTreeSet<(i32, i32)>,
+}
+
+impl RegionChunks {
+    /// 为 noise 文件中没有记录的区块恢复默认值，由 `write` 写出后返回该 region 的还原结果
+    fn into_report(
+        mut self,
+        defaults: &[(String, Value)],
+        write: impl FnOnce(Vec<ChunkData>) -> Result<()>,
+    ) -> Result<RestoreReport> {
+        let chunks_written = self.chunks.len();
+        let mut defaults_injected = BTreeMap::new();
+        for chunk in &mut self.chunks {
+            if self.noise.contains(&(chunk.x, chunk.z)) {
+                continue;
             }
+            for field in restore_defaults_with_values(&mut chunk.data, defaults) {
+                *defaults_injected.entry(field).or_insert(0) += 1;
+            }
+        }
+        write(self.chunks)?;
+        Ok(RestoreReport {
+            regions_total: 1,
+            regions_restored: 1,
+            chunks_written,
+            chunks_skipped: self.skipped_chunks,
+            defaults_injected,
+            errors: self.errors,
+            ..Default::default()
+        })
+    }
+}
+
+/// 汇总读取结果；有无法还原的区块时，尽力还原模式下跳过这些区块并保留错误信息，
+/// 否则返回包含所有区块错误的错误（每行一个）
+///
+/// 位置与所在 region 或记录的坐标不符的区块（`misplaced`）总是单独跳过并记录，不影响其余区块
+fn finish_chunks(
+    chunks: Vec<ChunkData>,
+    errors: Vec<String>,
+    misplaced: Vec<String>,
+    mut file_errors: Vec<String>,
+    options: ChunkReadOptions,
+) -> Result<RegionChunks> {
+    if !options.best_effort && !errors.is_empty() {
+        anyhow::bail!(
+            "{} 个区块无法还原:\n    {}",
+            errors.len(),
+            errors.join("\n    ")
+        );
+    }
+    for message in &misplaced {
+        warn!("{}，跳过该区块", message);
+    }
+    let skipped_chunks = errors.len() + misplaced.len();
+    file_errors.extend(errors);
+    file_errors.extend(misplaced);
+    Ok(RegionChunks {
+        chunks,
+        skipped_chunks,
+        errors: file_errors,
+        noise: BTreeSet::new(),
+    })
+}
+
+/// 读取 SNBT 切片中的区块（每行一个区块）
+fn read_snbt_chunks(
+    region: (i32, i32),
+    files: &[PathBuf],
+    options: ChunkReadOptions,
+) -> Result<RegionChunks> {
+    let (layer_files, files): (Vec<&PathBuf>, Vec<&PathBuf>) =
+        files.iter().partition(|path| is_layer_file(path));
+    let mut file_errors = Vec::new();
+    let mut noise_chunks = BTreeSet::new();
+    let mut layers =
+        load_chunk_layers_snbt(&layer_files, options, &mut noise_chunks, &mut file_errors)?;
+    let mut pending = BTreeMap::new();
+    let mut errors = Vec::new();
+    let mut misplaced = Vec::new();
+
+    for file_path in files {
+        debug!("  读取 {}", file_path.display());
+        let content = match fs::read_to_string(file_path)
+            .with_context(|| format!("读取 {} 失败", file_path.display()))
+        {
+            Ok(content) => content,
+            Err(e) => {
+                skip_error(Err(e), options.best_effort, &mut file_errors)?;
+                continue;
+            }
+        };
+
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let source = format!("{} 第 {} 行", file_path.display(), i + 1);
+            let mut map = match snbt_to_nbt(line) {
+                Ok(Value::Compound(map)) => map,
+                Ok(_) => {
+                    errors.push(format!("{}: 区块不是复合标签", source));
+                    continue;
+                }
+                Err(e) => {
+                    errors.push(format!("{}: {:#}", source, e));
+                    continue;
+                }
+            };
 
-            let mut value = json_to_nbt(&chunk_json)?;
+            // 取出并移除 x, z 字段
+            let (Some(Value::Int(cx)), Some(Value::Int(cz))) = (map.remove("x"), map.remove("z"))
+            else {
+                errors.push(format!("{}: 缺少 x/z 坐标", source));
+                continue;
+            };
+            let timestamp = nbt_timestamp(map.remove("_ts"));
+            let position = actual_position(&map, region, cx, cz);
+            let chunk = PendingChunk {
+                source,
+                recorded: (cx, cz),
+                data: map,
+                timestamp,
+            };
+            add_pending_chunk(&mut pending, position, chunk, options.dedupe, &mut errors);
+        }
+    }
 
-            if restore_default_values {
-                restore_defaults(&mut value);
+    // 合并拆分出的片段后重新打包
+    let mut chunks = Vec::new();
+    let mut noise = BTreeSet::new();
+    for ((x, z), mut chunk) in pending {
+        let (cx, cz) = chunk.recorded;
+        if let Some(fragment) = layers.remove(&(cx, cz)) {
+            merge_nbt_layer(&mut chunk.data, fragment);
+            // noise 文件中记录的时间戳
+            if let Some(ts) = chunk.data.remove("_ts") {
+                chunk.timestamp = nbt_timestamp(Some(ts));
             }
+        }
+        if let Err(message) = check_position(region, (cx, cz), (x, z), options) {
+            misplaced.push(format!(
+                "{} 区块 ({}, {}): {}",
+                chunk.source, cx, cz, message
+            ));
+            continue;
+        }
+        if noise_chunks.contains(&(cx, cz)) {
+            noise.insert((x, z));
+        }
+        let mut value = Value::Compound(chunk.data);
+        match pack_chunk(&mut value) {
+            Ok(()) => chunks.push(ChunkData {
+                x,
+                z,
+                timestamp: chunk.timestamp,
+                data: value,
+            }),
+            Err(e) => errors.push(format!("{} 区块 ({}, {}): {:#}", chunk.source, cx, cz, e)),
+        }
+    }
 
-            chunks.push(ChunkData {
+    // 主切片中缺失的区块由片段单独还原，缺少的部分保持默认
+    for ((cx, cz), mut fragment) in layers {
+        let timestamp = nbt_timestamp(fragment.remove("_ts"));
+        fill_layer_only_chunk(&mut fragment, region, cx, cz);
+        if noise_chunks.contains(&(cx, cz)) {
+            noise.insert((cx, cz));
+        }
+        let mut value = Value::Compound(fragment);
+        match pack_chunk(&mut value) {
+            Ok(()) => chunks.push(ChunkData {
                 x: cx,
                 z: cz,
+                timestamp,
                 data: value,
+            }),
+            Err(e) => errors.push(format!("区块片段 ({}, {}): {:#}", cx, cz, e)),
+        }
+    }
+
+    Ok(RegionChunks {
+        noise,
+        ..finish_chunks(chunks, errors, misplaced, file_errors, options)?
+    })
+}
+
+/// SNBT 区块中 `_ts` 字段记录的时间戳，没有时为 0
+fn nbt_timestamp(value: Option<Value>) -> u32 {
+    match value {
+        Some(Value::Int(ts)) => ts as u32,
+        Some(Value::Long(ts)) => ts as u32,
+        _ => 0,
+    }
+}
+
+/// 将展开的 block_states、biomes 和高度图重新打包为 LongArray
+fn pack_chunk(value: &mut Value) -> Result<()> {
+    pack_block_states(value)?;
+    pack_biomes(value)?;
+    pack_heightmaps(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::compare_regions;
+    use crate::export::{export_world_to_memory, export_world_with_config};
+    use crate::manifest::EXPORT_MANIFEST_FILE;
+    use crate::nbt_json::nbt_to_snbt;
+    use crate::test_util::{sample_chunks, sample_entity_chunk, write_region, write_world};
+
+    #[test]
+    fn entities_round_trip() {
+        let world = tempfile::tempdir().unwrap();
+        write_world(world.path(), &sample_chunks(&[(0, 0), (1, 0)]));
+        let entities: Vec<ChunkData> = [(0, 0), (3, 2)]
+            .into_iter()
+            .map(|(x, z)| ChunkData {
+                x,
+                z,
+                timestamp: 1_700_000_000,
+                data: sample_entity_chunk(x, z),
+            })
+            .collect();
+        write_region(world.path(), "entities", &entities);
+
+        let config = Config::default();
+        // 去噪时移除实体的运动状态，保留 UUID
+        let files = export_world_to_memory(world.path(), true, false, &config).unwrap();
+        let slice = &files["entities/r.0.0.0.json"];
+        assert!(
+            !slice.contains("Motion") && slice.contains("UUID"),
+            "{}",
+            slice
+        );
+        let files = export_world_to_memory(world.path(), true, true, &config).unwrap();
+        assert!(!files["entities/r.0.0.0.json"].contains("UUID"));
+
+        let files = export_world_to_memory(world.path(), false, false, &config).unwrap();
+        assert!(files.contains_key("entities/r.0.0.0.json"));
+
+        let output = tempfile::tempdir().unwrap();
+        let output = output.path().join("world");
+        restore_world_from_memory(&files, &output, true, &config).unwrap();
+        let differences = compare_regions(
+            &world.path().join("entities/r.0.0.mca"),
+            &output.join("entities/r.0.0.mca"),
+            &[],
+        )
+        .unwrap();
+        assert!(differences.is_empty(), "{:?}", differences);
+    }
+
+    /// 在 `json_dir/region/` 中写入 r.0.0 的切片，其中 (1, 0) 的 xPos 指向 r.1.0
+    fn write_misplaced_slice(json_dir: &Path) {
+        let chunk = |x: i32, x_pos: i32| {
+            serde_json::json!({
+                "x": x, "z": 0, "xPos": x_pos, "zPos": 0,
+                "Status": "minecraft:full", "DataVersion": 3465,
+            })
+        };
+        let slice = serde_json::json!({ "chunks": [chunk(0, 0), chunk(1, 40)] });
+        fs::create_dir_all(json_dir.join("region")).unwrap();
+        fs::write(
+            json_dir.join("region/r.0.0.0.json"),
+            serde_json::to_string(&slice).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn misplaced_chunk_is_skipped_without_failing_the_region() {
+        let json_dir = tempfile::tempdir().unwrap();
+        write_misplaced_slice(json_dir.path());
+        let output = tempfile::tempdir().unwrap();
+        let output = output.path().join("world");
+
+        let report =
+            restore_world_with_config(json_dir.path(), &output, false, &Config::default()).unwrap();
+        assert!(report.failures.is_empty());
+        assert_eq!((report.chunks_written, report.chunks_skipped), (1, 1));
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("(40, 0)"), "{}", report.errors[0]);
+        let chunks = read_mca(&output.join("region/r.0.0.mca")).unwrap();
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[test]
+    fn unreadable_region_aborts_relocation() {
+        let json_dir = tempfile::tempdir().unwrap();
+        write_misplaced_slice(json_dir.path());
+        fs::write(
+            json_dir.path().join("region/r.1.0.0.json"),
+            "{\"chunks\": [",
+        )
+        .unwrap();
+        let output = tempfile::tempdir().unwrap();
+
+        let mut config = Config::default();
+        config.restore.relocate_chunks = true;
+        let error = restore_world_with_config(
+            json_dir.path(),
+            &output.path().join("world"),
+            false,
+            &config,
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", error).contains("r.1.0"), "{:#}", error);
+    }
+
+    /// 在 `json_dir/region/` 中写入 r.0.0 的两个切片，都含有区块 (5, 7)，LastUpdate 分别为 1 和 2
+    fn write_conflicting_slices(json_dir: &Path, format: SliceFormat) {
+        let chunk = |x: i32, z: i32, last_update: i64| {
+            serde_json::json!({
+                "x": x, "z": z, "xPos": x, "zPos": z,
+                "Status": "minecraft:full", "LastUpdate": format!("{}L", last_update),
+            })
+        };
+        let slices = [
+            vec![chunk(0, 0, 1), chunk(5, 7, 1)],
+            vec![chunk(5, 7, 2), chunk(6, 7, 2)],
+        ];
+        let dir = json_dir.join("region");
+        fs::create_dir_all(&dir).unwrap();
+        for (id, chunks) in slices.into_iter().enumerate() {
+            let path = dir.join(format!("r.0.0.{}.{}", id, format.extension()));
+            let content = match format {
+                SliceFormat::Json => serde_json::json!({ "chunks": chunks }).to_string(),
+                SliceFormat::Snbt => chunks
+                    .iter()
+                    .map(|c| nbt_to_snbt(&json_to_nbt(c).unwrap()) + "\n")
+                    .collect(),
+            };
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    fn last_update(chunk: &ChunkData) -> Option<&Value> {
+        match &chunk.data {
+            Value::Compound(root) => root.get("LastUpdate"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn duplicate_chunk_across_slices_names_both_files() {
+        let json_dir = tempfile::tempdir().unwrap();
+        write_conflicting_slices(json_dir.path(), SliceFormat::Json);
+        let output = tempfile::tempdir().unwrap();
+
+        let report = restore_world_with_config(
+            json_dir.path(),
+            &output.path().join("world"),
+            false,
+            &Config::default(),
+        )
+        .unwrap();
+        assert_eq!(report.failures.len(), 1);
+        let error = &report.failures[0].error;
+        assert!(error.contains("r.0.0.0.json"), "{}", error);
+        assert!(error.contains("r.0.0.1.json"), "{}", error);
+        assert!(error.contains("--dedupe last"), "{}", error);
+        assert!(!output.path().join("world/region/r.0.0.mca").exists());
+    }
+
+    #[test]
+    fn dedupe_last_keeps_the_later_slice() {
+        for format in [SliceFormat::Json, SliceFormat::Snbt] {
+            let json_dir = tempfile::tempdir().unwrap();
+            write_conflicting_slices(json_dir.path(), format);
+            let output = tempfile::tempdir().unwrap();
+            let output = output.path().join("world");
+
+            let mut config = Config::default();
+            config.restore.format = format;
+            config.restore.dedupe = DuplicateChunkPolicy::Last;
+            let report =
+                restore_world_with_config(json_dir.path(), &output, false, &config).unwrap();
+            assert!(report.failures.is_empty(), "{:?}", report.failures);
+            assert_eq!(report.chunks_written, 3);
+
+            let chunks = read_mca(&output.join("region/r.0.0.mca")).unwrap();
+            assert_eq!(chunks.len(), 3);
+            let duplicate = chunks.iter().find(|c| (c.x, c.z) == (5, 7)).unwrap();
+            assert_eq!(
+                last_update(duplicate),
+                Some(&Value::Long(2)),
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    fn write_slice(dir: &Path, content: &str) -> PathBuf {
+        let path = dir.join("r.0.0.0.json");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn slice_chunks_are_streamed_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_slice(
+            dir.path(),
+            r#"{"version": {"nested": [1, 2]}, "chunks": [{"x": 0}, {"x": 1}, {"x": 2}], "after": null}"#,
+        );
+        let mut seen = Vec::new();
+        for_each_slice_chunk(&path, |i, chunk| {
+            seen.push((i, chunk["x"].as_i64().unwrap()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(seen, [(0, 0), (1, 1), (2, 2)]);
+    }
+
+    /// 统计当前线程已分配但未释放的字节数及其峰值的分配器（其它线程的分配互不影响）
+    struct CountingAllocator;
+
+    thread_local! {
+        static LIVE_BYTES: std::cell::Cell<isize> = const { std::cell::Cell::new(0) };
+        static PEAK_BYTES: std::cell::Cell<isize> = const { std::cell::Cell::new(0) };
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = LIVE_BYTES.try_with(|live| {
+                live.set(live.get() + layout.size() as isize);
+                let _ = PEAK_BYTES.try_with(|peak| peak.set(peak.get().max(live.get())));
             });
+            std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            let _ = LIVE_BYTES.try_with(|live| live.set(live.get() - layout.size() as isize));
+            std::alloc::System.dealloc(ptr, layout)
         }
     }
 
-    if !chunks.is_empty() {
-        let output_file = output_dir.join(format!("r.{}.{}.mca", rx, rz));
-        write_mca(&output_file, &chunks)?;
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// `f` 运行期间当前线程比开始时多占用的内存峰值（字节）
+    fn peak_allocation(f: impl FnOnce()) -> isize {
+        let start = LIVE_BYTES.with(|live| live.get());
+        PEAK_BYTES.with(|peak| peak.set(start));
+        f();
+        PEAK_BYTES.with(|peak| peak.get()) - start
     }
 
-    Ok(())
+    #[test]
+    fn streaming_a_slice_keeps_one_chunk_in_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk = |i: usize| {
+            let values: Vec<String> = (0..2048).map(|v| (v * i % 1000).to_string()).collect();
+            format!("{{\"x\":{},\"data\":[{}]}}", i, values.join(","))
+        };
+        let chunks: Vec<String> = (0..256).map(chunk).collect();
+        let path = write_slice(
+            dir.path(),
+            &format!("{{\"chunks\":[{}]}}", chunks.join(",")),
+        );
+        let size = fs::metadata(&path).unwrap().len() as isize;
+
+        let mut count = 0;
+        let streaming = peak_allocation(|| {
+            for_each_slice_chunk(&path, |_, chunk| {
+                count += chunk["data"].as_array().unwrap().len();
+                Ok(())
+            })
+            .unwrap();
+        });
+        assert_eq!(count, 256 * 2048);
+        let tree = peak_allocation(|| {
+            let content = fs::read(&path).unwrap();
+            let value: JsonValue = serde_json::from_slice(&content).unwrap();
+            assert_eq!(value["chunks"].as_array().unwrap().len(), 256);
+        });
+        // 流式解析只多出文件内容和单个区块；完整的 JSON 树是文件大小的数倍
+        assert!(
+            streaming < size * 2,
+            "流式解析峰值 {} 字节，文件 {} 字节",
+            streaming,
+            size
+        );
+        assert!(
+            tree > streaming * 4,
+            "JSON 树峰值 {} 字节，流式 {} 字节",
+            tree,
+            streaming
+        );
+    }
+
+    #[test]
+    fn slice_callback_error_stops_parsing() {
+        let dir = tempfile::tempdir().unwrap();
+        // 第三个区块不是有效的 JSON，回调在第二个区块出错时不应解析到它
+        let path = write_slice(dir.path(), r#"{"chunks": [{"x": 0}, {"x": 1}, {"x": "#);
+        let mut calls = 0;
+        let err = for_each_slice_chunk(&path, |i, _| {
+            calls += 1;
+            if i == 1 {
+                anyhow::bail!("区块 {} 无效", i);
+            }
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(calls, 2);
+        assert_eq!(err.to_string(), "区块 1 无效");
+    }
+
+    #[test]
+    fn malformed_slices_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        for content in [
+            r#"{"version": 1}"#,
+            r#"{"chunks": [{"x": 0}"#,
+            r#"{"chunks": []} trailing"#,
+            r#"{"chunks": {"x": 0}}"#,
+            r#"[{"x": 0}]"#,
+        ] {
+            let path = write_slice(dir.path(), content);
+            assert!(
+                for_each_slice_chunk(&path, |_, _| Ok(())).is_err(),
+                "{}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_manifest_is_recorded_in_best_effort_mode() {
+        let world = tempfile::tempdir().unwrap();
+        write_world(world.path(), &sample_chunks(&[(0, 0)]));
+        let json_dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        export_world_with_config(world.path(), json_dir.path(), false, false, &config).unwrap();
+        fs::write(json_dir.path().join(EXPORT_MANIFEST_FILE), "{ not json").unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let strict = restore_world_with_config(
+            json_dir.path(),
+            &output.path().join("strict"),
+            false,
+            &config,
+        );
+        assert!(strict.is_err());
+
+        let mut config = Config::default();
+        config.restore.best_effort = true;
+        let report = restore_world_with_config(
+            json_dir.path(),
+            &output.path().join("best_effort"),
+            false,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert!(report.errors[0].contains(EXPORT_MANIFEST_FILE));
+        assert_eq!(report.chunks_written, 1);
+    }
+
+    #[test]
+    fn sections_merge_by_y_and_other_lists_are_replaced() {
+        let mut chunk = serde_json::json!({
+            "sections": [{"Y": -1, "a": 1}, {"Y": 1, "a": 3}],
+            "Entities": [{"id": "a", "Motion": [0.0]}, {"id": "b"}],
+        });
+        merge_json_value(
+            &mut chunk,
+            serde_json::json!({
+                "sections": [{"Y": 0, "a": 2}, {"Y": 1, "SkyLight": "x"}],
+                "Entities": [{"id": "a", "Motion": [1.0]}, {"id": "c"}],
+            }),
+        );
+        assert_eq!(
+            chunk,
+            serde_json::json!({
+                "sections": [
+                    {"Y": -1, "a": 1},
+                    {"Y": 0, "a": 2},
+                    {"Y": 1, "a": 3, "SkyLight": "x"},
+                ],
+                "Entities": [{"id": "a", "Motion": [1.0]}, {"id": "c"}],
+            })
+        );
+
+        let section = |y: i8, key: &str| {
+            Value::Compound(HashMap::from([
+                ("Y".to_string(), Value::Byte(y)),
+                (key.to_string(), Value::Int(y as i32)),
+            ]))
+        };
+        let mut sections = Value::List(vec![section(-1, "a"), section(1, "a")]);
+        merge_nbt_value(
+            &mut sections,
+            Value::List(vec![section(0, "a"), section(1, "b")]),
+        );
+        let Value::List(merged) = &sections else {
+            unreachable!()
+        };
+        assert_eq!(merged.len(), 3);
+        assert_eq!(
+            merged[2],
+            Value::Compound(HashMap::from([
+                ("Y".to_string(), Value::Byte(1)),
+                ("a".to_string(), Value::Int(1)),
+                ("b".to_string(), Value::Int(1)),
+            ]))
+        );
+
+        let mut list = Value::List(vec![Value::Int(1), Value::Int(2)]);
+        merge_nbt_value(&mut list, Value::List(vec![Value::Int(3)]));
+        assert_eq!(list, Value::List(vec![Value::Int(3)]));
+    }
+
+    #[test]
+    fn lossless_defaults_apply_only_to_chunks_without_noise() {
+        let world = tempfile::tempdir().unwrap();
+        write_world(world.path(), &sample_chunks(&[(0, 0), (1, 0)]));
+        let json_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.denoise.lossless = true;
+        export_world_with_config(world.path(), json_dir.path(), true, false, &config).unwrap();
+
+        // 去掉 (1, 0) 在 noise 文件中的记录
+        let noise_path = json_dir.path().join("region/r.0.0.noise.json");
+        let mut noise: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&noise_path).unwrap()).unwrap();
+        noise["chunks"]
+            .as_array_mut()
+            .unwrap()
+            .retain(|entry| entry["x"] != 1);
+        fs::write(&noise_path, noise.to_string()).unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let output = output.path().join("world");
+        let report = restore_world_with_config(json_dir.path(), &output, true, &config).unwrap();
+        assert_eq!(report.defaults_injected.get("LastUpdate"), Some(&1));
+
+        let chunks = read_mca(&output.join("region/r.0.0.mca")).unwrap();
+        let field = |x: i32, key: &str| {
+            let chunk = chunks.iter().find(|c| c.x == x).unwrap();
+            let Value::Compound(map) = &chunk.data else {
+                unreachable!()
+            };
+            map.get(key).cloned()
+        };
+        assert_eq!(field(0, "LastUpdate"), Some(Value::Long(1000)));
+        assert_eq!(field(0, "isLightOn"), Some(Value::Byte(1)));
+        assert_eq!(field(1, "LastUpdate"), Some(Value::Long(0)));
+        assert_eq!(field(1, "isLightOn"), Some(Value::Byte(0)));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn legacy_report_accessors_match_fields() {
+        let report = RestoreReport {
+            chunks_written: 3,
+            chunks_skipped: 1,
+            ..Default::default()
+        };
+        assert_eq!(report.restored_chunks(), 3);
+        assert_eq!(report.skipped_chunks(), 1);
+
+        let mut chunk = crate::test_util::sample_chunk(0, 0);
+        if let Value::Compound(map) = &mut chunk {
+            map.remove("LastUpdate");
+        }
+        crate::denoise::restore_defaults(&mut chunk);
+        let Value::Compound(map) = &chunk else {
+            unreachable!()
+        };
+        assert_eq!(map["LastUpdate"], Value::Long(0));
+        assert_eq!(map["isLightOn"], Value::Byte(1));
+    }
+
+    #[test]
+    fn existing_output_is_refused_unless_cleaned() {
+        let world = tempfile::tempdir().unwrap();
+        write_world(world.path(), &sample_chunks(&[(0, 0)]));
+        let json_dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        export_world_with_config(world.path(), json_dir.path(), false, false, &config).unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let stale = output.path().join("region/r.9.9.mca");
+        fs::create_dir_all(stale.parent().unwrap()).unwrap();
+        fs::write(&stale, b"stale").unwrap();
+        fs::write(output.path().join("notes.txt"), b"keep").unwrap();
+
+        assert!(restore_world(json_dir.path(), output.path(), false).is_err());
+        assert!(restore_world_with_config(json_dir.path(), output.path(), false, &config).is_err());
+        assert!(stale.exists());
+        assert!(!output.path().join("region/r.0.0.mca").exists());
+
+        let mut config = Config::default();
+        config.restore.existing_output = ExistingOutputPolicy::Clean;
+        restore_world_with_config(json_dir.path(), output.path(), false, &config).unwrap();
+        assert!(!stale.exists());
+        assert!(output.path().join("region/r.0.0.mca").exists());
+        assert!(output.path().join("notes.txt").exists());
+    }
+
+    #[test]
+    fn split_block_entities_are_sliced_and_legacy_names_still_restore() {
+        let world = tempfile::tempdir().unwrap();
+        let mut chunks = sample_chunks(&[(0, 0), (1, 0)]);
+        if let Value::Compound(root) = &mut chunks[1].data {
+            let chest = crate::test_util::compound([
+                ("id", Value::String("minecraft:chest".to_string())),
+                ("x", Value::Int(16)),
+                ("y", Value::Int(-60)),
+                ("z", Value::Int(0)),
+            ]);
+            root.insert("block_entities".to_string(), Value::List(vec![chest]));
+        }
+        write_world(world.path(), &chunks);
+
+        let mut config = Config::default();
+        config.export.split_block_entities = true;
+        let mut files = export_world_to_memory(world.path(), false, false, &config).unwrap();
+        let be: JsonValue = serde_json::from_str(&files["region/be/r.0.0.0.json"]).unwrap();
+        assert_eq!(be["chunks"][0]["x"], 1);
+        assert!(be["chunks"][0].to_string().contains("minecraft:chest"));
+        assert!(!files["region/r.0.0.0.json"].contains("minecraft:chest"));
+
+        // 与不拆分方块实体时的还原结果相同
+        let restore = |files: &HashMap<String, String>, config: &Config| {
+            let output = tempfile::tempdir().unwrap();
+            let path = output.path().join("world");
+            restore_world_from_memory(files, &path, false, config).unwrap();
+            output
+        };
+        let baseline =
+            export_world_to_memory(world.path(), false, false, &Config::default()).unwrap();
+        let baseline = restore(&baseline, &Config::default());
+        let same_as_baseline = |files: &HashMap<String, String>| {
+            let output = restore(files, &config);
+            compare_regions(
+                &baseline.path().join("world/region/r.0.0.mca"),
+                &output.path().join("world/region/r.0.0.mca"),
+                &[],
+            )
+            .unwrap()
+            .is_empty()
+        };
+        assert!(same_as_baseline(&files));
+
+        // 旧版本导出的文件名
+        let content = files.remove("region/be/r.0.0.0.json").unwrap();
+        files.insert("region/be/r.0.0.entities.json".to_string(), content);
+        assert!(same_as_baseline(&files));
+    }
 }
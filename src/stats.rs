@@ -0,0 +1,326 @@
+//! 世界统计 - 从已导出的区块文件（不需要 MCA 文件）或世界的 MCA 文件统计方块、实体和区块分布
+
+use crate::block_states::unpack_indices;
+use crate::config::{ChunkBounds, Config};
+use crate::denoise::{chunk_body, legacy_field_name};
+use crate::export::{build_thread_pool, dimensions};
+use crate::mca::{parse_mca_filename, read_mca, ChunkData};
+use crate::nbt_json::FieldMapper;
+use crate::restore::{collect_region_files, read_region_chunks, ChunkReadOptions};
+use anyhow::Result;
+use fastnbt::Value;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 单个维度的统计结果
+#[derive(Debug, Clone, Default)]
+pub struct DimensionStats {
+    /// 维度目录（如 `DIM-1`），主世界为空
+    pub dimension: String,
+    /// 维度名称
+    pub name: String,
+    /// 区块总数
+    pub chunks: usize,
+    /// 各类方块实体数量（按 `id`）
+    pub block_entities: BTreeMap<String, usize>,
+    /// 各类实体数量（按 `id`，来自世界的 `entities/` 或区块中的 `Entities`）
+    pub entities: BTreeMap<String, usize>,
+    /// 各类方块数量（按调色板中的方块名）
+    pub blocks: BTreeMap<String, u64>,
+    /// section 数量 -> 区块数量
+    pub sections_histogram: BTreeMap<usize, usize>,
+    /// 含有 section 的区块范围（区块坐标），没有时为 `None`
+    pub bounds: Option<ChunkBounds>,
+}
+
+impl DimensionStats {
+    /// 方块实体总数
+    pub fn block_entity_count(&self) -> usize {
+        self.block_entities.values().sum()
+    }
+
+    /// 实体总数
+    pub fn entity_count(&self) -> usize {
+        self.entities.values().sum()
+    }
+
+    /// section 总数
+    pub fn section_count(&self) -> usize {
+        self.sections_histogram
+            .iter()
+            .map(|(sections, chunks)| sections * chunks)
+            .sum()
+    }
+
+    /// 数量最多的 `n` 种方块（数量相同时按名称排序）
+    pub fn top_blocks(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut blocks: Vec<_> = self
+            .blocks
+            .iter()
+            .map(|(name, &count)| (name.as_str(), count))
+            .collect();
+        blocks.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        blocks.truncate(n);
+        blocks
+    }
+
+    /// 合并另一个统计结果
+    fn merge(&mut self, other: DimensionStats) {
+        self.chunks += other.chunks;
+        for (id, count) in other.block_entities {
+            *self.block_entities.entry(id).or_insert(0) += count;
+        }
+        for (id, count) in other.entities {
+            *self.entities.entry(id).or_insert(0) += count;
+        }
+        for (name, count) in other.blocks {
+            *self.blocks.entry(name).or_insert(0) += count;
+        }
+        for (sections, count) in other.sections_histogram {
+            *self.sections_histogram.entry(sections).or_insert(0) += count;
+        }
+        if let Some(bounds) = other.bounds {
+            self.extend_bounds(bounds);
+        }
+    }
+
+    /// 扩大区块范围以包含 `bounds`
+    fn extend_bounds(&mut self, bounds: ChunkBounds) {
+        self.bounds = Some(match self.bounds {
+            Some(b) => ChunkBounds::new(
+                b.min_x.min(bounds.min_x),
+                b.min_z.min(bounds.min_z),
+                b.max_x.max(bounds.max_x),
+                b.max_z.max(bounds.max_z),
+            ),
+            None => bounds,
+        });
+    }
+
+    /// 统计单个区块（`cx`/`cz` 为全局区块坐标）
+    ///
+    /// 1.18 之前的区块读取 `Level` 中的 `Sections` 和 `TileEntities`（旧格式的方块不计数）
+    fn add_chunk(&mut self, cx: i32, cz: i32, chunk: &Value) {
+        let Value::Compound(root) = chunk else {
+            return;
+        };
+        let (map, legacy) = chunk_body(root);
+        let name = |field| {
+            if legacy {
+                legacy_field_name(field)
+            } else {
+                field
+            }
+        };
+        self.chunks += 1;
+
+        count_ids(map.get(name("block_entities")), &mut self.block_entities);
+        // 1.17 之前实体保存在区块中
+        count_ids(map.get("Entities"), &mut self.entities);
+
+        let sections = match map.get(name("sections")) {
+            Some(Value::List(sections)) => sections.as_slice(),
+            _ => &[],
+        };
+        *self.sections_histogram.entry(sections.len()).or_insert(0) += 1;
+        if !sections.is_empty() {
+            self.extend_bounds(ChunkBounds::new(cx, cz, cx, cz));
+        }
+        for section in sections {
+            if let Value::Compound(section) = section {
+                if let Some(Value::Compound(block_states)) = section.get("block_states") {
+                    self.add_block_states(block_states);
+                }
+            }
+        }
+    }
+
+    /// 统计实体区块（世界 `entities/` 中的 MCA）中的实体
+    fn add_entity_chunk(&mut self, chunk: &Value) {
+        if let Value::Compound(map) = chunk {
+            count_ids(map.get("Entities"), &mut self.entities);
+        }
+    }
+
+    /// 按调色板和 `data` 统计 section 中的方块
+    fn add_block_states(&mut self, block_states: &HashMap<String, Value>) {
+        let Some(Value::List(palette)) = block_states.get("palette") else {
+            return;
+        };
+        let names: Vec<String> = palette
+            .iter()
+            .map(|entry| match entry {
+                Value::Compound(entry) => match entry.get("Name") {
+                    Some(Value::String(name)) => name.clone(),
+                    _ => "(unknown)".to_string(),
+                },
+                _ => "(unknown)".to_string(),
+            })
+            .collect();
+
+        let mut counts = vec![0u64; names.len()];
+        match block_states.get("data") {
+            Some(Value::LongArray(data)) => {
+                let Some(indices) = unpack_indices(data, names.len()) else {
+                    return;
+                };
+                for index in indices {
+                    if let Some(count) = counts.get_mut(index as usize) {
+                        *count += 1;
+                    }
+                }
+            }
+            // 只有一种方块时没有 data
+            _ if names.len() == 1 => counts[0] = 4096,
+            _ => return,
+        }
+
+        for (name, count) in names.into_iter().zip(counts) {
+            if count > 0 {
+                *self.blocks.entry(name).or_insert(0) += count;
+            }
+        }
+    }
+}
+
+/// 按 `id` 统计列表中的实体或方块实体
+fn count_ids(list: Option<&Value>, counts: &mut BTreeMap<String, usize>) {
+    let Some(Value::List(list)) = list else {
+        return;
+    };
+    for entry in list {
+        if let Value::Compound(entry) = entry {
+            let id = match entry.get("id") {
+                Some(Value::String(id)) => id.clone(),
+                _ => "(unknown)".to_string(),
+            };
+            *counts.entry(id).or_insert(0) += 1;
+        }
+    }
+}
+
+/// 统计导出目录中各维度的区块
+///
+/// 使用 `config.restore.format` 读取区块文件，没有区块的维度不返回
+pub fn export_stats(json_path: &Path, config: &Config) -> Result<Vec<DimensionStats>> {
+    let format = config.restore.format;
+    let field_mapper = FieldMapper::from_config(&config.field_mapping);
+    let pool = build_thread_pool(config.performance.threads)?;
+    let mut result = Vec::new();
+
+    for (dim_folder, dim_name) in &dimensions(json_path) {
+        let region_path = json_path.join(dim_folder).join("region");
+        if !region_path.exists() {
+            continue;
+        }
+        let region_files: Vec<_> = collect_region_files(&region_path, format)?
+            .into_iter()
+            .collect();
+        if region_files.is_empty() {
+            continue;
+        }
+
+        let region_stats = pool.install(|| {
+            region_files
+                .par_iter()
+                .map(|((rx, rz), files)| {
+                    let mut stats = DimensionStats::default();
+                    for ChunkData { x, z, data, .. } in read_region_chunks(
+                        (*rx, *rz),
+                        files,
+                        format,
+                        &field_mapper,
+                        ChunkReadOptions::lenient(),
+                    )?
+                    .chunks
+                    {
+                        stats.add_chunk(rx * 32 + x, rz * 32 + z, &data);
+                    }
+                    Ok(stats)
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        let mut stats = DimensionStats {
+            dimension: dim_folder.to_string(),
+            name: dim_name.to_string(),
+            ..Default::default()
+        };
+        for region in region_stats {
+            stats.merge(region);
+        }
+        result.push(stats);
+    }
+    Ok(result)
+}
+
+/// 统计世界中各维度的区块（通过 [`read_mca`] 读取 `region/` 和 `entities/` 中的 MCA 文件）
+///
+/// 只读取文件，可以在正在运行的世界上执行；没有区块和实体的维度不返回
+pub fn world_stats(world_path: &Path, config: &Config) -> Result<Vec<DimensionStats>> {
+    let pool = build_thread_pool(config.performance.threads)?;
+    let mut result = Vec::new();
+
+    for (dim_folder, dim_name) in &dimensions(world_path) {
+        let dim_path = world_path.join(dim_folder);
+        let region_files = mca_files(&dim_path.join("region"))?;
+        let entity_files = mca_files(&dim_path.join("entities"))?;
+        if region_files.is_empty() && entity_files.is_empty() {
+            continue;
+        }
+
+        let read = |files: &[(i32, i32, PathBuf)], entities: bool| {
+            pool.install(|| {
+                files
+                    .par_iter()
+                    .map(|(rx, rz, path)| {
+                        let mut stats = DimensionStats::default();
+                        for ChunkData { x, z, data, .. } in read_mca(path)? {
+                            if entities {
+                                stats.add_entity_chunk(&data);
+                            } else {
+                                stats.add_chunk(rx * 32 + x, rz * 32 + z, &data);
+                            }
+                        }
+                        Ok(stats)
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+        };
+        let region_stats = read(&region_files, false)?;
+        let entity_stats = read(&entity_files, true)?;
+
+        let mut stats = DimensionStats {
+            dimension: dim_folder.to_string(),
+            name: dim_name.to_string(),
+            ..Default::default()
+        };
+        for region in region_stats.into_iter().chain(entity_stats) {
+            stats.merge(region);
+        }
+        result.push(stats);
+    }
+    Ok(result)
+}
+
+/// 目录中的 `r.{rx}.{rz}.mca` 文件，目录不存在时为空
+fn mca_files(dir: &Path) -> Result<Vec<(i32, i32, PathBuf)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let coords = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(parse_mca_filename);
+        if let Some((rx, rz)) = coords {
+            files.push((rx, rz, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
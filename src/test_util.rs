@@ -0,0 +1,137 @@
+//! 测试用的区块和世界构造
+
+use crate::mca::{write_mca, ChunkData};
+use fastnbt::{IntArray, LongArray, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// 构造 compound
+pub(crate) fn compound<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Compound(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+/// 只由一种方块填满、生物群系为平原的 section
+pub(crate) fn uniform_section(y: i8, block: &str) -> Value {
+    compound([
+        ("Y", Value::Byte(y)),
+        (
+            "block_states",
+            compound([(
+                "palette",
+                Value::List(vec![compound([("Name", Value::String(block.to_string()))])]),
+            )]),
+        ),
+        (
+            "biomes",
+            compound([(
+                "palette",
+                Value::List(vec![Value::String("minecraft:plains".to_string())]),
+            )]),
+        ),
+        (
+            "BlockLight",
+            Value::ByteArray(fastnbt::ByteArray::new(vec![0; 2048])),
+        ),
+        (
+            "SkyLight",
+            Value::ByteArray(fastnbt::ByteArray::new(vec![15; 2048])),
+        ),
+    ])
+}
+
+/// 1.20 格式的完整区块：y = -4 为石头，y = -3 为 `cave_air`，其余 section 为空气
+pub(crate) fn sample_chunk(x: i32, z: i32) -> Value {
+    let sections = (-4i8..20)
+        .map(|y| match y {
+            -4 => uniform_section(y, "minecraft:stone"),
+            -3 => uniform_section(y, "minecraft:cave_air"),
+            _ => uniform_section(y, "minecraft:air"),
+        })
+        .collect();
+    compound([
+        ("DataVersion", Value::Int(3465)),
+        ("xPos", Value::Int(x)),
+        ("zPos", Value::Int(z)),
+        ("yPos", Value::Int(-4)),
+        ("Status", Value::String("minecraft:full".to_string())),
+        ("LastUpdate", Value::Long(1000 + (x * 32 + z) as i64)),
+        ("InhabitedTime", Value::Long(42)),
+        ("isLightOn", Value::Byte(1)),
+        ("sections", Value::List(sections)),
+        (
+            "Heightmaps",
+            compound([(
+                "MOTION_BLOCKING",
+                Value::LongArray(LongArray::new(vec![0x0101_0101_0101_0101; 37])),
+            )]),
+        ),
+        ("block_entities", Value::List(Vec::new())),
+        ("PostProcessing", Value::List(Vec::new())),
+    ])
+}
+
+/// 区块坐标 `coords` 的 [`sample_chunk`]，时间戳依次递增
+pub(crate) fn sample_chunks(coords: &[(i32, i32)]) -> Vec<ChunkData> {
+    coords
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, z))| ChunkData {
+            x,
+            z,
+            timestamp: 1_700_000_000 + i as u32,
+            data: sample_chunk(x, z),
+        })
+        .collect()
+}
+
+/// 在 `world` 中写入只有主世界 `region/r.0.0.mca` 的世界
+pub(crate) fn write_world(world: &Path, chunks: &[ChunkData]) {
+    write_region(world, "region", chunks);
+}
+
+/// 写入主世界 `folder/r.0.0.mca`（如 `entities`）
+pub(crate) fn write_region(world: &Path, folder: &str, chunks: &[ChunkData]) {
+    let dir = world.join(folder);
+    fs::create_dir_all(&dir).unwrap();
+    write_mca(&dir.join("r.0.0.mca"), chunks).unwrap();
+}
+
+/// 1.17+ `entities/` 中的实体区块：一只带乘客的牛
+pub(crate) fn sample_entity_chunk(x: i32, z: i32) -> Value {
+    let entity = |id: &str, passengers: Vec<Value>| {
+        let mut entity = compound([
+            ("id", Value::String(id.to_string())),
+            ("UUID", Value::IntArray(IntArray::new(vec![x, z, 7, 11]))),
+            (
+                "Motion",
+                Value::List(vec![
+                    Value::Double(0.01),
+                    Value::Double(-0.08),
+                    Value::Double(0.0),
+                ]),
+            ),
+            ("Air", Value::Short(300)),
+        ]);
+        if let (Value::Compound(map), false) = (&mut entity, passengers.is_empty()) {
+            map.insert("Passengers".to_string(), Value::List(passengers));
+        }
+        entity
+    };
+    compound([
+        ("DataVersion", Value::Int(3465)),
+        ("Position", Value::IntArray(IntArray::new(vec![x, z]))),
+        (
+            "Entities",
+            Value::List(vec![entity(
+                "minecraft:cow",
+                vec![entity("minecraft:chicken", Vec::new())],
+            )]),
+        ),
+    ])
+}
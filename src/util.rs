@@ -0,0 +1,202 @@
+//! 通用工具函数
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 临时文件名计数器，保证同一进程内的临时文件名不重复
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 原子地写入文件：先写入同目录下的临时文件，再重命名为目标文件
+///
+/// 进程在写入过程中被终止（或磁盘写满）时，目标文件保持原样（或不存在），不会留下只写了一半的文件；
+/// 写入失败时临时文件会被删除。不同步到磁盘，断电时仍可能丢失内容，需要时使用 [`write_atomically_synced`]
+pub fn write_atomically(path: &Path, data: impl AsRef<[u8]>) -> Result<()> {
+    write_atomically_with(path, |writer| writer.write_all(data.as_ref()))
+}
+
+/// 与 [`write_atomically`] 相同，但通过 `write` 将内容逐步写入带缓冲的临时文件，不需要先在内存中拼出完整内容
+pub fn write_atomically_with(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>,
+) -> Result<()> {
+    write_temp_and_rename(path, false, write)
+}
+
+/// 与 [`write_atomically`] 相同，但重命名前先把临时文件同步到磁盘，重命名后同步所在目录
+///
+/// 用于游戏直接读取的 region 文件和 level.dat，断电后也不会出现截断的文件
+pub fn write_atomically_synced(path: &Path, data: impl AsRef<[u8]>) -> Result<()> {
+    write_temp_and_rename(path, true, |writer| writer.write_all(data.as_ref()))
+}
+
+fn write_temp_and_rename(
+    path: &Path,
+    sync: bool,
+    write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>,
+) -> Result<()> {
+    let temp = TempFile::new(path);
+    let file = fs::File::create(&temp.path)
+        .with_context(|| format!("创建临时文件 {} 失败", temp.path.display()))?;
+    let mut writer = BufWriter::new(file);
+    write(&mut writer)
+        .and_then(|_| writer.into_inner().map_err(|e| e.into_error()))
+        .and_then(|file| if sync { file.sync_all() } else { Ok(()) })
+        .with_context(|| format!("写入临时文件 {} 失败", temp.path.display()))?;
+    // Windows 上 `fs::rename` 使用 MoveFileExW(MOVEFILE_REPLACE_EXISTING)，同样直接覆盖已有的目标文件，
+    // 不需要先删除目标；目标文件为只读或被其它进程独占打开时重命名失败，此时返回错误并保留原文件
+    fs::rename(&temp.path, path).with_context(|| format!("重命名为 {} 失败", path.display()))?;
+    temp.persist();
+    if sync {
+        sync_parent_dir(path);
+    }
+    Ok(())
+}
+
+/// 同步目标文件所在目录，保证重命名本身写入磁盘（仅 Unix，失败时忽略）
+fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// 系统临时目录下不重复的目录路径 `{prefix}_{进程 ID}_{序号}`（不创建目录）
+pub(crate) fn unique_temp_dir(prefix: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "{}_{}_{}",
+        prefix,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// 当前 UTC 时间的 ISO 8601 表示（如 `2024-01-31T08:00:00Z`）
+pub(crate) fn iso8601_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    // 由 1970-01-01 起的天数换算公历日期（Howard Hinnant 的 civil_from_days）
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+/// 目标文件旁的临时文件，离开作用域时（写入失败）自动删除
+struct TempFile {
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl TempFile {
+    /// 生成临时文件路径 `.{文件名}.{随机后缀}.tmp`（与目标文件位于同一目录，保证重命名不跨文件系统）
+    fn new(target: &Path) -> Self {
+        let name = target
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let suffix = format!(
+            "{:x}{:x}{:x}",
+            std::process::id(),
+            nanos,
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        Self {
+            path: target.with_file_name(format!(".{}.{}.tmp", name, suffix)),
+            persisted: false,
+        }
+    }
+
+    /// 临时文件已重命名为目标文件，不再删除
+    fn persist(mut self) {
+        self.persisted = true;
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 目录中的文件名（排序后）
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn failed_write_leaves_no_partial_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let target = dir.join("r.0.0.mca");
+
+        let fail_midway = |writer: &mut dyn Write| {
+            writer.write_all(&[0u8; 8192])?;
+            Err(std::io::Error::other("模拟写入失败"))
+        };
+        assert!(write_atomically_with(&target, fail_midway).is_err());
+        assert!(file_names(dir).is_empty(), "{:?}", file_names(dir));
+
+        // 已有的文件保持原样（同步和不同步两种写入方式）
+        write_atomically_synced(&target, b"old").unwrap();
+        assert!(write_atomically_with(&target, fail_midway).is_err());
+        assert!(write_temp_and_rename(&target, true, fail_midway).is_err());
+        assert_eq!(file_names(dir), ["r.0.0.mca"]);
+        assert_eq!(fs::read(&target).unwrap(), b"old");
+
+        write_atomically(&target, b"new").unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+        write_atomically_synced(&target, b"synced").unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"synced");
+
+        // 重命名失败（目标是目录）时同样删除已同步的临时文件
+        let level_dat = dir.join("level.dat");
+        fs::create_dir(&level_dat).unwrap();
+        assert!(write_atomically_synced(&level_dat, b"level").is_err());
+        assert_eq!(file_names(dir), ["level.dat", "r.0.0.mca"]);
+    }
+}
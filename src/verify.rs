@@ -0,0 +1,310 @@
+//! 往返校验 - 导出到临时目录再还原，逐区块比较原世界和还原后的世界
+
+use crate::block_states::compute_heightmaps;
+use crate::compare::compare_nbt;
+use crate::config::{Config, ExistingOutputPolicy};
+use crate::denoise::{restore_defaults_with_values, ChunkDenoiser};
+use crate::export::{
+    build_thread_pool, dimensions, export_world_with_config, exported_chunk, region_files,
+    source_key, RegionKind,
+};
+use crate::mca::{parse_region_filename, read_mca, ChunkData};
+use crate::restore::{dimension_default_values, restore_world_with_config};
+use anyhow::{Context, Result};
+use fastnbt::Value;
+use log::info;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// 每个区块的差异说明中最多列出的路径数
+const MAX_REPORTED_PATHS: usize = 3;
+
+/// 往返校验结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    /// 每个 region 的校验结果（按维度和文件名排序）
+    pub regions: Vec<RegionVerification>,
+}
+
+impl VerifyReport {
+    /// 所有 region 是否都通过校验
+    pub fn passed(&self) -> bool {
+        self.regions.iter().all(|r| r.passed())
+    }
+
+    /// 未通过校验的 region
+    pub fn failed_regions(&self) -> impl Iterator<Item = &RegionVerification> {
+        self.regions.iter().filter(|r| !r.passed())
+    }
+}
+
+/// 单个 region 的校验结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RegionVerification {
+    /// 相对世界目录的路径（如 `DIM-1/region/r.0.0.mca`、`entities/r.0.0.mca`）
+    pub file: String,
+    /// 比较的区块数（导出时跳过的区块不计）
+    pub chunks_checked: usize,
+    /// 还原结果与预期不一致的区块
+    pub mismatches: Vec<ChunkMismatch>,
+}
+
+impl RegionVerification {
+    /// 是否没有意外的差异
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// 还原结果与预期不一致的区块（全局区块坐标）
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkMismatch {
+    pub x: i32,
+    pub z: i32,
+    /// 差异说明
+    pub message: String,
+}
+
+/// 校验世界的导出 → 还原往返是否无损
+///
+/// 将世界导出到临时目录并还原为临时世界，再用 [`read_mca`] 读取两边的 `region/`、`entities/`
+/// 和 `poi/` 中的区域文件（包括 `.mcr`），用 [`compare_nbt`] 逐区块比较原区块和还原后的区块。
+/// 原区块先按导出和还原的规则得到预期结果（去噪、移除空 section 和空值、补全默认值），
+/// 还原后的区块不做任何处理。临时目录在校验结束后删除
+pub fn verify_world(
+    world_path: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+) -> Result<VerifyReport> {
+    let temp_dir = std::env::temp_dir().join(format!("mcj_verify_{}", std::process::id()));
+    let result = verify_world_in(world_path, &temp_dir, denoise, aggressive, config);
+    let _ = fs::remove_dir_all(&temp_dir);
+    result
+}
+
+fn verify_world_in(
+    world_path: &Path,
+    temp_dir: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+) -> Result<VerifyReport> {
+    // 校验完整的往返过程，不使用试运行、合并和选择性还原
+    let mut config = config.clone();
+    config.export.dry_run = false;
+    config.restore.existing_output = ExistingOutputPolicy::Fail;
+    config.restore.bounds = None;
+    config.restore.regions.clear();
+
+    let json_dir = temp_dir.join("json");
+    let restored_dir = temp_dir.join("world");
+    if temp_dir.exists() {
+        fs::remove_dir_all(temp_dir)?;
+    }
+
+    info!("步骤 1/3: 导出到 {:?}", json_dir);
+    export_world_with_config(world_path, &json_dir, denoise, aggressive, &config)?;
+    info!("步骤 2/3: 还原到 {:?}", restored_dir);
+    restore_world_with_config(
+        &json_dir,
+        &restored_dir,
+        config.restore.restore_defaults,
+        &config,
+    )?;
+    info!("步骤 3/3: 比较区块");
+
+    let mut regions = Vec::new();
+    for (dim_folder, _) in &dimensions(world_path) {
+        for kind in RegionKind::ALL {
+            let region_path = world_path.join(dim_folder).join(kind.folder());
+            if !region_path.is_dir() {
+                continue;
+            }
+            for path in region_files(&region_path)? {
+                regions.push((dim_folder.clone(), kind, path));
+            }
+        }
+    }
+
+    let pool = build_thread_pool(config.performance.threads)?;
+    let regions = pool.install(|| {
+        regions
+            .par_iter()
+            .map(|(dim_folder, kind, path)| {
+                let key = source_key(world_path, path);
+                // 还原总是写出 .mca 文件
+                let restored_path = restored_dir.join(&key).with_extension("mca");
+                verify_region(
+                    &key,
+                    dim_folder,
+                    *kind,
+                    path,
+                    &restored_path,
+                    denoise,
+                    aggressive,
+                    &config,
+                )
+                .with_context(|| format!("校验 {} 失败", key))
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+    Ok(VerifyReport { regions })
+}
+
+/// 比较单个 region 的原始区块和还原后的区块
+#[allow(clippy::too_many_arguments)]
+fn verify_region(
+    key: &str,
+    dim_folder: &str,
+    kind: RegionKind,
+    original_path: &Path,
+    restored_path: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+) -> Result<RegionVerification> {
+    let (rx, rz, _) = original_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(parse_region_filename)
+        .context("无效的区域文件名")?;
+    let dimension = (!dim_folder.is_empty()).then_some(dim_folder);
+    let denoiser = denoise
+        .then(|| ChunkDenoiser::new(&config.denoise, dimension))
+        .transpose()?;
+    let bounds = config.export.bounds;
+    let terrain = kind == RegionKind::Chunks;
+    // 还原时补全的默认值只属于方块区块
+    let defaults = if terrain && config.restore.restore_defaults {
+        dimension_default_values(&config.restore.default_values()?, config, dimension)
+    } else {
+        Vec::new()
+    };
+
+    let by_pos = |chunks: Vec<ChunkData>| -> BTreeMap<(i32, i32), Value> {
+        chunks
+            .into_iter()
+            .map(|ChunkData { x, z, data, .. }| ((rx * 32 + x, rz * 32 + z), data))
+            .collect()
+    };
+    let original = by_pos(read_mca(original_path)?);
+    let mut restored = if restored_path.exists() {
+        by_pos(read_mca(restored_path)?)
+    } else {
+        BTreeMap::new()
+    };
+
+    let mut chunks_checked = 0;
+    let mut mismatches = Vec::new();
+    let mut mismatch = |(x, z): (i32, i32), message: String| {
+        mismatches.push(ChunkMismatch { x, z, message });
+    };
+
+    for (pos, data) in &original {
+        // 区块范围之外和导出时跳过的区块不应出现在还原结果中
+        let expected = if bounds.is_some_and(|b| !b.contains(pos.0, pos.1)) {
+            None
+        } else {
+            exported_chunk(data, kind, denoiser.as_ref(), aggressive, config)
+        };
+        let actual = restored.remove(pos);
+        match (expected, actual) {
+            (None, None) => continue,
+            (None, Some(_)) => mismatch(*pos, "导出时应跳过的区块出现在还原结果中".to_string()),
+            (Some(_), None) => mismatch(*pos, "还原结果中缺少该区块".to_string()),
+            (Some((mut expected, noise)), Some(actual)) => {
+                if terrain && config.restore.compute_heightmaps {
+                    compute_heightmaps(&mut expected);
+                }
+                // noise 文件中有记录的区块按原值还原，不恢复默认值
+                if !noise {
+                    restore_defaults_with_values(&mut expected, &defaults);
+                }
+                let differences = compare_nbt(&expected, &actual, &[]);
+                if !differences.is_empty() {
+                    let paths: Vec<String> = differences
+                        .iter()
+                        .take(MAX_REPORTED_PATHS)
+                        .map(|d| d.to_string())
+                        .collect();
+                    let more = if differences.len() > MAX_REPORTED_PATHS {
+                        format!(" 等 {} 处", differences.len())
+                    } else {
+                        String::new()
+                    };
+                    mismatch(*pos, format!("内容不同: {}{}", paths.join("; "), more));
+                }
+            }
+        }
+        chunks_checked += 1;
+    }
+    for pos in restored.into_keys() {
+        mismatch(pos, "还原结果中多出原世界没有的区块".to_string());
+    }
+    Ok(RegionVerification {
+        file: key.to_string(),
+        chunks_checked,
+        mismatches,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{sample_chunks, sample_entity_chunk, write_region, write_world};
+
+    #[test]
+    fn sample_world_round_trips() {
+        let world = tempfile::tempdir().unwrap();
+        write_world(world.path(), &sample_chunks(&[(0, 0), (1, 0), (5, 7)]));
+        let entities = vec![ChunkData {
+            x: 0,
+            z: 0,
+            timestamp: 1_700_000_000,
+            data: sample_entity_chunk(0, 0),
+        }];
+        write_region(world.path(), "entities", &entities);
+
+        let temp = tempfile::tempdir().unwrap();
+        let report =
+            verify_world_in(world.path(), temp.path(), true, false, &Config::default()).unwrap();
+        let files: Vec<&str> = report.regions.iter().map(|r| r.file.as_str()).collect();
+        assert_eq!(files, ["region/r.0.0.mca", "entities/r.0.0.mca"]);
+        assert!(report.passed(), "{:?}", report);
+    }
+
+    #[test]
+    fn changed_value_is_reported_with_its_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original/r.0.0.mca");
+        let restored = dir.path().join("restored/r.0.0.mca");
+        let mut chunks = sample_chunks(&[(0, 0)]);
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::create_dir_all(restored.parent().unwrap()).unwrap();
+        crate::mca::write_mca(&original, &chunks).unwrap();
+        if let Value::Compound(root) = &mut chunks[0].data {
+            root.insert("DataVersion".to_string(), Value::Int(3700));
+        }
+        crate::mca::write_mca(&restored, &chunks).unwrap();
+
+        let config = Config::default();
+        let result = verify_region(
+            "region/r.0.0.mca",
+            "",
+            RegionKind::Chunks,
+            &original,
+            &restored,
+            false,
+            false,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(result.chunks_checked, 1);
+        assert_eq!(result.mismatches.len(), 1);
+        assert!(result.mismatches[0].message.contains("DataVersion"));
+    }
+}
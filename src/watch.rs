@@ -0,0 +1,185 @@
+//! 监视模式 - 轮询世界目录，重新导出被游戏保存过的 region
+
+use crate::config::Config;
+use crate::export::{
+    build_thread_pool, dimensions, export_mca_with_config, export_world_incremental,
+    remove_region_slices, source_key, RegionReport,
+};
+use crate::filter::AndFilter;
+use crate::incremental::{source_state, IncrementalManifest};
+use crate::nbt_json::FieldMapper;
+use anyhow::Result;
+use log::{error, info, warn};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// region 导出失败（如文件正被游戏写入）时的最大重试次数
+const MAX_RETRIES: usize = 3;
+
+/// 两次重试之间的等待时间
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// 检查停止标志的间隔
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 已知 region 文件的状态：维度目录、修改时间和大小
+type RegionStamps = BTreeMap<PathBuf, (String, SystemTime, u64)>;
+
+/// 监视世界目录，持续导出有变化的 region，直到 `stop` 被设置
+///
+/// 启动时先执行一次增量导出；之后每隔 `interval` 比较各 `.mca` 文件的修改时间和大小，
+/// 只重新导出发生变化的文件，并同步更新增量导出清单（之后可直接使用 `--incremental`）
+pub fn watch_world(
+    world_path: &Path,
+    output_path: &Path,
+    denoise: bool,
+    aggressive: bool,
+    config: &Config,
+    interval: Duration,
+    stop: &AtomicBool,
+) -> Result<()> {
+    // 文件可能正被游戏写入，映射的文件被截断时会导致进程崩溃
+    let mut config = config.clone();
+    config.export.use_mmap = false;
+    let config = &config;
+
+    export_world_incremental(world_path, output_path, denoise, aggressive, config, None)?;
+    let mut known = scan_regions(world_path)?;
+    let field_mapper = FieldMapper::from_config(&config.field_mapping);
+    let filter = AndFilter::from_config(&config.export);
+    let pool = build_thread_pool(config.performance.threads)?;
+
+    info!(
+        "监视 {}（每 {} 秒检查一次，Ctrl-C 停止）",
+        world_path.display(),
+        interval.as_secs()
+    );
+    while wait(interval, stop) {
+        let current = scan_regions(world_path)?;
+        let removed: Vec<PathBuf> = known
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .cloned()
+            .collect();
+        let changed: Vec<_> = current
+            .iter()
+            .filter(|(path, stamp)| known.get(*path) != Some(stamp))
+            .map(|(path, stamp)| (path.clone(), stamp.clone()))
+            .collect();
+        if removed.is_empty() && changed.is_empty() {
+            continue;
+        }
+
+        let mut manifest = IncrementalManifest::load(output_path);
+        for mca_path in removed {
+            let key = source_key(world_path, &mca_path);
+            let output_mca = output_path.join(&key);
+            if let Some(region_output) = output_mca.parent() {
+                remove_region_slices(region_output, &output_mca)?;
+            }
+            manifest.regions.remove(&key);
+            known.remove(&mca_path);
+            info!("  移除 {}", key);
+        }
+
+        for (mca_path, stamp) in changed {
+            let key = source_key(world_path, &mca_path);
+            let region_output = output_path.join(&stamp.0).join("region");
+            let dimension = (!stamp.0.is_empty()).then_some(stamp.0.as_str());
+            let result = with_retry(&key, || {
+                remove_region_slices(&region_output, &mca_path)?;
+                pool.install(|| {
+                    export_mca_with_config(
+                        &mca_path,
+                        &region_output,
+                        dimension,
+                        denoise,
+                        aggressive,
+                        &config.denoise,
+                        &config.export,
+                        &field_mapper,
+                        &filter,
+                    )
+                })
+            });
+            match result {
+                Ok(report) => {
+                    info!("  更新 {}（{} 个区块）", key, report.chunks_exported);
+                    manifest.regions.insert(key, source_state(&mca_path, None)?);
+                    known.insert(mca_path, stamp);
+                }
+                // 保留旧状态，下一轮继续尝试
+                Err(e) => error!("  失败 {}: {}", key, e),
+            }
+        }
+        manifest.save(output_path)?;
+    }
+
+    info!("停止监视");
+    Ok(())
+}
+
+/// 读取所有维度中 `.mca` 文件的修改时间和大小
+fn scan_regions(world_path: &Path) -> Result<RegionStamps> {
+    let mut stamps = BTreeMap::new();
+    for (dim_folder, _) in &dimensions(world_path) {
+        let region_path = world_path.join(dim_folder).join("region");
+        if !region_path.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&region_path)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "mca") {
+                continue;
+            }
+            // 文件可能在扫描过程中被删除或替换
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            stamps.insert(
+                path,
+                (dim_folder.clone(), metadata.modified()?, metadata.len()),
+            );
+        }
+    }
+    Ok(stamps)
+}
+
+/// 失败时等待 1 秒后重试，最多重试 [`MAX_RETRIES`] 次
+fn with_retry(key: &str, export: impl Fn() -> Result<RegionReport>) -> Result<RegionReport> {
+    let mut retries = 0;
+    loop {
+        match export() {
+            Ok(report) => return Ok(report),
+            Err(e) if retries < MAX_RETRIES => {
+                retries += 1;
+                warn!(
+                    "{} 导出失败（{}），{} 秒后重试（{}/{}）",
+                    key,
+                    e,
+                    RETRY_DELAY.as_secs(),
+                    retries,
+                    MAX_RETRIES
+                );
+                thread::sleep(RETRY_DELAY);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 等待 `interval`，期间 `stop` 被设置时返回 false
+fn wait(interval: Duration, stop: &AtomicBool) -> bool {
+    let deadline = Instant::now() + interval;
+    while Instant::now() < deadline {
+        if stop.load(Ordering::Relaxed) {
+            return false;
+        }
+        thread::sleep(STOP_CHECK_INTERVAL.min(deadline - Instant::now()));
+    }
+    !stop.load(Ordering::Relaxed)
+}
@@ -0,0 +1,95 @@
+//! 按区块访问世界 - 按需读取 region 文件并缓存最近使用的 region
+
+use crate::export::region_files;
+use crate::mca::{parse_region_filename, read_mca, ChunkData};
+use anyhow::{Context, Result};
+use lru::LruCache;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 默认缓存的 region 数量
+pub const DEFAULT_REGION_CACHE_SIZE: usize = 16;
+
+/// 逐区块读取世界
+///
+/// 打开时只列出 region 文件，读取区块时才解析对应的 region 文件，
+/// 并在内存中缓存最近使用的若干个 region（每个 region 最多 1024 个区块）
+pub struct WorldReader {
+    regions: HashMap<(i32, i32), PathBuf>,
+    cache: Mutex<LruCache<(i32, i32), Vec<ChunkData>>>,
+}
+
+impl WorldReader {
+    /// 打开世界的主世界（`region/`），缓存 [`DEFAULT_REGION_CACHE_SIZE`] 个 region
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::open_with_capacity(path, DEFAULT_REGION_CACHE_SIZE)
+    }
+
+    /// 打开世界的主世界，最多缓存 `capacity` 个 region（至少为 1）
+    pub fn open_with_capacity(path: &Path, capacity: usize) -> Result<Self> {
+        Self::open_dimension(path, "", capacity)
+    }
+
+    /// 打开世界中的某个维度（如 `DIM-1`、`dimensions/mymod/mining`，主世界为空），
+    /// 最多缓存 `capacity` 个 region（至少为 1）
+    pub fn open_dimension(path: &Path, dimension: &str, capacity: usize) -> Result<Self> {
+        let region_path = path.join(dimension).join("region");
+        let mut regions = HashMap::new();
+        if region_path.is_dir() {
+            for file in region_files(&region_path)
+                .with_context(|| format!("无法读取 {}", region_path.display()))?
+            {
+                let filename = file.file_name().unwrap().to_str().unwrap();
+                if let Some((rx, rz, _)) = parse_region_filename(filename) {
+                    regions.insert((rx, rz), file);
+                }
+            }
+        } else if !path.is_dir() {
+            anyhow::bail!("世界目录不存在: {}", path.display());
+        }
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Ok(Self {
+            regions,
+            cache: Mutex::new(LruCache::new(capacity)),
+        })
+    }
+
+    /// 读取区块坐标为 (cx, cz) 的区块（方块坐标右移 4 位），区块或 region 不存在时返回 `None`
+    ///
+    /// 返回的 [`ChunkData`] 中 `x`、`z` 为 region 内坐标
+    pub fn chunk_at(&self, cx: i32, cz: i32) -> Result<Option<ChunkData>> {
+        let (x, z) = (cx & 31, cz & 31);
+        self.with_region(cx >> 5, cz >> 5, |chunks| {
+            chunks.iter().find(|c| c.x == x && c.z == z).cloned()
+        })
+    }
+
+    /// 世界中所有 region 的坐标 (rx, rz)，已排序
+    pub fn region_coords(&self) -> Vec<(i32, i32)> {
+        let mut coords: Vec<_> = self.regions.keys().copied().collect();
+        coords.sort();
+        coords
+    }
+
+    /// 读取 region (rx, rz) 中的所有区块，region 不存在时返回空列表
+    pub fn chunks_in_region(&self, rx: i32, rz: i32) -> Result<Vec<ChunkData>> {
+        self.with_region(rx, rz, |chunks| chunks.to_vec())
+    }
+
+    /// 在缓存的 region 区块上执行 `f`，未缓存时先读取 region 文件
+    fn with_region<T>(&self, rx: i32, rz: i32, f: impl FnOnce(&[ChunkData]) -> T) -> Result<T> {
+        let Some(path) = self.regions.get(&(rx, rz)) else {
+            return Ok(f(&[]));
+        };
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(chunks) = cache.get(&(rx, rz)) {
+            return Ok(f(chunks));
+        }
+        let chunks = read_mca(path).with_context(|| format!("无法读取 {}", path.display()))?;
+        let result = f(&chunks);
+        cache.put((rx, rz), chunks);
+        Ok(result)
+    }
+}